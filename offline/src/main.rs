@@ -0,0 +1,372 @@
+// 离线重放 hid.dll 抓取的快照:
+//   dying-light-offline replay <snapshot.dlsnap>
+//   dying-light-offline ptrscan <snapshot.dlsnap> <目标地址> [深度] [最大偏移]
+//   dying-light-offline exports <snapshot.dlsnap> [过滤]
+//   dying-light-offline script <snapshot.dlsnap> <脚本.rhai>...
+
+#![allow(dead_code)]
+
+#[path = "../../src/bone_probe.rs"]
+mod bone_probe;
+#[path = "../../src/clock.rs"]
+mod clock;
+#[path = "../../src/draw.rs"]
+mod draw;
+#[path = "../../src/esp.rs"]
+mod esp;
+#[path = "../../src/exports.rs"]
+mod exports;
+#[path = "../../src/freecam.rs"]
+mod freecam;
+#[path = "../../src/health.rs"]
+mod health;
+#[path = "../../src/items.rs"]
+mod items;
+#[path = "../../src/label.rs"]
+mod label;
+#[path = "../../src/math.rs"]
+mod math;
+#[path = "../../src/offsets.rs"]
+mod offsets;
+#[path = "../../src/plugin.rs"]
+mod plugin;
+#[path = "../../src/pointer_scan.rs"]
+mod pointer_scan;
+#[path = "../../src/radar.rs"]
+mod radar;
+#[path = "../../src/script.rs"]
+mod script;
+#[path = "../../src/skeleton.rs"]
+mod skeleton;
+#[path = "../../src/snapshot.rs"]
+mod snapshot;
+#[path = "../../src/speed.rs"]
+mod speed;
+#[path = "../../src/teleport.rs"]
+mod teleport;
+#[path = "../../src/trail.rs"]
+mod trail;
+#[path = "../../src/visibility.rs"]
+mod visibility;
+#[path = "../../src/vitals.rs"]
+mod vitals;
+#[path = "../../src/waypoint.rs"]
+mod waypoint;
+#[path = "../../src/world.rs"]
+mod world;
+
+use draw::Recorder;
+use esp::EspFlags;
+use health::HealthTracker;
+use math::{Camera, Vec2, Vec3};
+use offsets::Offsets;
+use pointer_scan::ScanConfig;
+use script::{ScriptFrame, ScriptHost};
+use snapshot::{Memory, Snapshot};
+use std::{collections::BTreeMap, process::exit};
+use world::{Array, ModelObject, World};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        Some("replay") if args.len() == 3 => replay(&load(&args[2])),
+        Some("ptrscan") if args.len() >= 4 => ptrscan(&load(&args[2]), &args[3..]),
+        Some("exports") if args.len() >= 3 => exports(&load(&args[2]), args.get(3)),
+        Some("script") if args.len() >= 4 => run_scripts(&load(&args[2]), &args[3..]),
+        _ => {
+            eprintln!("用法:");
+            eprintln!("  dying-light-offline replay <snapshot.dlsnap>");
+            eprintln!(
+                "  dying-light-offline ptrscan <snapshot.dlsnap> <目标地址> [深度] [最大偏移]"
+            );
+            eprintln!("  dying-light-offline exports <snapshot.dlsnap> [过滤]");
+            eprintln!("  dying-light-offline script <snapshot.dlsnap> <脚本.rhai>...");
+            exit(2);
+        }
+    }
+}
+
+fn load(path: &str) -> Snapshot {
+    let result = std::fs::File::open(path)
+        .and_then(|file| Snapshot::read_from(&mut std::io::BufReader::new(file)));
+
+    match result {
+        Ok(val) => val,
+        Err(err) => {
+            eprintln!("读取 {} 失败: {}", path, err);
+            exit(1);
+        }
+    }
+}
+
+fn root(snapshot: &Snapshot, name: &str) -> usize {
+    match snapshot.root(name) {
+        Some(val) => val,
+        None => {
+            eprintln!("快照里没有 {}", name);
+            exit(1);
+        }
+    }
+}
+
+// 快照里没有相机位置，用玩家位置和视角代替，屏幕按 1920x1080、垂直视角 60 度算
+fn replay_camera(snapshot: &Snapshot, world: &World) -> (Vec3<f32>, Camera) {
+    let player_pos = snapshot
+        .read::<Vec3<f32>>(world.player_world_pos_p as usize)
+        .unwrap_or_default();
+    let angle = snapshot
+        .read::<Vec2<f32>>(world.camera_angle_p as usize)
+        .unwrap_or_default();
+
+    let camera = Camera {
+        pos: player_pos,
+        yaw: angle.x,
+        pitch: angle.y,
+        fov: 60.0,
+        width: 1920.0,
+        height: 1080.0,
+    };
+
+    (player_pos, camera)
+}
+
+fn replay(snapshot: &Snapshot) {
+    let mut offsets = Offsets::new();
+    offsets.apply(|name| snapshot.root(&format!("offset:{}", name)));
+
+    let world = match world::resolve_world(snapshot, &offsets, root(snapshot, "c_game_pp")) {
+        Some(val) => val,
+        None => {
+            eprintln!("World 链解析失败");
+            exit(1);
+        }
+    };
+
+    println!("{:#?}", world);
+
+    let array_p = root(snapshot, "model_obj_array");
+    let array: Array<*const ModelObject> = Array {
+        ptr: snapshot.read_ptr(array_p).unwrap_or_default() as *const _,
+        len: snapshot.read::<u32>(array_p + 8).unwrap_or_default(),
+        max: snapshot.read::<u32>(array_p + 12).unwrap_or_default(),
+    };
+
+    let (player_pos, camera) = replay_camera(snapshot, &world);
+
+    // 和游戏里一样走 esp::draw_entity，画出来的命令记在 Recorder 里打印
+    let flags = EspFlags {
+        dot: true,
+        health_bar: true,
+        ..Default::default()
+    };
+    let mut recorder = Recorder::default();
+    let mut health_tracker = HealthTracker::default();
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+    for model_obj_p in world::model_obj_addrs(snapshot, &offsets, &array) {
+        let obj = match world::decode_obj(snapshot, &offsets, model_obj_p) {
+            Some(val) => val,
+            None => {
+                if let Some(item) = world::decode_item(snapshot, &offsets, model_obj_p) {
+                    println!(
+                        "{:#014X}  {}  logo={:#X}  pos=({:.2}, {:.2}, {:.2})  {}",
+                        model_obj_p,
+                        item.category,
+                        item.logo,
+                        item.world_pos.x,
+                        item.world_pos.y,
+                        item.world_pos.z,
+                        item.preset,
+                    );
+
+                    *counts.entry(item.category.to_string()).or_default() += 1;
+                }
+
+                continue;
+            }
+        };
+
+        if obj.c_model_obj_p == world.player_c_model_obj_p {
+            continue;
+        }
+
+        println!(
+            "{:#014X}  {}  logo={:#X}  pos=({:.2}, {:.2}, {:.2})  hp={:.1}  {}",
+            model_obj_p,
+            obj.model_obj_type,
+            snapshot
+                .read::<u32>(obj.c_model_obj_logo_p as usize)
+                .unwrap_or_default(),
+            obj.c_model_obj_world_pos.x,
+            obj.c_model_obj_world_pos.y,
+            obj.c_model_obj_world_pos.z,
+            snapshot
+                .read::<f32>(obj.model_obj_health_p as usize)
+                .unwrap_or_default(),
+            obj.model_obj_str,
+        );
+
+        let health = health_tracker.update(
+            model_obj_p,
+            snapshot
+                .read::<f32>(obj.model_obj_health_p as usize)
+                .unwrap_or_default(),
+            0.0,
+        );
+
+        esp::draw_basic_entity(
+            &mut recorder,
+            &flags,
+            &camera,
+            &obj,
+            player_pos,
+            Some(health),
+            [1.0, 1.0, 1.0, 1.0],
+            100.0,
+        );
+
+        for cmd in recorder.take() {
+            println!("    {:?}", cmd);
+        }
+
+        *counts.entry(obj.model_obj_type.to_string()).or_default() += 1;
+    }
+
+    println!();
+    for (model_obj_type, count) in counts {
+        println!("{}: {}", model_obj_type, count);
+    }
+}
+
+fn ptrscan(snapshot: &Snapshot, args: &[String]) {
+    let target = match pointer_scan::parse_hex(&args[0]) {
+        Some(val) => val,
+        None => {
+            eprintln!("目标地址无效: {}", args[0]);
+            exit(2);
+        }
+    };
+
+    let mut config = ScanConfig::default();
+    if let Some(val) = args.get(1).and_then(|arg| arg.parse().ok()) {
+        config.max_depth = val;
+    }
+    if let Some(val) = args.get(2).and_then(|arg| pointer_scan::parse_hex(arg)) {
+        config.max_offset = val;
+    }
+
+    for path in pointer_scan::scan(snapshot, target, &config) {
+        println!("{}", path);
+    }
+}
+
+// 只有抓到了 engine_x64_rwdi.dll 的 PE 头和导出表时才有结果
+fn exports(snapshot: &Snapshot, filter: Option<&String>) {
+    let module = match snapshot
+        .modules
+        .iter()
+        .find(|module| module.name.eq_ignore_ascii_case("engine_x64_rwdi.dll"))
+    {
+        Some(val) => val,
+        None => {
+            eprintln!("快照里没有 engine_x64_rwdi.dll");
+            exit(1);
+        }
+    };
+
+    let exports = match exports::read_exports(snapshot, module.base) {
+        Some(val) => val,
+        None => {
+            eprintln!("读取导出表失败");
+            exit(1);
+        }
+    };
+
+    let filter = filter.map(|val| val.to_lowercase()).unwrap_or_default();
+
+    for export in &exports {
+        if !export.name.to_lowercase().contains(&filter) {
+            continue;
+        }
+
+        match &export.shape {
+            Some(shape) => println!(
+                "{:#010X}  {}  {}",
+                export.addr - module.base,
+                export.name,
+                shape
+            ),
+            None => println!("{:#010X}  {}", export.addr - module.base, export.name),
+        }
+    }
+}
+
+// 用快照里的实体跑一帧脚本，打印画出来的命令和 log
+// 离线拿不到引擎的相机，用玩家位置和视角凑一个 1920x1080 的
+fn run_scripts(snapshot: &Snapshot, paths: &[String]) {
+    let mut offsets = Offsets::new();
+    offsets.apply(|name| snapshot.root(&format!("offset:{}", name)));
+
+    let world = match world::resolve_world(snapshot, &offsets, root(snapshot, "c_game_pp")) {
+        Some(val) => val,
+        None => {
+            eprintln!("World 链解析失败");
+            exit(1);
+        }
+    };
+
+    let array_p = root(snapshot, "model_obj_array");
+    let array: Array<*const ModelObject> = Array {
+        ptr: snapshot.read_ptr(array_p).unwrap_or_default() as *const _,
+        len: snapshot.read::<u32>(array_p + 8).unwrap_or_default(),
+        max: snapshot.read::<u32>(array_p + 12).unwrap_or_default(),
+    };
+
+    let (player_pos, camera) = replay_camera(snapshot, &world);
+
+    let frame = ScriptFrame {
+        time: 0.0,
+        player_pos,
+        camera,
+        entities: script::collect_entities(
+            snapshot,
+            &offsets,
+            &world::model_obj_addrs(snapshot, &offsets, &array),
+            player_pos,
+            world.player_c_model_obj_p as usize,
+        ),
+        settings: Default::default(),
+    };
+
+    let mut host = ScriptHost::new();
+    for path in paths {
+        match std::fs::read_to_string(path) {
+            Ok(source) => host.load_source(path, &source),
+            Err(err) => {
+                eprintln!("读取 {} 失败: {}", path, err);
+                exit(1);
+            }
+        }
+    }
+
+    let mut recorder = Recorder::default();
+    host.run_frame(&frame, &mut recorder);
+
+    for cmd in recorder.take() {
+        println!("{:?}", cmd);
+    }
+
+    for script in &host.scripts {
+        for log in &script.logs {
+            println!("[{}] {}", script.name, log);
+        }
+        for (name, val) in &script.settings {
+            println!("[{}] 设置 {} = {}", script.name, name, val);
+        }
+        if let Some(err) = &script.error {
+            println!("[{}] 错误: {}", script.name, err);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// 插件接口，和 src/plugin.rs 保持一致
+//
+// 把编译好的 DLL 放到游戏目录的 plugins 文件夹里，启动时加载，菜单 "插件" 页可以重新加载
+// DLL 要导出:
+//   uint32_t trainer_plugin_abi_version(void);               返回 TRAINER_PLUGIN_ABI_VERSION
+//   const PluginApi *trainer_plugin_init(const HostApi *host); 失败时返回 NULL
+//
+// 版本不一致时不会调用 trainer_plugin_init
+// 字符串都是 UTF-8，以 0 结尾；颜色是 rgba，0.0 ~ 1.0
+
+#pragma once
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#define TRAINER_PLUGIN_ABI_VERSION 1
+
+// HostApi 里的 entity 返回的实体
+typedef struct PluginEntity {
+    uint64_t addr;
+    // 0 ZombieNormal  1 ZombieSpecial  2 ZombieHunter
+    // 3 SurvivorNormal  4 SurvivorSpecial  5 SurvivorShopkeeper
+    // 6 PlayerHuman  7 PlayerHunter  8 Other
+    uint32_t model_type;
+    float pos[3];
+    float health;
+    float distance;
+} PluginEntity;
+
+// 第一个参数都传 HostApi.host
+// 以后只会在末尾加函数，用之前先看 size 够不够
+typedef struct HostApi {
+    uint32_t abi_version;
+    uint32_t size;
+    void *host;
+
+    // 这一帧的实体快照
+    double (*frame_time)(void *host);
+    void (*player_pos)(void *host, float out[3]);
+    uint32_t (*entity_count)(void *host);
+    bool (*entity)(void *host, uint32_t index, PluginEntity *out);
+    // 在相机后面时返回 false
+    bool (*world_to_screen)(void *host, const float pos[3], float out[2]);
+
+    // 绘制，只在 on_frame 里有效
+    void (*draw_text)(void *host, float x, float y, const float color[4], const char *text);
+    void (*draw_line)(void *host, float x1, float y1, float x2, float y2, const float color[4],
+                      float thickness);
+    void (*draw_circle)(void *host, float x, float y, float radius, const float color[4],
+                        float thickness, bool filled);
+    void (*draw_rect)(void *host, float x1, float y1, float x2, float y2, const float color[4],
+                      float thickness, bool filled);
+
+    // 第一次调用时登记设置并返回默认值，之后返回菜单里改过的值
+    bool (*setting_bool)(void *host, const char *name, bool default_value);
+    float (*setting_float)(void *host, const char *name, float default_value);
+
+    void (*log)(void *host, const char *text);
+
+    // 菜单控件，只在 on_ui 里有效
+    void (*ui_text)(void *host, const char *text);
+    bool (*ui_checkbox)(void *host, const char *label, bool *value);
+    bool (*ui_button)(void *host, const char *label);
+    bool (*ui_slider_float)(void *host, const char *label, float min, float max, float *value);
+} HostApi;
+
+// trainer_plugin_init 返回，插件卸载前要一直有效
+typedef struct PluginApi {
+    uint32_t abi_version;
+    // sizeof(PluginApi)
+    uint32_t size;
+    const char *name;
+    // 每帧调用，返回非 0 表示出错，插件会被关掉
+    int32_t (*on_frame)(const HostApi *host);
+    // 插件自己的菜单页，可以为 NULL
+    void (*on_ui)(const HostApi *host);
+    // 卸载前调用，可以为 NULL
+    void (*shutdown)(void);
+} PluginApi;
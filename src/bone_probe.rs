@@ -0,0 +1,250 @@
+use crate::{
+    draw::{Draw, Layer},
+    label::{Label, Side},
+    math::{Vec2, Vec3},
+    skeleton::Rig,
+    world::ModelType,
+};
+
+// 坐标差在这个距离以内的两个索引算同一个骨骼
+const SAME_POS_EPSILON: f32 = 0.001;
+
+const COLOR_OK: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+const COLOR_DUPLICATE: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ProbeState {
+    Ok,
+    // get_bone_joint_pos 没写坐标，多半是索引超出了骨架
+    Zero,
+    // 和前面某个索引的坐标一样
+    Duplicate(u8),
+}
+
+impl std::fmt::Display for ProbeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeState::Ok => write!(f, "正常"),
+            ProbeState::Zero => write!(f, "零坐标"),
+            ProbeState::Duplicate(index) => write!(f, "同 {}", index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProbePoint {
+    pub(crate) index: u8,
+    pub(crate) world_pos: Vec3<f32>,
+    pub(crate) screen_pos: Option<[f32; 2]>,
+    pub(crate) state: ProbeState,
+}
+
+// positions 按骨骼索引排列，从 0 开始
+pub(crate) fn classify(positions: &[Vec3<f32>]) -> Vec<ProbeState> {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(index, pos)| {
+            if pos.is_zero() {
+                return ProbeState::Zero;
+            }
+
+            match positions[..index]
+                .iter()
+                .position(|other| (*other - *pos).length() < SAME_POS_EPSILON)
+            {
+                Some(other) => ProbeState::Duplicate(other as u8),
+                None => ProbeState::Ok,
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn draw_probe<D: Draw>(draw: &mut D, points: &[ProbePoint]) {
+    for point in points {
+        let Some(screen_pos) = point.screen_pos else {
+            continue;
+        };
+
+        let color = match point.state {
+            ProbeState::Duplicate(_) => COLOR_DUPLICATE,
+            _ => COLOR_OK,
+        };
+
+        draw.circle(Layer::Background, screen_pos, 3.0, color, 1.0, true);
+    }
+}
+
+// 每个索引一个标签，交给 label::layout 挤开，measure 返回文字的宽高
+pub(crate) fn probe_labels(
+    points: &[ProbePoint],
+    names: &[String],
+    measure: impl Fn(&str) -> [f32; 2],
+) -> Vec<Label> {
+    let mut labels = Vec::new();
+
+    for point in points {
+        let Some(screen_pos) = point.screen_pos else {
+            continue;
+        };
+
+        let name = names
+            .get(point.index as usize)
+            .map(|val| val.trim())
+            .unwrap_or_default();
+
+        let (text, color) = match point.state {
+            ProbeState::Duplicate(other) => (format!("{}={}", point.index, other), COLOR_DUPLICATE),
+            _ if name.is_empty() => (point.index.to_string(), COLOR_OK),
+            _ => (format!("{} {}", point.index, name), COLOR_OK),
+        };
+
+        let mut label = Label::new(
+            Vec2::new(screen_pos[0], screen_pos[1] - 3.0),
+            Side::Above,
+            color,
+        );
+        let size = measure(&text);
+        label.push(text, size);
+
+        labels.push(label);
+    }
+
+    labels
+}
+
+// 只包含起了名字的索引，骨骼链需要自己在骨架文件里补
+pub(crate) fn to_rig(name: &str, names: &[String]) -> Rig {
+    let mut rig = Rig::new(name);
+
+    for (index, bone_name) in names.iter().enumerate().take(u8::MAX as usize + 1) {
+        // 骨架文件按空白分隔，名字不能带空格，也不能是纯数字
+        let bone_name: String = bone_name
+            .trim()
+            .chars()
+            .map(|val| if val.is_whitespace() { '_' } else { val })
+            .collect();
+
+        if bone_name.is_empty() || bone_name.parse::<u8>().is_ok() {
+            continue;
+        }
+
+        rig.bones.push((index as u8, bone_name));
+    }
+
+    rig
+}
+
+// 同名的骨架只换骨骼名，保留手写的骨骼链、样式和已经绑定的分类
+// 没有骨骼链的骨架什么都画不出来，绑定了反而会盖掉默认骨架，所以有骨骼链时才绑定 bind_type
+// 返回保存的骨架有没有骨骼链
+pub(crate) fn merge_rig(rigs: &mut Vec<Rig>, new_rig: Rig, bind_type: Option<ModelType>) -> bool {
+    let rig = match rigs.iter().position(|val| val.name == new_rig.name) {
+        Some(index) => {
+            rigs[index].bones = new_rig.bones;
+            &mut rigs[index]
+        }
+        None => {
+            rigs.push(new_rig);
+            rigs.last_mut().unwrap()
+        }
+    };
+
+    if rig.chains.is_empty() {
+        return false;
+    }
+
+    if let Some(model_type) = bind_type
+        && !rig.types.contains(&model_type)
+    {
+        rig.types.push(model_type);
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skeleton::Skeletons;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|val| val.to_string()).collect()
+    }
+
+    #[test]
+    fn classify_marks_zero_and_duplicates() {
+        let positions = [
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::default(),
+            Vec3::new(4.0, 5.0, 6.0),
+            Vec3::new(1.0, 2.0, 3.0005),
+            Vec3::new(4.0, 5.0, 6.0),
+            Vec3::default(),
+        ];
+
+        assert_eq!(
+            classify(&positions),
+            vec![
+                ProbeState::Ok,
+                ProbeState::Zero,
+                ProbeState::Ok,
+                ProbeState::Duplicate(0),
+                ProbeState::Duplicate(2),
+                ProbeState::Zero,
+            ]
+        );
+    }
+
+    #[test]
+    fn to_rig_keeps_only_usable_names() {
+        let rig = to_rig("probe", &names(&["Pelvis", "", " 12 ", "Left Hand", "  "]));
+
+        assert_eq!(
+            rig.bones,
+            vec![(0, "Pelvis".to_string()), (3, "Left_Hand".to_string())]
+        );
+        assert!(rig.chains.is_empty());
+    }
+
+    #[test]
+    fn saved_rig_parses_back() {
+        let rig = to_rig("probe", &names(&["Pelvis", "Spine", "", "Head"]));
+
+        let skeletons = Skeletons::parse(&rig.to_string()).unwrap();
+        assert_eq!(skeletons.rigs, vec![rig]);
+    }
+
+    #[test]
+    fn merge_keeps_chains_and_binds_only_with_chains() {
+        let mut rigs = Skeletons::builtin().rigs;
+        let chains = rigs[0].chains.clone();
+
+        let new_rig = to_rig("humanoid", &names(&["Root"]));
+        assert!(merge_rig(&mut rigs, new_rig, Some(ModelType::Other)));
+        assert_eq!(rigs.len(), 1);
+        assert_eq!(rigs[0].bones, vec![(0, "Root".to_string())]);
+        assert_eq!(rigs[0].chains, chains);
+        assert_eq!(rigs[0].types.last(), Some(&ModelType::Other));
+
+        // 再绑一次不会重复
+        let new_rig = to_rig("humanoid", &names(&["Root"]));
+        merge_rig(&mut rigs, new_rig, Some(ModelType::Other));
+        let count = rigs[0]
+            .types
+            .iter()
+            .filter(|val| **val == ModelType::Other)
+            .count();
+        assert_eq!(count, 1);
+
+        // 新骨架没有骨骼链，不绑定
+        let new_rig = to_rig("probe", &names(&["Root"]));
+        assert!(!merge_rig(
+            &mut rigs,
+            new_rig,
+            Some(ModelType::ZombieNormal)
+        ));
+        assert_eq!(rigs.len(), 2);
+        assert!(rigs[1].types.is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+use crate::exports::{Export, ExportShape, ReturnType, ScalarType, find_method};
+
+// 游戏里的时间按小时算，0.0 ~ 24.0
+const DAY_HOURS: f32 = 24.0;
+
+// 入夜和天亮的默认时间是照游戏里的表现估的，没有出处，
+// 导出表里有 ILevel 的 IsNight 之类的函数时用它翻转的时刻替换
+const NIGHT_START: f32 = 21.0;
+const NIGHT_END: f32 = 5.0;
+
+// 测时间流速时两次采样至少隔多久，单位秒
+const RATE_SAMPLE_TIME: f64 = 2.0;
+
+pub(crate) fn wrap_hour(hour: f32) -> f32 {
+    hour.rem_euclid(DAY_HOURS)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NightRange {
+    pub(crate) start: f32,
+    pub(crate) end: f32,
+    // 是引擎翻转时记下的，不是默认值
+    pub(crate) start_seen: bool,
+    pub(crate) end_seen: bool,
+    last_night: Option<bool>,
+}
+
+impl Default for NightRange {
+    fn default() -> Self {
+        Self {
+            start: NIGHT_START,
+            end: NIGHT_END,
+            start_seen: false,
+            end_seen: false,
+            last_night: None,
+        }
+    }
+}
+
+impl NightRange {
+    pub(crate) fn is_night(&self, hour: f32) -> bool {
+        let hour = wrap_hour(hour);
+
+        if self.end <= self.start {
+            !(self.end..self.start).contains(&hour)
+        } else {
+            (self.start..self.end).contains(&hour)
+        }
+    }
+
+    // 离入夜还有几个游戏小时
+    pub(crate) fn hours_until_night(&self, hour: f32) -> f32 {
+        wrap_hour(self.start - wrap_hour(hour))
+    }
+
+    // 离天亮还有几个游戏小时
+    pub(crate) fn hours_until_day(&self, hour: f32) -> f32 {
+        wrap_hour(self.end - wrap_hour(hour))
+    }
+
+    // night 是引擎说的是不是晚上，和上一次不一样时记下这个时刻
+    pub(crate) fn observe(&mut self, hour: f32, night: bool) {
+        match (self.last_night, night) {
+            (Some(false), true) => {
+                self.start = wrap_hour(hour);
+                self.start_seen = true;
+            }
+            (Some(true), false) => {
+                self.end = wrap_hour(hour);
+                self.end_seen = true;
+            }
+            _ => (),
+        }
+
+        self.last_night = Some(night);
+    }
+
+    // 手动改时间后的那次翻转不是真的入夜/天亮
+    pub(crate) fn skip_jump(&mut self) {
+        self.last_night = None;
+    }
+}
+
+// ILevel 上和时间有关的导出没确认过修饰名，按签名和名字在导出表里认
+// 必须有成对的 float GetX() 和 void SetX(float)，X 一样，这样读到的原值和写回去的是同一个东西
+// 时间: X 里有 Time 和 Day 或 Hour
+// 流速: X 里有 Day 和 Speed、Scale 或 Rate，SetTimeScale 之类的全局流速不算
+pub(crate) fn is_speed_stem(stem: &str) -> bool {
+    stem.contains("Day")
+        && ["Speed", "Scale", "Rate"]
+            .iter()
+            .any(|val| stem.contains(val))
+}
+
+pub(crate) fn is_time_stem(stem: &str) -> bool {
+    stem.contains("Time") && (stem.contains("Day") || stem.contains("Hour")) && !is_speed_stem(stem)
+}
+
+fn getter_stem(shape: &ExportShape) -> Option<&str> {
+    if shape.class != "ILevel"
+        || shape.arg.is_some()
+        || shape.ret != ReturnType::Scalar(ScalarType::F32)
+    {
+        return None;
+    }
+
+    shape.method.strip_prefix("Get")
+}
+
+fn setter_stem(shape: &ExportShape) -> Option<&str> {
+    if shape.class != "ILevel"
+        || shape.arg != Some(ScalarType::F32)
+        || shape.ret != ReturnType::Void
+    {
+        return None;
+    }
+
+    shape.method.strip_prefix("Set")
+}
+
+// 返回 [GetX, SetX]，有多对时取名字最短的
+pub(crate) fn find_pair(
+    exports: &[Export],
+    is_stem: fn(&str) -> bool,
+) -> Option<[(&Export, &ExportShape); 2]> {
+    let getter = |stem: &str| find_method(exports, |shape| getter_stem(shape) == Some(stem));
+
+    let setter = find_method(exports, |shape| {
+        setter_stem(shape).is_some_and(|stem| is_stem(stem) && getter(stem).is_some())
+    })?;
+
+    Some([getter(setter_stem(setter.1)?)?, setter])
+}
+
+// bool IsNight() 之类
+pub(crate) fn is_night_getter(shape: &ExportShape) -> bool {
+    shape.class == "ILevel"
+        && shape.method.contains("Night")
+        && shape.arg.is_none()
+        && shape.ret == ReturnType::Scalar(ScalarType::Bool)
+}
+
+// "HH:MM"
+pub(crate) fn format_hour(hour: f32) -> String {
+    let minutes = (wrap_hour(hour) * 60.0) as u32 % (DAY_HOURS as u32 * 60);
+
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+// "HH:MM" 或者小时数 "21.5"
+pub(crate) fn parse_hour(text: &str) -> Option<f32> {
+    let text = text.trim();
+
+    let hour = match text.split_once(':') {
+        Some((hour, minute)) => {
+            let hour: u32 = hour.trim().parse().ok()?;
+            let minute: u32 = minute.trim().parse().ok()?;
+            if minute >= 60 {
+                return None;
+            }
+
+            hour as f32 + minute as f32 / 60.0
+        }
+        None => text.parse().ok()?,
+    };
+
+    if !(0.0..DAY_HOURS).contains(&hour) {
+        return None;
+    }
+
+    Some(hour)
+}
+
+// "1分30秒"
+pub(crate) fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+
+    if seconds >= 3600 {
+        format!("{}时{}分", seconds / 3600, seconds % 3600 / 60)
+    } else if seconds >= 60 {
+        format!("{}分{}秒", seconds / 60, seconds % 60)
+    } else {
+        format!("{}秒", seconds)
+    }
+}
+
+// 按实际经过的时间算游戏时间走得多快，用来把倒计时换成现实时间
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ClockRate {
+    last: Option<(f64, f32)>,
+    // 每现实秒走多少游戏小时
+    hours_per_second: Option<f32>,
+}
+
+impl ClockRate {
+    pub(crate) fn sample(&mut self, now: f64, hour: f32) {
+        let Some((last_time, last_hour)) = self.last else {
+            self.last = Some((now, hour));
+            return;
+        };
+
+        let elapsed = now - last_time;
+        if elapsed < RATE_SAMPLE_TIME {
+            return;
+        }
+
+        // 过了午夜会从 24 回到 0
+        let delta = wrap_hour(hour - last_hour);
+        self.hours_per_second = Some(delta / elapsed as f32);
+        self.last = Some((now, hour));
+    }
+
+    // 改了时间以后旧的采样不能用了
+    pub(crate) fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn hours_per_second(&self) -> Option<f32> {
+        self.hours_per_second
+    }
+
+    // 游戏小时换成现实秒，时间停着时返回 None
+    pub(crate) fn real_seconds(&self, hours: f32) -> Option<f64> {
+        let rate = self.hours_per_second?;
+        if rate <= 0.0 {
+            return None;
+        }
+
+        Some((hours / rate) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exports::parse_shape;
+
+    fn shape(mangled: &str) -> ExportShape {
+        parse_shape(mangled).unwrap()
+    }
+
+    #[test]
+    fn default_night_wraps_midnight() {
+        let range = NightRange::default();
+
+        assert!(range.is_night(22.0));
+        assert!(range.is_night(2.0));
+        assert!(!range.is_night(12.0));
+        assert_eq!(range.hours_until_night(19.0), 2.0);
+        assert_eq!(range.hours_until_day(23.0), 6.0);
+    }
+
+    #[test]
+    fn observe_learns_flips_but_not_jumps() {
+        let mut range = NightRange::default();
+
+        range.observe(20.0, false);
+        range.observe(20.5, true);
+        assert_eq!(range.start, 20.5);
+        assert!(range.start_seen);
+
+        // 手动跳到中午，这次翻转不算天亮
+        range.skip_jump();
+        range.observe(12.0, false);
+        assert!(!range.end_seen);
+
+        range.observe(20.6, true);
+        range.observe(6.0, true);
+        range.observe(6.1, false);
+        assert_eq!(range.end, 6.1);
+        assert!(range.end_seen);
+        assert!(range.is_night(21.0));
+        assert!(!range.is_night(6.5));
+    }
+
+    fn names<'a>(pair: Option<[(&'a Export, &'a ExportShape); 2]>) -> Option<[&'a str; 2]> {
+        pair.map(|[getter, setter]| [getter.1.method.as_str(), setter.1.method.as_str()])
+    }
+
+    #[test]
+    fn time_exports_bound_as_get_set_pairs() {
+        let exports: Vec<Export> = [
+            "?GetTimeScale@ILevel@@QEBAMXZ",
+            "?SetTimeScale@ILevel@@QEAAXM@Z",
+            "?GetTimeOfDay@ILevel@@QEBAMXZ",
+            "?SetTimeOfDay@ILevel@@QEAAXM@Z",
+            "?GetTimeOfDayHour@IGame@@QEBAMXZ",
+            "?SetTimeOfDayHour@IGame@@QEAAXM@Z",
+            "?GetDayTimeSpeed@ILevel@@QEBAMXZ",
+            "?SetDayCycleSpeed@ILevel@@QEAAXM@Z",
+        ]
+        .iter()
+        .map(|name| Export {
+            name: name.to_string(),
+            addr: 0x1000,
+            shape: parse_shape(name),
+        })
+        .collect();
+
+        assert_eq!(
+            names(find_pair(&exports, is_time_stem)),
+            Some(["GetTimeOfDay", "SetTimeOfDay"])
+        );
+
+        // SetTimeScale 是全局流速，GetDayTimeSpeed / SetDayCycleSpeed 不是一对
+        assert_eq!(names(find_pair(&exports, is_speed_stem)), None);
+
+        let mut exports = exports;
+        for name in [
+            "?GetTimeOfDaySpeed@ILevel@@QEBAMXZ",
+            "?SetTimeOfDaySpeed@ILevel@@QEAAXM@Z",
+        ] {
+            exports.push(Export {
+                name: name.to_string(),
+                addr: 0x2000,
+                shape: parse_shape(name),
+            });
+        }
+
+        assert_eq!(
+            names(find_pair(&exports, is_speed_stem)),
+            Some(["GetTimeOfDaySpeed", "SetTimeOfDaySpeed"])
+        );
+        assert_eq!(
+            names(find_pair(&exports, is_time_stem)),
+            Some(["GetTimeOfDay", "SetTimeOfDay"])
+        );
+    }
+
+    #[test]
+    fn night_export_matched_by_signature() {
+        assert!(is_night_getter(&shape("?IsNight@ILevel@@QEBA_NXZ")));
+        assert!(!is_night_getter(&shape("?IsNight@IGame@@QEBA_NXZ")));
+        assert!(!is_night_getter(&shape("?IsNight@ILevel@@QEBAMXZ")));
+    }
+}
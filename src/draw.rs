@@ -0,0 +1,209 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Layer {
+    #[default]
+    Background,
+    // 菜单窗口之上
+    #[allow(dead_code)]
+    Foreground,
+    // 当前正在构建的 imgui 窗口，比如雷达
+    Window,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DrawCmd {
+    Text {
+        layer: Layer,
+        pos: [f32; 2],
+        color: [f32; 4],
+        text: String,
+    },
+    Line {
+        layer: Layer,
+        from: [f32; 2],
+        to: [f32; 2],
+        color: [f32; 4],
+        thickness: f32,
+    },
+    Circle {
+        layer: Layer,
+        center: [f32; 2],
+        radius: f32,
+        color: [f32; 4],
+        thickness: f32,
+        filled: bool,
+    },
+    Rect {
+        layer: Layer,
+        min: [f32; 2],
+        max: [f32; 2],
+        color: [f32; 4],
+        thickness: f32,
+        filled: bool,
+    },
+    Polyline {
+        layer: Layer,
+        points: Vec<[f32; 2]>,
+        color: [f32; 4],
+        thickness: f32,
+        closed: bool,
+    },
+}
+
+impl DrawCmd {
+    pub(crate) fn layer(&self) -> Layer {
+        match self {
+            DrawCmd::Text { layer, .. }
+            | DrawCmd::Line { layer, .. }
+            | DrawCmd::Circle { layer, .. }
+            | DrawCmd::Rect { layer, .. }
+            | DrawCmd::Polyline { layer, .. } => *layer,
+        }
+    }
+}
+
+// ESP 只通过这个接口画图，游戏里是 imgui，离线时用 Recorder 记下来
+pub(crate) trait Draw {
+    fn submit(&mut self, cmd: DrawCmd);
+
+    fn text(&mut self, layer: Layer, pos: [f32; 2], color: [f32; 4], text: String) {
+        self.submit(DrawCmd::Text {
+            layer,
+            pos,
+            color,
+            text,
+        });
+    }
+
+    fn line(
+        &mut self,
+        layer: Layer,
+        from: [f32; 2],
+        to: [f32; 2],
+        color: [f32; 4],
+        thickness: f32,
+    ) {
+        self.submit(DrawCmd::Line {
+            layer,
+            from,
+            to,
+            color,
+            thickness,
+        });
+    }
+
+    fn circle(
+        &mut self,
+        layer: Layer,
+        center: [f32; 2],
+        radius: f32,
+        color: [f32; 4],
+        thickness: f32,
+        filled: bool,
+    ) {
+        self.submit(DrawCmd::Circle {
+            layer,
+            center,
+            radius,
+            color,
+            thickness,
+            filled,
+        });
+    }
+
+    fn rect(
+        &mut self,
+        layer: Layer,
+        min: [f32; 2],
+        max: [f32; 2],
+        color: [f32; 4],
+        thickness: f32,
+        filled: bool,
+    ) {
+        self.submit(DrawCmd::Rect {
+            layer,
+            min,
+            max,
+            color,
+            thickness,
+            filled,
+        });
+    }
+
+    fn polyline(
+        &mut self,
+        layer: Layer,
+        points: Vec<[f32; 2]>,
+        color: [f32; 4],
+        thickness: f32,
+        closed: bool,
+    ) {
+        if points.len() < 2 {
+            return;
+        }
+
+        self.submit(DrawCmd::Polyline {
+            layer,
+            points,
+            color,
+            thickness,
+            closed,
+        });
+    }
+}
+
+// 只记录命令，不画，用来在 Linux 上检查某个实体会画出什么
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Recorder {
+    pub(crate) cmds: Vec<DrawCmd>,
+}
+
+#[allow(dead_code)]
+impl Recorder {
+    pub(crate) fn take(&mut self) -> Vec<DrawCmd> {
+        std::mem::take(&mut self.cmds)
+    }
+
+    pub(crate) fn count(&self, layer: Layer) -> usize {
+        self.cmds.iter().filter(|cmd| cmd.layer() == layer).count()
+    }
+}
+
+impl Draw for Recorder {
+    fn submit(&mut self, cmd: DrawCmd) {
+        self.cmds.push(cmd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorder_keeps_layers_and_order() {
+        let mut recorder = Recorder::default();
+
+        recorder.text(Layer::Background, [1.0, 2.0], [1.0; 4], "a".to_string());
+        recorder.line(Layer::Window, [0.0, 0.0], [3.0, 4.0], [1.0; 4], 2.0);
+        recorder.circle(Layer::Foreground, [5.0, 6.0], 7.0, [1.0; 4], 1.0, true);
+        // 少于两个点的折线不画
+        recorder.polyline(Layer::Background, vec![[0.0, 0.0]], [1.0; 4], 1.0, false);
+
+        assert_eq!(recorder.count(Layer::Background), 1);
+        assert_eq!(recorder.count(Layer::Window), 1);
+        assert_eq!(recorder.count(Layer::Foreground), 1);
+
+        let cmds = recorder.take();
+        assert_eq!(
+            cmds[1],
+            DrawCmd::Line {
+                layer: Layer::Window,
+                from: [0.0, 0.0],
+                to: [3.0, 4.0],
+                color: [1.0; 4],
+                thickness: 2.0,
+            }
+        );
+        assert!(recorder.cmds.is_empty());
+    }
+}
@@ -0,0 +1,791 @@
+use crate::{
+    draw::{Draw, Layer},
+    health::Health,
+    label::{LabelAnchor, Side},
+    math::{Camera, Vec2, Vec3},
+    world::Obj,
+};
+
+// 箭头离屏幕边缘的距离
+const ARROW_MARGIN: f32 = 48.0;
+const ARROW_MIN_SIZE: f32 = 8.0;
+const ARROW_MAX_SIZE: f32 = 22.0;
+
+// 3D 方框在骨骼外面留的空隙，头顶留多一点
+const BOX_PADDING: f32 = 0.15;
+const BOX_HEAD_PADDING: f32 = 0.3;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum BoxMode {
+    #[default]
+    Off,
+    Box2D,
+    Corners,
+    Box3D,
+}
+
+impl std::fmt::Display for BoxMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoxMode::Off => write!(f, "关闭"),
+            BoxMode::Box2D => write!(f, "2D 方框"),
+            BoxMode::Corners => write!(f, "四角"),
+            BoxMode::Box3D => write!(f, "3D 方框"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct EspFlags {
+    pub(crate) model_type_name: bool,
+    pub(crate) bones: bool,
+    pub(crate) visible_line: bool,
+    pub(crate) type_data: bool,
+    pub(crate) logo: bool,
+    pub(crate) model_obj_p: bool,
+    pub(crate) box_mode: BoxMode,
+    pub(crate) box_thickness: f32,
+    // 0 时不填充
+    pub(crate) box_fill_alpha: f32,
+    pub(crate) health_bar: bool,
+    pub(crate) health_text: bool,
+    // 远处只画一个点
+    pub(crate) dot: bool,
+    // 骨骼、方框和文字按可见性换颜色
+    pub(crate) visibility: bool,
+    pub(crate) visible_color: [f32; 4],
+    pub(crate) occluded_color: [f32; 4],
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum Lod {
+    #[default]
+    Near,
+    Mid,
+    Far,
+}
+
+impl EspFlags {
+    // 中距离不画骨骼，远距离只留标签和一个点，也就省掉了 GetBoneJointPos 调用
+    pub(crate) fn with_lod(mut self, lod: Lod) -> Self {
+        match lod {
+            Lod::Near => (),
+            Lod::Mid => {
+                self.bones = false;
+                self.visible_line = false;
+            }
+            Lod::Far => {
+                self.bones = false;
+                self.visible_line = false;
+                self.box_mode = BoxMode::Off;
+                self.health_bar = false;
+                self.dot = true;
+            }
+        }
+
+        self
+    }
+}
+
+pub(crate) fn lod_for(distance: f32, near: f32, mid: f32) -> Lod {
+    if distance <= near {
+        Lod::Near
+    } else if distance <= mid {
+        Lod::Mid
+    } else {
+        Lod::Far
+    }
+}
+
+// 从 max_distance * fade_start 开始线性淡出，到 max_distance 时完全透明
+pub(crate) fn fade_alpha(distance: f32, max_distance: f32, fade_start: f32) -> f32 {
+    let start = max_distance * fade_start.clamp(0.0, 1.0);
+    if distance <= start {
+        return 1.0;
+    }
+
+    if distance >= max_distance {
+        return 0.0;
+    }
+
+    1.0 - (distance - start) / (max_distance - start)
+}
+
+// 一个实体在这一帧画图需要的全部数据，屏幕坐标已经投影好
+#[derive(Debug, Clone)]
+pub(crate) struct EspEntity<'a> {
+    pub(crate) obj: &'a Obj,
+    pub(crate) color: [f32; 4],
+    pub(crate) screen_pos: [f32; 2],
+    pub(crate) distance: f32,
+    pub(crate) logo: u32,
+    // 每条骨骼链投影后的点
+    pub(crate) bones: Vec<Vec<[f32; 2]>>,
+    // 来自骨架定义
+    pub(crate) bone_thickness: f32,
+    pub(crate) bone_joints: bool,
+    // 还没检测过是 None
+    pub(crate) visible: Option<bool>,
+    // 逐骨骼检测时和 bones 一一对应，否则为空
+    pub(crate) bone_visible: Vec<Vec<bool>>,
+    // 屏幕底部中间 -> 可见的骨骼
+    pub(crate) visible_line: Option<([f32; 2], [f32; 2])>,
+    // 骨骼投影后的范围 (左上, 右下)
+    pub(crate) box_2d: Option<([f32; 2], [f32; 2])>,
+    // oriented_box 的 8 个角投影后的坐标
+    pub(crate) box_3d: Option<[[f32; 2]; 8]>,
+    pub(crate) health: Option<Health>,
+    // 头部骨骼投影后的坐标，标签锚点选头顶时才有
+    pub(crate) head_pos: Option<[f32; 2]>,
+}
+
+// 实体的文字，一行一个，交给 label::layout 排版
+pub(crate) fn entity_lines(flags: &EspFlags, entity: &EspEntity) -> Vec<String> {
+    let obj = entity.obj;
+
+    let mut lines = Vec::new();
+
+    let health_text = match entity.health {
+        Some(health) if flags.health_text => {
+            Some(format!("{:.0}/{:.0}", health.current, health.max))
+        }
+        _ => None,
+    };
+
+    if flags.model_type_name {
+        let mut line = format!("{}  {:.2}", obj.model_obj_type, entity.distance);
+        if let Some(text) = &health_text {
+            line.push_str("  ");
+            line.push_str(text);
+        }
+
+        lines.push(line);
+    } else if let Some(text) = health_text {
+        lines.push(text);
+    }
+
+    if flags.type_data {
+        lines.push(obj.model_obj_str.clone());
+    }
+
+    if flags.logo {
+        lines.push(format!("{:#X?}", entity.logo));
+    }
+
+    if flags.model_obj_p {
+        lines.push(format!("model_obj_p: {:p}", obj.model_obj_p));
+        lines.push(format!("c_model_obj_p: {:p}", obj.c_model_obj_p));
+    }
+
+    lines
+}
+
+pub(crate) fn label_anchor(entity: &EspEntity, anchor: LabelAnchor) -> (Vec2<f32>, Side) {
+    let head = entity.head_pos.unwrap_or(entity.screen_pos);
+
+    let top = match (entity.box_2d, entity.box_3d) {
+        (Some(bounds), _) => Some(padded_bounds(bounds)),
+        (None, Some(corners)) => screen_bounds(&corners),
+        (None, None) => None,
+    };
+
+    match anchor {
+        LabelAnchor::Head => (Vec2::new(head[0], head[1]), Side::Above),
+        LabelAnchor::Feet => (
+            Vec2::new(entity.screen_pos[0], entity.screen_pos[1]),
+            Side::Below,
+        ),
+        LabelAnchor::BoxTop => match top {
+            Some((min, max)) => (Vec2::new((min[0] + max[0]) / 2.0, min[1]), Side::Above),
+            None => (Vec2::new(head[0], head[1]), Side::Above),
+        },
+    }
+}
+
+// 透明度沿用实体颜色的，距离淡出时一起变淡
+fn visibility_color(flags: &EspFlags, entity: &EspEntity, visible: bool) -> [f32; 4] {
+    let color = if visible {
+        flags.visible_color
+    } else {
+        flags.occluded_color
+    };
+
+    with_alpha(color, entity.color[3])
+}
+
+// 骨骼、方框和文字用的颜色
+pub(crate) fn state_color(flags: &EspFlags, entity: &EspEntity) -> [f32; 4] {
+    match entity.visible {
+        Some(visible) if flags.visibility => visibility_color(flags, entity, visible),
+        _ => entity.color,
+    }
+}
+
+// 文字不在这里画，见 entity_lines
+pub(crate) fn draw_entity<D: Draw>(draw: &mut D, flags: &EspFlags, entity: &EspEntity) {
+    if flags.dot {
+        draw.circle(
+            Layer::Background,
+            entity.screen_pos,
+            3.0,
+            entity.color,
+            1.0,
+            true,
+        );
+    }
+
+    draw_box(draw, flags, entity);
+
+    if flags.health_bar
+        && let Some(health) = entity.health
+    {
+        draw_health_bar(draw, entity, &health);
+    }
+
+    if flags.bones {
+        let color = state_color(flags, entity);
+
+        for (i, bone_list) in entity.bones.iter().enumerate() {
+            let visible = match entity.bone_visible.get(i) {
+                Some(val) if flags.visibility => val,
+                _ => {
+                    draw.polyline(
+                        Layer::Background,
+                        bone_list.clone(),
+                        color,
+                        entity.bone_thickness,
+                        false,
+                    );
+                    continue;
+                }
+            };
+
+            // 两端都可见的骨骼才算可见
+            for (j, points) in bone_list.windows(2).enumerate() {
+                draw.line(
+                    Layer::Background,
+                    points[0],
+                    points[1],
+                    visibility_color(flags, entity, visible[j] && visible[j + 1]),
+                    entity.bone_thickness,
+                );
+            }
+        }
+
+        if entity.bone_joints {
+            // 链之间共用的关节只画一次
+            let mut joints: Vec<[f32; 2]> = Vec::new();
+
+            for point in entity.bones.iter().flatten() {
+                if joints.contains(point) {
+                    continue;
+                }
+
+                joints.push(*point);
+                draw.circle(
+                    Layer::Background,
+                    *point,
+                    entity.bone_thickness + 1.0,
+                    color,
+                    1.0,
+                    true,
+                );
+            }
+        }
+    }
+
+    if flags.visible_line
+        && let Some((from, to)) = entity.visible_line
+    {
+        draw.line(Layer::Background, from, to, entity.color, 2.0);
+    }
+}
+
+// 只用内存里读得到的数据画一个实体，屏幕里画 draw_entity，屏幕外画箭头，返回是否在屏幕里
+// 骨骼、方框和可见性要调引擎，这里不填；离线重放用它，和游戏里走同一套画法
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_basic_entity<D: Draw>(
+    draw: &mut D,
+    flags: &EspFlags,
+    camera: &Camera,
+    obj: &Obj,
+    player_pos: Vec3<f32>,
+    health: Option<Health>,
+    color: [f32; 4],
+    arrow_range: f32,
+) -> bool {
+    let pos = obj.c_model_obj_world_pos;
+    let distance = (pos - player_pos).length();
+
+    let screen_pos = camera.world_to_screen(pos).screen().filter(|val| {
+        val.x >= 0.0 && val.x <= camera.width && val.y >= 0.0 && val.y <= camera.height
+    });
+
+    let screen_pos = match screen_pos {
+        Some(val) => val,
+        None => {
+            draw_offscreen_arrow(
+                draw,
+                camera,
+                camera.screen_direction(pos),
+                distance,
+                arrow_range,
+                color,
+            );
+            return false;
+        }
+    };
+
+    let entity = EspEntity {
+        obj,
+        color,
+        screen_pos: screen_pos.to_array(),
+        distance,
+        logo: 0,
+        bones: Vec::new(),
+        bone_thickness: 1.5,
+        bone_joints: false,
+        visible: None,
+        bone_visible: Vec::new(),
+        visible_line: None,
+        box_2d: None,
+        box_3d: None,
+        health,
+        head_pos: None,
+    };
+
+    draw_entity(draw, flags, &entity);
+
+    true
+}
+
+// 在屏幕边缘沿 direction 画一个箭头，越近越大，超出 max_range 不画
+pub(crate) fn draw_offscreen_arrow<D: Draw>(
+    draw: &mut D,
+    camera: &Camera,
+    direction: Vec2<f32>,
+    distance: f32,
+    max_range: f32,
+    color: [f32; 4],
+) {
+    if direction == Vec2::default() || distance > max_range {
+        return;
+    }
+
+    let center = camera.center();
+    let half_x = (center.x - ARROW_MARGIN).max(0.0);
+    let half_y = (center.y - ARROW_MARGIN).max(0.0);
+
+    // 从屏幕中心沿方向走到内缩后的矩形边上
+    let scale_x = if direction.x != 0.0 {
+        half_x / direction.x.abs()
+    } else {
+        f32::MAX
+    };
+    let scale_y = if direction.y != 0.0 {
+        half_y / direction.y.abs()
+    } else {
+        f32::MAX
+    };
+    let pos = center + direction * scale_x.min(scale_y);
+
+    let closeness = 1.0 - (distance / max_range).clamp(0.0, 1.0);
+    let size = ARROW_MIN_SIZE + (ARROW_MAX_SIZE - ARROW_MIN_SIZE) * closeness;
+
+    let side = Vec2::new(-direction.y, direction.x);
+    let tip = pos + direction * size;
+    let back = pos - direction * (size * 0.5);
+
+    draw.polyline(
+        Layer::Background,
+        vec![
+            tip.to_array(),
+            (back + side * (size * 0.6)).to_array(),
+            (back - side * (size * 0.6)).to_array(),
+        ],
+        color,
+        2.0,
+        true,
+    );
+
+    let label = back - direction * (size + 8.0);
+    draw.text(
+        Layer::Background,
+        [label.x - 16.0, label.y - 8.0],
+        color,
+        format!("{:.0}", distance),
+    );
+}
+
+pub(crate) fn screen_bounds(points: &[[f32; 2]]) -> Option<([f32; 2], [f32; 2])> {
+    let first = points.first()?;
+
+    let mut min = *first;
+    let mut max = *first;
+
+    for point in points {
+        min = [min[0].min(point[0]), min[1].min(point[1])];
+        max = [max[0].max(point[0]), max[1].max(point[1])];
+    }
+
+    Some((min, max))
+}
+
+// 以 right 的水平方向为 x 轴、竖直向上为 y 轴，把骨骼包起来的盒子
+// 第 i 个角: bit0 = x 取最大, bit1 = y 取最大, bit2 = z 取最大
+pub(crate) fn oriented_box(bones: &[Vec3<f32>], right: Vec3<f32>) -> Option<[Vec3<f32>; 8]> {
+    if bones.is_empty() {
+        return None;
+    }
+
+    let up = Vec3::new(0.0, 1.0, 0.0);
+
+    let mut right = Vec3::new(right.x, 0.0, right.z).normalize();
+    if right.is_zero() {
+        right = Vec3::new(1.0, 0.0, 0.0);
+    }
+    let forward = up.cross(right);
+
+    let axes = [right, up, forward];
+
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+
+    for bone in bones {
+        for (i, axis) in axes.iter().enumerate() {
+            let val = bone.dot(*axis);
+            min[i] = min[i].min(val);
+            max[i] = max[i].max(val);
+        }
+    }
+
+    for i in 0..3 {
+        min[i] -= BOX_PADDING;
+        max[i] += if i == 1 {
+            BOX_HEAD_PADDING
+        } else {
+            BOX_PADDING
+        };
+    }
+
+    let mut corners = [Vec3::default(); 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let pick = |axis: usize| {
+            if i & (1 << axis) != 0 {
+                max[axis]
+            } else {
+                min[axis]
+            }
+        };
+
+        *corner = right * pick(0) + up * pick(1) + forward * pick(2);
+    }
+
+    Some(corners)
+}
+
+// 骨骼点是关节中心，向外扩一点，头顶多扩一些
+fn padded_bounds((min, max): ([f32; 2], [f32; 2])) -> ([f32; 2], [f32; 2]) {
+    let pad = (max[1] - min[1]) * 0.08;
+
+    (
+        [min[0] - pad, min[1] - pad * 2.0],
+        [max[0] + pad, max[1] + pad],
+    )
+}
+
+fn with_alpha(color: [f32; 4], alpha: f32) -> [f32; 4] {
+    [color[0], color[1], color[2], color[3] * alpha]
+}
+
+fn draw_box<D: Draw>(draw: &mut D, flags: &EspFlags, entity: &EspEntity) {
+    let color = state_color(flags, entity);
+    let thickness = flags.box_thickness;
+
+    match flags.box_mode {
+        BoxMode::Off => (),
+        BoxMode::Box2D | BoxMode::Corners => {
+            let (min, max) = match entity.box_2d {
+                Some(val) => padded_bounds(val),
+                None => return,
+            };
+
+            if flags.box_fill_alpha > 0.0 {
+                draw.rect(
+                    Layer::Background,
+                    min,
+                    max,
+                    with_alpha(color, flags.box_fill_alpha),
+                    thickness,
+                    true,
+                );
+            }
+
+            if flags.box_mode == BoxMode::Box2D {
+                draw.rect(Layer::Background, min, max, color, thickness, false);
+                return;
+            }
+
+            let len = ((max[0] - min[0]).min(max[1] - min[1]) / 4.0).max(2.0);
+
+            for (x, dir_x) in [(min[0], 1.0), (max[0], -1.0)] {
+                for (y, dir_y) in [(min[1], 1.0), (max[1], -1.0)] {
+                    draw.line(
+                        Layer::Background,
+                        [x, y],
+                        [x + len * dir_x, y],
+                        color,
+                        thickness,
+                    );
+                    draw.line(
+                        Layer::Background,
+                        [x, y],
+                        [x, y + len * dir_y],
+                        color,
+                        thickness,
+                    );
+                }
+            }
+        }
+        BoxMode::Box3D => {
+            let corners = match entity.box_3d {
+                Some(val) => val,
+                None => return,
+            };
+
+            if flags.box_fill_alpha > 0.0
+                && let Some((min, max)) = screen_bounds(&corners)
+            {
+                draw.rect(
+                    Layer::Background,
+                    min,
+                    max,
+                    with_alpha(color, flags.box_fill_alpha),
+                    thickness,
+                    true,
+                );
+            }
+
+            // 12 条边，每条连接只差一个 bit 的两个角
+            for i in 0..8 {
+                for bit in [1, 2, 4] {
+                    if i & bit == 0 {
+                        draw.line(
+                            Layer::Background,
+                            corners[i],
+                            corners[i | bit],
+                            color,
+                            thickness,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 有 2D 方框时竖着画在方框左边，否则横着画在实体位置上方
+fn draw_health_bar<D: Draw>(draw: &mut D, entity: &EspEntity, health: &Health) {
+    const BAR_WIDTH: f32 = 4.0;
+    const BAR_LENGTH: f32 = 40.0;
+
+    let background = [0.0, 0.0, 0.0, 0.6];
+    let fraction = health.fraction();
+
+    let (back_min, back_max, fill_min) = match entity.box_2d.map(padded_bounds) {
+        Some((min, max)) => {
+            let left = min[0] - BAR_WIDTH - 4.0;
+            let height = max[1] - min[1];
+
+            (
+                [left, min[1]],
+                [left + BAR_WIDTH, max[1]],
+                [left, max[1] - height * fraction],
+            )
+        }
+        None => {
+            let left = entity.screen_pos[0];
+            let top = entity.screen_pos[1] - BAR_WIDTH - 4.0;
+
+            (
+                [left, top],
+                [left + BAR_LENGTH, top + BAR_WIDTH],
+                [left, top],
+            )
+        }
+    };
+
+    let fill_max = match entity.box_2d {
+        Some(_) => back_max,
+        None => [back_min[0] + BAR_LENGTH * fraction, back_max[1]],
+    };
+
+    draw.rect(Layer::Background, back_min, back_max, background, 1.0, true);
+    draw.rect(
+        Layer::Background,
+        fill_min,
+        fill_max,
+        health.color(),
+        1.0,
+        true,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::{DrawCmd, Recorder};
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    fn camera() -> Camera {
+        Camera {
+            pos: Vec3::default(),
+            yaw: 0.0,
+            pitch: 0.0,
+            fov: 60.0,
+            width: 1920.0,
+            height: 1080.0,
+        }
+    }
+
+    fn obj_at(pos: Vec3<f32>) -> Obj {
+        Obj {
+            c_model_obj_world_pos: pos,
+            ..Default::default()
+        }
+    }
+
+    fn near(a: [f32; 2], b: [f32; 2]) -> bool {
+        (a[0] - b[0]).abs() < 0.01 && (a[1] - b[1]).abs() < 0.01
+    }
+
+    #[test]
+    fn entity_in_front_draws_dot_and_health_bar() {
+        let flags = EspFlags {
+            dot: true,
+            health_bar: true,
+            ..Default::default()
+        };
+        let health = Health {
+            current: 50.0,
+            max: 100.0,
+            flashing: false,
+        };
+
+        // yaw 0 朝 +x，正前方 10 米的点投影到屏幕中心
+        let obj = obj_at(Vec3::new(10.0, 0.0, 0.0));
+
+        let mut recorder = Recorder::default();
+        let on_screen = draw_basic_entity(
+            &mut recorder,
+            &flags,
+            &camera(),
+            &obj,
+            Vec3::default(),
+            Some(health),
+            RED,
+            100.0,
+        );
+        assert!(on_screen);
+
+        let cmds = recorder.take();
+        assert_eq!(cmds.len(), 3);
+        assert!(cmds.iter().all(|cmd| cmd.layer() == Layer::Background));
+
+        match &cmds[0] {
+            DrawCmd::Circle {
+                center,
+                color,
+                filled,
+                ..
+            } => {
+                assert!(near(*center, [960.0, 540.0]));
+                assert_eq!(*color, RED);
+                assert!(filled);
+            }
+            cmd => panic!("{:?}", cmd),
+        }
+
+        // 没有方框时血条横着画在实体上方，半血填一半
+        match (&cmds[1], &cmds[2]) {
+            (
+                DrawCmd::Rect { min, max, .. },
+                DrawCmd::Rect {
+                    min: fill_min,
+                    max: fill_max,
+                    color,
+                    ..
+                },
+            ) => {
+                assert!(near(*min, [960.0, 532.0]));
+                assert!(near(*max, [1000.0, 536.0]));
+                assert!(near(*fill_min, [960.0, 532.0]));
+                assert!(near(*fill_max, [980.0, 536.0]));
+                assert_eq!(*color, health.color());
+            }
+            cmds => panic!("{:?}", cmds),
+        }
+    }
+
+    #[test]
+    fn entity_behind_draws_arrow_at_bottom_edge() {
+        let obj = obj_at(Vec3::new(-10.0, 0.0, 0.0));
+
+        let mut recorder = Recorder::default();
+        let on_screen = draw_basic_entity(
+            &mut recorder,
+            &EspFlags::default(),
+            &camera(),
+            &obj,
+            Vec3::default(),
+            None,
+            RED,
+            100.0,
+        );
+        assert!(!on_screen);
+
+        let cmds = recorder.take();
+        assert_eq!(cmds.len(), 2);
+
+        match &cmds[0] {
+            DrawCmd::Polyline {
+                layer,
+                points,
+                closed,
+                ..
+            } => {
+                assert_eq!(*layer, Layer::Background);
+                assert!(closed);
+                // 箭头尖在屏幕底边内缩 ARROW_MARGIN 的位置再往下 size
+                let size = ARROW_MIN_SIZE + (ARROW_MAX_SIZE - ARROW_MIN_SIZE) * 0.9;
+                assert!(near(points[0], [960.0, 1080.0 - ARROW_MARGIN + size]));
+            }
+            cmd => panic!("{:?}", cmd),
+        }
+
+        match &cmds[1] {
+            DrawCmd::Text { text, .. } => assert_eq!(text, "10"),
+            cmd => panic!("{:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn arrow_skipped_beyond_range() {
+        let obj = obj_at(Vec3::new(-10.0, 0.0, 0.0));
+
+        let mut recorder = Recorder::default();
+        draw_basic_entity(
+            &mut recorder,
+            &EspFlags::default(),
+            &camera(),
+            &obj,
+            Vec3::default(),
+            None,
+            RED,
+            5.0,
+        );
+
+        assert!(recorder.take().is_empty());
+    }
+}
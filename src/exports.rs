@@ -0,0 +1,636 @@
+use crate::{pointer_scan::parse_hex, snapshot::Memory, world::read_c_string};
+
+// 导出表太大说明读错了
+const MAX_EXPORTS: usize = 0x20000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Export {
+    pub(crate) name: String,
+    pub(crate) addr: usize,
+    // 解析不了的签名是 None，不能调用
+    pub(crate) shape: Option<ExportShape>,
+}
+
+// 从 PE 头读模块的按名导出，跳过转发到别的模块的
+pub(crate) fn read_exports<M: Memory>(mem: &M, base: usize) -> Option<Vec<Export>> {
+    if mem.read::<u16>(base)? != 0x5A4D {
+        return None;
+    }
+
+    let nt_p = base + mem.read::<u32>(base + 0x3C)? as usize;
+    if mem.read::<u32>(nt_p)? != 0x4550 {
+        return None;
+    }
+
+    // IMAGE_OPTIONAL_HEADER64::DataDirectory[0]
+    let dir_rva = mem.read::<u32>(nt_p + 0x88)? as usize;
+    let dir_size = mem.read::<u32>(nt_p + 0x8C)? as usize;
+    if dir_rva == 0 {
+        return Some(Vec::new());
+    }
+
+    let dir_p = base + dir_rva;
+    let name_count = mem.read::<u32>(dir_p + 0x18)? as usize;
+    let functions_p = base + mem.read::<u32>(dir_p + 0x1C)? as usize;
+    let names_p = base + mem.read::<u32>(dir_p + 0x20)? as usize;
+    let ordinals_p = base + mem.read::<u32>(dir_p + 0x24)? as usize;
+
+    if name_count > MAX_EXPORTS {
+        return None;
+    }
+
+    let mut exports = Vec::with_capacity(name_count);
+
+    for index in 0..name_count {
+        let name_rva = mem.read::<u32>(names_p + index * 4)? as usize;
+        let ordinal = mem.read::<u16>(ordinals_p + index * 2)? as usize;
+        let func_rva = mem.read::<u32>(functions_p + ordinal * 4)? as usize;
+
+        if (dir_rva..dir_rva + dir_size).contains(&func_rva) {
+            continue;
+        }
+
+        let name = read_c_string(mem, base + name_rva, 0x400)?;
+        let shape = parse_shape(&name);
+
+        exports.push(Export {
+            name,
+            addr: base + func_rva,
+            shape,
+        });
+    }
+
+    Some(exports)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ScalarType {
+    Bool,
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    // 指向的类名
+    Ptr(String),
+}
+
+impl std::fmt::Display for ScalarType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScalarType::Bool => write!(f, "bool"),
+            ScalarType::I8 => write!(f, "char"),
+            ScalarType::U8 => write!(f, "unsigned char"),
+            ScalarType::I16 => write!(f, "short"),
+            ScalarType::U16 => write!(f, "unsigned short"),
+            ScalarType::I32 => write!(f, "int"),
+            ScalarType::U32 => write!(f, "unsigned int"),
+            ScalarType::I64 => write!(f, "__int64"),
+            ScalarType::U64 => write!(f, "unsigned __int64"),
+            ScalarType::F32 => write!(f, "float"),
+            ScalarType::F64 => write!(f, "double"),
+            ScalarType::Ptr(class) => write!(f, "{} *", class),
+        }
+    }
+}
+
+impl ScalarType {
+    pub(crate) fn is_int(&self) -> bool {
+        !matches!(self, ScalarType::F32 | ScalarType::F64 | ScalarType::Ptr(_))
+    }
+
+    // rax 里只有低位是有效的，按类型截断，浮点和指针返回 None
+    pub(crate) fn truncate(&self, val: u64) -> Option<i64> {
+        let val = match self {
+            ScalarType::Bool => (val as u8 != 0) as i64,
+            ScalarType::I8 => val as i8 as i64,
+            ScalarType::U8 => val as u8 as i64,
+            ScalarType::I16 => val as i16 as i64,
+            ScalarType::U16 => val as u16 as i64,
+            ScalarType::I32 => val as i32 as i64,
+            ScalarType::U32 => val as u32 as i64,
+            ScalarType::I64 | ScalarType::U64 => val as i64,
+            ScalarType::F32 | ScalarType::F64 | ScalarType::Ptr(_) => return None,
+        };
+
+        Some(val)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ReturnType {
+    Void,
+    Scalar(ScalarType),
+    // 按值返回的 vec2/vec3，调用时多传一个输出指针
+    Vec2,
+    Vec3,
+}
+
+impl std::fmt::Display for ReturnType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReturnType::Void => write!(f, "void"),
+            ReturnType::Scalar(ty) => write!(f, "{}", ty),
+            ReturnType::Vec2 => write!(f, "vec2"),
+            ReturnType::Vec3 => write!(f, "vec3"),
+        }
+    }
+}
+
+// 能调用的只有非静态成员函数，最多一个标量参数:
+//   ret Class::Method() [const]
+//   ret Class::Method(scalar) [const]
+// ret 是 void、标量、指针或者按值的 vec2/vec3
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExportShape {
+    pub(crate) class: String,
+    pub(crate) method: String,
+    pub(crate) is_const: bool,
+    pub(crate) ret: ReturnType,
+    pub(crate) arg: Option<ScalarType>,
+}
+
+impl std::fmt::Display for ExportShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}::{}(", self.ret, self.class, self.method)?;
+        if let Some(arg) = &self.arg {
+            write!(f, "{}", arg)?;
+        }
+        write!(f, ")")?;
+        if self.is_const {
+            write!(f, " const")?;
+        }
+        Ok(())
+    }
+}
+
+impl ExportShape {
+    // const 或者 Get 开头的当作只读，其余的都可能改游戏状态
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.is_const || self.method.starts_with("Get")
+    }
+}
+
+// MSVC x64 的修饰名，比如 "?GetScreenWidth@IGame@@QEAAHXZ"
+pub(crate) fn parse_shape(mangled: &str) -> Option<ExportShape> {
+    let rest = mangled.strip_prefix('?')?;
+
+    // 构造、析构、运算符这类特殊名字不支持
+    if rest.starts_with('?') {
+        return None;
+    }
+
+    let (method, rest) = rest.split_once('@')?;
+    let (scope, rest) = rest.split_once("@@")?;
+    if method.is_empty() || scope.is_empty() || scope.contains(['?', '$']) {
+        return None;
+    }
+
+    // "Inner@Outer" 是 Outer::Inner
+    let class = scope.split('@').rev().collect::<Vec<_>>().join("::");
+
+    // 访问权限，静态函数 (C D K L S T) 没有 this
+    let rest = match rest.as_bytes().first()? {
+        b'A' | b'B' | b'E' | b'F' | b'I' | b'J' | b'M' | b'N' | b'Q' | b'R' | b'U' | b'V' => {
+            &rest[1..]
+        }
+        _ => return None,
+    };
+
+    // __ptr64 的 this，const 与否，__cdecl
+    let rest = rest.strip_prefix('E')?;
+    let (is_const, rest) = match rest.as_bytes().first()? {
+        b'A' => (false, &rest[1..]),
+        b'B' => (true, &rest[1..]),
+        _ => return None,
+    };
+    let rest = rest.strip_prefix('A')?;
+
+    let (ret, rest) = parse_return(rest)?;
+
+    let arg = match rest {
+        "XZ" => None,
+        _ => {
+            let (arg, rest) = parse_scalar(rest)?;
+            if rest != "@Z" || matches!(arg, ScalarType::Ptr(_)) {
+                return None;
+            }
+            Some(arg)
+        }
+    };
+
+    Some(ExportShape {
+        class,
+        method: method.to_string(),
+        is_const,
+        ret,
+        arg,
+    })
+}
+
+fn parse_return(text: &str) -> Option<(ReturnType, &str)> {
+    if let Some(rest) = text.strip_prefix('X') {
+        return Some((ReturnType::Void, rest));
+    }
+
+    for prefix in ["?AV", "?BV", "?AU", "?BU"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            let (name, rest) = rest.split_once("@@")?;
+            return match name {
+                "vec2" => Some((ReturnType::Vec2, rest)),
+                "vec3" => Some((ReturnType::Vec3, rest)),
+                _ => None,
+            };
+        }
+    }
+
+    let (ty, rest) = parse_scalar(text)?;
+    Some((ReturnType::Scalar(ty), rest))
+}
+
+fn parse_scalar(text: &str) -> Option<(ScalarType, &str)> {
+    for prefix in ["PEAV", "PEBV", "PEAU", "PEBU"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            let (name, rest) = rest.split_once("@@")?;
+            let class = name.split('@').rev().collect::<Vec<_>>().join("::");
+            return Some((ScalarType::Ptr(class), rest));
+        }
+    }
+
+    for prefix in ["PEAX", "PEBX"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return Some((ScalarType::Ptr("void".to_string()), rest));
+        }
+    }
+
+    // 字符串
+    for prefix in ["PEAD", "PEBD"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return Some((ScalarType::Ptr("char".to_string()), rest));
+        }
+    }
+
+    if let Some(rest) = text.strip_prefix('_') {
+        let ty = match rest.as_bytes().first()? {
+            b'N' => ScalarType::Bool,
+            b'J' => ScalarType::I64,
+            b'K' => ScalarType::U64,
+            _ => return None,
+        };
+        return Some((ty, &rest[1..]));
+    }
+
+    let ty = match text.as_bytes().first()? {
+        b'C' | b'D' => ScalarType::I8,
+        b'E' => ScalarType::U8,
+        b'F' => ScalarType::I16,
+        b'G' => ScalarType::U16,
+        b'H' | b'J' => ScalarType::I32,
+        b'I' | b'K' => ScalarType::U32,
+        b'M' => ScalarType::F32,
+        b'N' => ScalarType::F64,
+        _ => return None,
+    };
+
+    Some((ty, &text[1..]))
+}
+
+// parse_shape 不支持引用参数，单独认 void Class::Method(const vec3 &)，返回方法名
+// 比如 "?SetPosition@IBaseCamera@@QEAAXAEBVvec3@@@Z"
+pub(crate) fn vec3_setter<'a>(mangled: &'a str, class: &str) -> Option<&'a str> {
+    let rest = mangled.strip_prefix('?')?;
+    let (method, rest) = rest.split_once('@')?;
+    let rest = rest.strip_prefix(class)?.strip_prefix("@@")?;
+
+    if method.is_empty() || method.starts_with('?') {
+        return None;
+    }
+
+    // 和 parse_shape 一样只要有 this 的成员函数
+    if !b"ABEFIJMNQRUV".contains(rest.as_bytes().first()?) {
+        return None;
+    }
+
+    (&rest[1..] == "EAAXAEBVvec3@@@Z").then_some(method)
+}
+
+// 按条件在导出表里挑一个成员函数，有多个时取方法名最短的
+// 修饰名没确认过的函数用这个绑定: 找不到说明这个版本的引擎没有，调用方要能退回去
+pub(crate) fn find_method(
+    exports: &[Export],
+    matches: impl Fn(&ExportShape) -> bool,
+) -> Option<(&Export, &ExportShape)> {
+    exports
+        .iter()
+        .filter_map(|export| Some((export, export.shape.as_ref()?)))
+        .filter(|(_, shape)| matches(shape))
+        .min_by_key(|(_, shape)| shape.method.len())
+}
+
+// 整数都放在通用寄存器里传
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CallArg {
+    Int(i64),
+    F32(f32),
+    F64(f64),
+}
+
+// 十进制、0x 开头的十六进制，bool 还可以是 true/false
+pub(crate) fn parse_arg(ty: &ScalarType, text: &str) -> Option<CallArg> {
+    let text = text.trim();
+
+    match ty {
+        ScalarType::F32 => text.parse().ok().map(CallArg::F32),
+        ScalarType::F64 => text.parse().ok().map(CallArg::F64),
+        ScalarType::Bool => match text {
+            "true" | "1" => Some(CallArg::Int(1)),
+            "false" | "0" => Some(CallArg::Int(0)),
+            _ => None,
+        },
+        _ if text.starts_with("0x") || text.starts_with("0X") => {
+            parse_hex(text).map(|val| CallArg::Int(val as i64))
+        }
+        _ => text.parse().ok().map(CallArg::Int),
+    }
+}
+
+// 调用后拿到的原始返回值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CallResult {
+    Void,
+    Int(u64),
+    F32(f32),
+    F64(f64),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+}
+
+// rax 里只有低位是有效的，按返回类型截断
+pub(crate) fn format_result(ret: &ReturnType, result: CallResult) -> String {
+    match (ret, result) {
+        (ReturnType::Scalar(ty), CallResult::Int(val)) => match ty {
+            ScalarType::Bool => format!("{}", val as u8 != 0),
+            ScalarType::I8 => format!("{}", val as i8),
+            ScalarType::U8 => format!("{}", val as u8),
+            ScalarType::I16 => format!("{}", val as i16),
+            ScalarType::U16 => format!("{}", val as u16),
+            ScalarType::I32 => format!("{}", val as i32),
+            ScalarType::U32 => format!("{} ({:#X})", val as u32, val as u32),
+            ScalarType::I64 => format!("{}", val as i64),
+            ScalarType::U64 | ScalarType::Ptr(_) => format!("{:#X}", val),
+            ScalarType::F32 | ScalarType::F64 => format!("{:#X}", val),
+        },
+        (_, CallResult::Void) => "(void)".to_string(),
+        (_, CallResult::F32(val)) => format!("{}", val),
+        (_, CallResult::F64(val)) => format!("{}", val),
+        (_, CallResult::Vec2([x, y])) => format!("({}, {})", x, y),
+        (_, CallResult::Vec3([x, y, z])) => format!("({}, {}, {})", x, y, z),
+        (_, CallResult::Int(val)) => format!("{:#X}", val),
+    }
+}
+
+// 会话函数以 SessionCooperativeDI 为 this 每帧调用，类名必须对得上
+// 类名是按 IGame / ILevel 的命名猜的，对不上时什么都不绑定，写入一直保持关闭
+fn is_session_getter(shape: &ExportShape) -> bool {
+    ThisObject::for_class(&shape.class) == Some(ThisObject::SessionCooperativeDI)
+        && shape.arg.is_none()
+}
+
+// 当前玩家数，GetMaxPlayerCount 之类的上限、本地玩家数都不算
+pub(crate) fn is_session_player_count(shape: &ExportShape) -> bool {
+    const NAMES: [&str; 5] = [
+        "PlayerCount",
+        "PlayersCount",
+        "NumPlayers",
+        "PlayersNum",
+        "PlayerNum",
+    ];
+
+    is_session_getter(shape)
+        && matches!(&shape.ret, ReturnType::Scalar(ty) if ty.is_int() && *ty != ScalarType::Bool)
+        && NAMES.contains(&shape.method.strip_prefix("Get").unwrap_or(&shape.method))
+}
+
+// 是不是联机的标志，IsCoopAllowed 之类的开关不算
+pub(crate) fn is_session_multiplayer_flag(shape: &ExportShape) -> bool {
+    const NAMES: [&str; 4] = ["IsMultiplayer", "IsCoop", "IsCooperative", "IsOnline"];
+
+    is_session_getter(shape)
+        && shape.ret == ReturnType::Scalar(ScalarType::Bool)
+        && NAMES.contains(&shape.method.as_str())
+}
+
+// 调用时当作 this 的 World 对象
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum ThisObject {
+    #[default]
+    GameDI,
+    LevelDI,
+    SessionCooperativeDI,
+    LocalClientDI,
+    PlayerDI,
+    CameraManagerDI,
+    CameraFPPDI,
+    CrosshairTarget,
+    Manual,
+}
+
+pub(crate) const THIS_OBJECTS: [ThisObject; 9] = [
+    ThisObject::GameDI,
+    ThisObject::LevelDI,
+    ThisObject::SessionCooperativeDI,
+    ThisObject::LocalClientDI,
+    ThisObject::PlayerDI,
+    ThisObject::CameraManagerDI,
+    ThisObject::CameraFPPDI,
+    ThisObject::CrosshairTarget,
+    ThisObject::Manual,
+];
+
+impl std::fmt::Display for ThisObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThisObject::GameDI => write!(f, "GameDI (IGame)"),
+            ThisObject::LevelDI => write!(f, "LevelDI (ILevel)"),
+            ThisObject::SessionCooperativeDI => write!(f, "SessionCooperativeDI"),
+            ThisObject::LocalClientDI => write!(f, "LocalClientDI"),
+            ThisObject::PlayerDI => write!(f, "PlayerDI"),
+            ThisObject::CameraManagerDI => write!(f, "CameraManagerDI"),
+            ThisObject::CameraFPPDI => write!(f, "CameraFPPDI (IBaseCamera)"),
+            ThisObject::CrosshairTarget => write!(f, "准星目标 (IModelObject)"),
+            ThisObject::Manual => write!(f, "手动地址"),
+        }
+    }
+}
+
+impl ThisObject {
+    // 按 impls.rs 里已经绑定过的类猜一个默认的 this
+    pub(crate) fn for_class(class: &str) -> Option<Self> {
+        match class {
+            "IGame" => Some(ThisObject::GameDI),
+            "ILevel" => Some(ThisObject::LevelDI),
+            "ISessionCooperative" => Some(ThisObject::SessionCooperativeDI),
+            "IBaseCamera" => Some(ThisObject::CameraFPPDI),
+            "IControlObject" | "IModelObject" => Some(ThisObject::CrosshairTarget),
+            _ => None,
+        }
+    }
+
+    // 选的对象不一定是这个类的，不对时返回提示
+    pub(crate) fn mismatch(self, class: &str) -> Option<String> {
+        match Self::for_class(class) {
+            Some(this) if this == self => None,
+            Some(this) => Some(format!(
+                "{} 的方法应该用 {}，现在选的是 {}",
+                class, this, self
+            )),
+            None => Some(format!("不知道哪个对象是 {}，现在选的是 {}", class, self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(name: &str) -> Export {
+        Export {
+            name: name.to_string(),
+            addr: 0x1000,
+            shape: parse_shape(name),
+        }
+    }
+
+    #[test]
+    fn find_method_matches_class_and_prefers_shortest_name() {
+        let exports = [
+            export("?GetScreenWidth@IGame@@QEAAHXZ"),
+            export("?GetScreenWidthScaled@IGame@@QEAAHXZ"),
+            export("?GetScreenWidth@ILevel@@QEAAHXZ"),
+            export("?BadName"),
+        ];
+
+        let (found, shape) = find_method(&exports, |shape| {
+            shape.class == "IGame" && shape.method.contains("ScreenWidth")
+        })
+        .unwrap();
+        assert_eq!(found.name, "?GetScreenWidth@IGame@@QEAAHXZ");
+        assert_eq!(shape.ret, ReturnType::Scalar(ScalarType::I32));
+
+        assert!(find_method(&exports, |shape| shape.method == "Missing").is_none());
+        assert!(find_method(&exports, |shape| shape.class == "IBaseCamera").is_none());
+    }
+
+    #[test]
+    fn parse_shape_reads_string_return() {
+        let shape = parse_shape("?GetName@ILevel@@QEBAPEBDXZ").unwrap();
+        assert_eq!(shape.class, "ILevel");
+        assert!(shape.is_const);
+        assert_eq!(
+            shape.ret,
+            ReturnType::Scalar(ScalarType::Ptr("char".to_string()))
+        );
+        assert_eq!(shape.arg, None);
+    }
+
+    #[test]
+    fn vec3_setter_matches_exact_signature() {
+        assert_eq!(
+            vec3_setter("?SetPosition@IBaseCamera@@QEAAXAEBVvec3@@@Z", "IBaseCamera"),
+            Some("SetPosition")
+        );
+
+        // 类不对、const、返回值不是 void、参数是按值的都不算
+        assert_eq!(
+            vec3_setter(
+                "?SetPosition@IControlObject@@QEAAXAEBVvec3@@@Z",
+                "IBaseCamera"
+            ),
+            None
+        );
+        assert_eq!(
+            vec3_setter("?SetPosition@IBaseCamera@@QEBAXAEBVvec3@@@Z", "IBaseCamera"),
+            None
+        );
+        assert_eq!(
+            vec3_setter(
+                "?SetPosition@IBaseCamera@@QEAA_NAEBVvec3@@@Z",
+                "IBaseCamera"
+            ),
+            None
+        );
+        assert_eq!(
+            vec3_setter("?SetPosition@IBaseCamera@@QEAAXVvec3@@@Z", "IBaseCamera"),
+            None
+        );
+        assert_eq!(
+            vec3_setter("?GetPosition@IBaseCamera@@QEBA?BVvec3@@XZ", "IBaseCamera"),
+            None
+        );
+    }
+
+    #[test]
+    fn read_only_is_const_or_getter() {
+        let shape = |name| parse_shape(name).unwrap();
+
+        assert!(shape("?GetScreenWidth@IGame@@QEAAHXZ").is_read_only());
+        assert!(shape("?IsVisible@IModelObject@@QEBA_NXZ").is_read_only());
+        assert!(!shape("?IsVisible@IModelObject@@QEAA_NXZ").is_read_only());
+        assert!(!shape("?SetHealth@IModelObject@@QEAAXM@Z").is_read_only());
+    }
+
+    #[test]
+    fn this_mismatch_needs_the_class_object() {
+        assert_eq!(ThisObject::LevelDI.mismatch("ILevel"), None);
+        assert!(ThisObject::GameDI.mismatch("ILevel").is_some());
+        assert!(ThisObject::Manual.mismatch("ILevel").is_some());
+        assert!(ThisObject::PlayerDI.mismatch("IPlayer").is_some());
+    }
+
+    #[test]
+    fn session_methods_need_exact_class_and_name() {
+        let shape = |name| parse_shape(name).unwrap();
+
+        assert!(is_session_player_count(&shape(
+            "?GetPlayerCount@ISessionCooperative@@QEBAHXZ"
+        )));
+        assert!(is_session_player_count(&shape(
+            "?GetNumPlayers@ISessionCooperative@@QEAAIXZ"
+        )));
+        assert!(!is_session_player_count(&shape(
+            "?GetMaxPlayerCount@ISessionCooperative@@QEBAHXZ"
+        )));
+        assert!(!is_session_player_count(&shape(
+            "?GetPlayerCount@ISessionManager@@QEBAHXZ"
+        )));
+        assert!(!is_session_player_count(&shape(
+            "?GetPlayerCount@ISessionCooperative@@QEBAMXZ"
+        )));
+
+        assert!(is_session_multiplayer_flag(&shape(
+            "?IsMultiplayer@ISessionCooperative@@QEBA_NXZ"
+        )));
+        assert!(!is_session_multiplayer_flag(&shape(
+            "?IsCoopAllowed@ISessionCooperative@@QEBA_NXZ"
+        )));
+        assert!(!is_session_multiplayer_flag(&shape(
+            "?IsMultiplayer@CSessionCooperative@@QEBA_NXZ"
+        )));
+        assert!(!is_session_multiplayer_flag(&shape(
+            "?IsMultiplayer@ISessionCooperative@@QEBAHXZ"
+        )));
+    }
+
+    #[test]
+    fn truncate_keeps_low_bits_only() {
+        assert_eq!(ScalarType::U8.truncate(0xFFFF_FF12), Some(0x12));
+        assert_eq!(ScalarType::I32.truncate(0xDEAD_0000_FFFF_FFFF), Some(-1));
+        assert_eq!(ScalarType::Bool.truncate(0x100), Some(0));
+        assert_eq!(ScalarType::F32.truncate(1), None);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::math::{Vec3, direction};
+
+// 按住加速/减速键时的倍数
+const FAST_SCALE: f32 = 4.0;
+const SLOW_SCALE: f32 = 0.25;
+
+// 每个方向 -1、0 或 1
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct FreeCamInput {
+    pub(crate) forward: f32,
+    pub(crate) right: f32,
+    pub(crate) up: f32,
+    pub(crate) fast: bool,
+    pub(crate) slow: bool,
+}
+
+// 前后沿视线方向，左右在水平面上，上下沿世界 y 轴
+pub(crate) fn move_camera(
+    pos: Vec3<f32>,
+    yaw: f32,
+    pitch: f32,
+    input: &FreeCamInput,
+    speed: f32,
+    delta_time: f32,
+) -> Vec3<f32> {
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let forward = direction(yaw, pitch);
+    // 和 Mat4::look_to 一样是左手系
+    let right = up.cross(direction(yaw, 0.0));
+
+    let dir = forward * input.forward + right * input.right + up * input.up;
+    if dir.is_zero() {
+        return pos;
+    }
+
+    let mut speed = speed;
+    if input.fast {
+        speed *= FAST_SCALE;
+    }
+    if input.slow {
+        speed *= SLOW_SCALE;
+    }
+
+    pos + dir.normalize() * (speed * delta_time)
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+// 掉血后闪烁多久，单位秒
+const FLASH_TIME: f64 = 0.4;
+// 多久没见到的实体从表里删掉
+const FORGET_TIME: f64 = 30.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Health {
+    pub(crate) current: f32,
+    pub(crate) max: f32,
+    pub(crate) flashing: bool,
+}
+
+impl Health {
+    pub(crate) fn fraction(&self) -> f32 {
+        if self.max <= 0.0 {
+            return 0.0;
+        }
+
+        (self.current / self.max).clamp(0.0, 1.0)
+    }
+
+    // 满血绿色，一半黄色，快死红色
+    pub(crate) fn color(&self) -> [f32; 4] {
+        let fraction = self.fraction();
+
+        if self.flashing {
+            return [1.0, 1.0, 1.0, 1.0];
+        }
+
+        if fraction > 0.5 {
+            [(1.0 - fraction) * 2.0, 1.0, 0.0, 1.0]
+        } else {
+            [1.0, fraction * 2.0, 0.0, 1.0]
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Entry {
+    max: f32,
+    last: f32,
+    flash_until: f64,
+    seen: f64,
+}
+
+// 引擎里没找到最大血量，按 ModelObject 记下见过的最高血量
+#[derive(Debug, Default, Clone)]
+pub(crate) struct HealthTracker {
+    entries: HashMap<usize, Entry>,
+}
+
+impl HealthTracker {
+    pub(crate) fn update(&mut self, model_obj_p: usize, current: f32, now: f64) -> Health {
+        let entry = self.entries.entry(model_obj_p).or_insert(Entry {
+            max: current,
+            last: current,
+            flash_until: 0.0,
+            seen: now,
+        });
+
+        if current < entry.last {
+            entry.flash_until = now + FLASH_TIME;
+        }
+
+        entry.max = entry.max.max(current);
+        entry.last = current;
+        entry.seen = now;
+
+        Health {
+            current,
+            max: entry.max,
+            flashing: now < entry.flash_until,
+        }
+    }
+
+    pub(crate) fn forget_old(&mut self, now: f64) {
+        self.entries
+            .retain(|_, entry| now - entry.seen < FORGET_TIME);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    draw::{Draw, DrawCmd, Layer},
+    plugin::PluginUi,
+};
+use hudhook::imgui::Ui;
+
+pub(crate) struct ImguiDraw<'ui> {
+    ui: &'ui Ui,
+}
+
+impl<'ui> ImguiDraw<'ui> {
+    pub(crate) fn new(ui: &'ui Ui) -> Self {
+        Self { ui }
+    }
+}
+
+impl Draw for ImguiDraw<'_> {
+    fn submit(&mut self, cmd: DrawCmd) {
+        // 同一层的 DrawListMut 同时只能存在一个，所以每条命令单独取一次，画完就释放
+        let draw_list = match cmd.layer() {
+            Layer::Background => self.ui.get_background_draw_list(),
+            Layer::Foreground => self.ui.get_foreground_draw_list(),
+            Layer::Window => self.ui.get_window_draw_list(),
+        };
+
+        match cmd {
+            DrawCmd::Text {
+                pos, color, text, ..
+            } => draw_list.add_text(pos, color, text),
+            DrawCmd::Line {
+                from,
+                to,
+                color,
+                thickness,
+                ..
+            } => draw_list
+                .add_line(from, to, color)
+                .thickness(thickness)
+                .build(),
+            DrawCmd::Circle {
+                center,
+                radius,
+                color,
+                thickness,
+                filled,
+                ..
+            } => draw_list
+                .add_circle(center, radius, color)
+                .thickness(thickness)
+                .filled(filled)
+                .build(),
+            DrawCmd::Rect {
+                min,
+                max,
+                color,
+                thickness,
+                filled,
+                ..
+            } => draw_list
+                .add_rect(min, max, color)
+                .thickness(thickness)
+                .filled(filled)
+                .build(),
+            DrawCmd::Polyline {
+                mut points,
+                color,
+                thickness,
+                closed,
+                ..
+            } => {
+                if closed {
+                    points.push(points[0]);
+                }
+
+                draw_list
+                    .add_polyline(points, color)
+                    .thickness(thickness)
+                    .build()
+            }
+        }
+    }
+}
+
+// 插件菜单页里的控件
+pub(crate) struct ImguiPluginUi<'ui> {
+    ui: &'ui Ui,
+}
+
+impl<'ui> ImguiPluginUi<'ui> {
+    pub(crate) fn new(ui: &'ui Ui) -> Self {
+        Self { ui }
+    }
+}
+
+impl PluginUi for ImguiPluginUi<'_> {
+    fn text(&mut self, text: &str) {
+        self.ui.text(text);
+    }
+
+    fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        self.ui.checkbox(label, value)
+    }
+
+    fn button(&mut self, label: &str) -> bool {
+        self.ui.button(label)
+    }
+
+    fn slider_float(&mut self, label: &str, min: f32, max: f32, value: &mut f32) -> bool {
+        self.ui.slider(label, min, max, value)
+    }
+}
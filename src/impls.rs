@@ -1,7 +1,15 @@
 // #![allow(unused)]
 #![allow(static_mut_refs)]
 
-use crate::{CameraFPPDI, ENGINE_DLL_INFO, GameDI, ModelObject, Vec2, Vec3};
+use crate::{
+    ENGINE_DLL_INFO, clock,
+    exports::{
+        self, Export, ExportShape, ReturnType, ScalarType, find_method, read_exports, vec3_setter,
+    },
+    math::{Vec2, Vec3},
+    process::LiveMemory,
+    world::{CameraFPPDI, GameDI, LevelDI, ModelObject, SessionCooperativeDI, read_c_string},
+};
 use std::{
     mem::{MaybeUninit, transmute},
     sync::Once,
@@ -21,6 +29,39 @@ unsafe fn get_proc_address<S: AsRef<str>>(mod_handle: usize, proc_name: S) -> Op
     Some(proc_addr)
 }
 
+// engine_x64_rwdi.dll 的导出表，第一次用到时读
+pub(crate) unsafe fn engine_exports() -> &'static [Export] {
+    static mut EXPORTS: Vec<Export> = Vec::new();
+
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        EXPORTS = read_exports(&LiveMemory, ENGINE_DLL_INFO.base).unwrap_or_default();
+    });
+
+    &EXPORTS
+}
+
+// 修饰名没确认过的函数，从真实的导出表里按条件找到的
+#[derive(Debug, Clone)]
+pub(crate) struct FoundMethod {
+    pub(crate) addr: usize,
+    pub(crate) name: String,
+    pub(crate) shape: ExportShape,
+}
+
+fn found((export, shape): (&Export, &ExportShape)) -> FoundMethod {
+    FoundMethod {
+        addr: export.addr,
+        name: export.name.clone(),
+        shape: shape.clone(),
+    }
+}
+
+unsafe fn find_engine_method(matches: impl Fn(&ExportShape) -> bool) -> Option<FoundMethod> {
+    find_method(engine_exports(), matches).map(found)
+}
+
 #[inline(always)]
 pub(crate) unsafe fn get_screen_width(game_di_p: *const GameDI) -> i32 {
     type Prototype = unsafe extern "system" fn(*const GameDI) -> i32;
@@ -169,6 +210,105 @@ pub(crate) unsafe fn get_bone_joint_pos(
     PROC_PTR.assume_init()(model_obj_p, world_pos, index)
 }
 
+// IModelObject 上名字像骨骼数的无参整数函数，没有时是 None
+pub(crate) unsafe fn bone_count_method() -> Option<&'static FoundMethod> {
+    static mut METHOD: Option<FoundMethod> = None;
+
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        METHOD = find_engine_method(|shape| {
+            shape.class == "IModelObject"
+                && shape.arg.is_none()
+                && shape.method.contains("Bone")
+                && (shape.method.contains("Count") || shape.method.contains("Num"))
+                && matches!(&shape.ret, ReturnType::Scalar(ty) if ty.is_int())
+        });
+    });
+
+    METHOD.as_ref()
+}
+
+// ILevel 上返回字符串、名字里带 Name 的无参 const 函数，当作地图名
+pub(crate) unsafe fn level_name_method() -> Option<&'static FoundMethod> {
+    static mut METHOD: Option<FoundMethod> = None;
+
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        METHOD = find_engine_method(|shape| {
+            shape.class == "ILevel"
+                && shape.arg.is_none()
+                && shape.is_const
+                && shape.method.contains("Name")
+                && shape.ret == ReturnType::Scalar(ScalarType::Ptr("char".to_string()))
+        });
+    });
+
+    METHOD.as_ref()
+}
+
+pub(crate) unsafe fn get_level_name(level_di_p: *const LevelDI) -> Option<String> {
+    type Prototype = unsafe extern "system" fn(*const LevelDI) -> usize;
+
+    let method = level_name_method()?;
+    let str_p = transmute::<usize, Prototype>(method.addr)(level_di_p);
+    if str_p == 0 {
+        return None;
+    }
+
+    read_c_string(&LiveMemory, str_p, 256).filter(|val| !val.is_empty())
+}
+
+// SessionCooperativeDI 上的玩家数，没有时再找 IsMultiplayer 之类的标志
+pub(crate) unsafe fn session_method() -> Option<&'static FoundMethod> {
+    static mut METHOD: Option<FoundMethod> = None;
+
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        METHOD = find_engine_method(exports::is_session_player_count)
+            .or_else(|| find_engine_method(exports::is_session_multiplayer_flag));
+    });
+
+    METHOD.as_ref()
+}
+
+// 联机时是 Some(true)，导出表里没有会话函数时是 None
+pub(crate) unsafe fn is_multiplayer(
+    session_cooperative_di_p: *const SessionCooperativeDI,
+) -> Option<bool> {
+    type Prototype = unsafe extern "system" fn(*const SessionCooperativeDI) -> u64;
+
+    let method = session_method()?;
+    let ReturnType::Scalar(ty) = &method.shape.ret else {
+        return None;
+    };
+
+    let val = ty.truncate(transmute::<usize, Prototype>(method.addr)(
+        session_cooperative_di_p,
+    ))?;
+
+    // 标志只会是 bool，其余的是玩家数
+    match ty {
+        ScalarType::Bool => Some(val != 0),
+        _ => Some(val > 1),
+    }
+}
+
+pub(crate) unsafe fn get_bone_count(model_obj_p: *const ModelObject) -> Option<u32> {
+    type Prototype = unsafe extern "system" fn(*const ModelObject) -> u64;
+
+    let method = bone_count_method()?;
+    let ReturnType::Scalar(ty) = &method.shape.ret else {
+        return None;
+    };
+
+    let count = ty.truncate(transmute::<usize, Prototype>(method.addr)(model_obj_p))?;
+
+    u32::try_from(count).ok()
+}
+
 // #[inline(always)]
 // pub(crate) unsafe fn point_to_screen_clamp_to_frustum(
 //     camera_fpp_di_p: *const CameraFPPDI,
@@ -301,6 +441,128 @@ pub(crate) unsafe fn get_position(camera_fpp_di_p: *const CameraFPPDI) -> *const
     return PROC_PTR.assume_init()(camera_fpp_di_p as *const CameraFPPDI, &mut pos);
 }
 
+// IBaseCamera 上签名是 void Set*Position(const vec3 &) 的导出，在导出表里确认过签名才绑定
+pub(crate) unsafe fn set_position_export() -> Option<&'static Export> {
+    static mut EXPORT: Option<Export> = None;
+
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| {
+        EXPORT = engine_exports()
+            .iter()
+            .filter(|export| {
+                vec3_setter(&export.name, "IBaseCamera")
+                    .is_some_and(|method| method.starts_with("Set") && method.contains("Position"))
+            })
+            .min_by_key(|export| export.name.len())
+            .cloned();
+    });
+
+    EXPORT.as_ref()
+}
+
+// 找不到时返回 false 而不是崩掉
+pub(crate) unsafe fn set_position(
+    camera_fpp_di_p: *const CameraFPPDI,
+    world_pos: *const Vec3<f32>,
+) -> bool {
+    type Prototype = unsafe extern "system" fn(*const CameraFPPDI, *const Vec3<f32>);
+
+    let Some(export) = set_position_export() else {
+        return false;
+    };
+
+    transmute::<usize, Prototype>(export.addr)(camera_fpp_di_p, world_pos);
+
+    true
+}
+
+// ILevel 上和时间有关的函数，按 clock.rs 里的签名在导出表里找，找不到的是 None
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ClockMethods {
+    pub(crate) get_time: Option<FoundMethod>,
+    pub(crate) set_time: Option<FoundMethod>,
+    pub(crate) get_speed: Option<FoundMethod>,
+    pub(crate) set_speed: Option<FoundMethod>,
+    pub(crate) is_night: Option<FoundMethod>,
+}
+
+pub(crate) unsafe fn clock_methods() -> &'static ClockMethods {
+    static mut METHODS: Option<ClockMethods> = None;
+
+    static ONCE: Once = Once::new();
+
+    // 只认成对的 GetX / SetX，缺一个就都不用
+    let pair = |is_stem: fn(&str) -> bool| match clock::find_pair(engine_exports(), is_stem) {
+        Some([getter, setter]) => (Some(found(getter)), Some(found(setter))),
+        None => (None, None),
+    };
+
+    ONCE.call_once(|| {
+        let (get_time, set_time) = pair(clock::is_time_stem);
+        let (get_speed, set_speed) = pair(clock::is_speed_stem);
+
+        METHODS = Some(ClockMethods {
+            get_time,
+            set_time,
+            get_speed,
+            set_speed,
+            is_night: find_engine_method(clock::is_night_getter),
+        });
+    });
+
+    METHODS.as_ref().unwrap()
+}
+
+// 时间单位是小时
+pub(crate) unsafe fn get_time_of_day(level_di_p: *const LevelDI) -> Option<f32> {
+    type Prototype = unsafe extern "system" fn(*const LevelDI) -> f32;
+
+    let method = clock_methods().get_time.as_ref()?;
+
+    Some(transmute::<usize, Prototype>(method.addr)(level_di_p))
+}
+
+pub(crate) unsafe fn set_time_of_day(level_di_p: *const LevelDI, hour: f32) -> bool {
+    type Prototype = unsafe extern "system" fn(*const LevelDI, f32);
+
+    let Some(method) = clock_methods().set_time.as_ref() else {
+        return false;
+    };
+
+    transmute::<usize, Prototype>(method.addr)(level_di_p, hour);
+
+    true
+}
+
+pub(crate) unsafe fn get_time_of_day_speed(level_di_p: *const LevelDI) -> Option<f32> {
+    type Prototype = unsafe extern "system" fn(*const LevelDI) -> f32;
+
+    let method = clock_methods().get_speed.as_ref()?;
+
+    Some(transmute::<usize, Prototype>(method.addr)(level_di_p))
+}
+
+pub(crate) unsafe fn set_time_of_day_speed(level_di_p: *const LevelDI, speed: f32) -> bool {
+    type Prototype = unsafe extern "system" fn(*const LevelDI, f32);
+
+    let Some(method) = clock_methods().set_speed.as_ref() else {
+        return false;
+    };
+
+    transmute::<usize, Prototype>(method.addr)(level_di_p, speed);
+
+    true
+}
+
+pub(crate) unsafe fn is_night(level_di_p: *const LevelDI) -> Option<bool> {
+    type Prototype = unsafe extern "system" fn(*const LevelDI) -> u64;
+
+    let method = clock_methods().is_night.as_ref()?;
+
+    Some(transmute::<usize, Prototype>(method.addr)(level_di_p) as u8 != 0)
+}
+
 #[inline(always)]
 pub(crate) unsafe fn is_in_frustum(model_obj_p: *const ModelObject) -> i8 {
     type Prototype = unsafe extern "system" fn(*const ModelObject) -> i8;
@@ -326,12 +588,12 @@ pub(crate) unsafe fn is_in_frustum(model_obj_p: *const ModelObject) -> i8 {
 #[inline(always)]
 pub(crate) unsafe fn get_objects_in_frustum(
     camera_fpp_di_p: *const CameraFPPDI,
-    model_obj_p_array_p: *const crate::Array<*const ModelObject>,
+    model_obj_p_array_p: *const crate::world::Array<*const ModelObject>,
     para: f32,
 ) -> i8 {
     type Prototype = unsafe extern "system" fn(
         *const CameraFPPDI,
-        *const crate::Array<*const ModelObject>,
+        *const crate::world::Array<*const ModelObject>,
         f32,
     ) -> i8;
 
@@ -0,0 +1,160 @@
+use crate::draw::{Draw, Layer};
+
+const ICON_SIZE: f32 = 5.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum ItemCategory {
+    Collectible,
+    Container,
+    Note,
+    Interactable,
+    #[default]
+    Other,
+}
+
+pub(crate) const ITEM_CATEGORIES: [ItemCategory; 5] = [
+    ItemCategory::Collectible,
+    ItemCategory::Container,
+    ItemCategory::Note,
+    ItemCategory::Interactable,
+    ItemCategory::Other,
+];
+
+impl std::fmt::Display for ItemCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemCategory::Collectible => write!(f, "收集品"),
+            ItemCategory::Container => write!(f, "容器"),
+            ItemCategory::Note => write!(f, "笔记"),
+            ItemCategory::Interactable => write!(f, "门/可互动"),
+            ItemCategory::Other => write!(f, "其他物件"),
+        }
+    }
+}
+
+// 按 preset 字符串 (小写) 里的关键字分类，前面的优先
+// 关键字是按常见命名猜的，打开 "模型名" 看到新的 preset 再往这里加
+const RULES: &[(&str, ItemCategory)] = &[
+    ("note", ItemCategory::Note),
+    ("letter", ItemCategory::Note),
+    ("journal", ItemCategory::Note),
+    ("diary", ItemCategory::Note),
+    ("tape", ItemCategory::Note),
+    ("collect", ItemCategory::Collectible),
+    ("pickup", ItemCategory::Collectible),
+    ("loot", ItemCategory::Collectible),
+    ("ammo", ItemCategory::Collectible),
+    ("money", ItemCategory::Collectible),
+    ("container", ItemCategory::Container),
+    ("chest", ItemCategory::Container),
+    ("crate", ItemCategory::Container),
+    ("locker", ItemCategory::Container),
+    ("cabinet", ItemCategory::Container),
+    ("safe", ItemCategory::Container),
+    ("stash", ItemCategory::Container),
+    ("airdrop", ItemCategory::Container),
+    ("door", ItemCategory::Interactable),
+    ("gate", ItemCategory::Interactable),
+    ("lever", ItemCategory::Interactable),
+    ("switch", ItemCategory::Interactable),
+    ("button", ItemCategory::Interactable),
+];
+
+// 见 world::decode_obj 里 logo 的注释
+pub(crate) fn classify_item(preset: &str, logo: u32) -> ItemCategory {
+    let preset = preset.to_ascii_lowercase();
+
+    if let Some((_, category)) = RULES.iter().find(|(key, _)| preset.contains(key)) {
+        return *category;
+    }
+
+    match logo {
+        0x2 => ItemCategory::Note,
+        0x8 => ItemCategory::Interactable,
+        _ => ItemCategory::Other,
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum ItemIcon {
+    #[default]
+    Dot,
+    Square,
+    Diamond,
+    Triangle,
+}
+
+pub(crate) const ITEM_ICONS: [ItemIcon; 4] = [
+    ItemIcon::Dot,
+    ItemIcon::Square,
+    ItemIcon::Diamond,
+    ItemIcon::Triangle,
+];
+
+impl std::fmt::Display for ItemIcon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemIcon::Dot => write!(f, "圆点"),
+            ItemIcon::Square => write!(f, "方块"),
+            ItemIcon::Diamond => write!(f, "菱形"),
+            ItemIcon::Triangle => write!(f, "三角"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ItemStyle {
+    pub(crate) enabled: bool,
+    pub(crate) color: [f32; 4],
+    pub(crate) icon: ItemIcon,
+    pub(crate) max_distance: f32,
+}
+
+// 按 ITEM_CATEGORIES 的顺序
+pub(crate) fn default_styles() -> [ItemStyle; ITEM_CATEGORIES.len()] {
+    let style = |color, icon, max_distance| ItemStyle {
+        enabled: false,
+        color,
+        icon,
+        max_distance,
+    };
+
+    [
+        style([1.0, 0.84, 0.0, 1.0], ItemIcon::Diamond, 80.0), // 金色
+        style([0.0, 1.0, 1.0, 1.0], ItemIcon::Square, 60.0),   // 青色
+        style([1.0, 1.0, 1.0, 1.0], ItemIcon::Triangle, 40.0), // 白色
+        style([0.6, 0.6, 1.0, 1.0], ItemIcon::Dot, 30.0),      // 淡紫
+        style([0.5, 0.5, 0.5, 1.0], ItemIcon::Dot, 20.0),      // 灰色
+    ]
+}
+
+pub(crate) fn draw_icon<D: Draw>(draw: &mut D, icon: ItemIcon, pos: [f32; 2], color: [f32; 4]) {
+    let [x, y] = pos;
+    let size = ICON_SIZE;
+
+    match icon {
+        ItemIcon::Dot => draw.circle(Layer::Background, pos, size * 0.7, color, 1.0, true),
+        ItemIcon::Square => draw.rect(
+            Layer::Background,
+            [x - size * 0.7, y - size * 0.7],
+            [x + size * 0.7, y + size * 0.7],
+            color,
+            1.5,
+            false,
+        ),
+        ItemIcon::Diamond => draw.polyline(
+            Layer::Background,
+            vec![[x, y - size], [x + size, y], [x, y + size], [x - size, y]],
+            color,
+            1.5,
+            true,
+        ),
+        ItemIcon::Triangle => draw.polyline(
+            Layer::Background,
+            vec![[x, y - size], [x + size, y + size], [x - size, y + size]],
+            color,
+            1.5,
+            true,
+        ),
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{
+    draw::{Draw, Layer},
+    math::Vec2,
+};
+
+// 标签之间至少留的空隙
+const LABEL_GAP: f32 = 2.0;
+// 标签被挤开超过这个距离才画引线
+const LEADER_MIN_OFFSET: f32 = 4.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum LabelAnchor {
+    Head,
+    #[default]
+    Feet,
+    BoxTop,
+}
+
+impl std::fmt::Display for LabelAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LabelAnchor::Head => write!(f, "头顶"),
+            LabelAnchor::Feet => write!(f, "脚下"),
+            LabelAnchor::BoxTop => write!(f, "方框上方"),
+        }
+    }
+}
+
+// 标签放在锚点的哪一边，被挤开时也往这边移
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum Side {
+    #[default]
+    Above,
+    Below,
+}
+
+// 一个实体的所有文字，按行竖着排成一块
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Label {
+    pub(crate) anchor: Vec2<f32>,
+    pub(crate) side: Side,
+    pub(crate) color: [f32; 4],
+    pub(crate) lines: Vec<String>,
+    pub(crate) line_height: f32,
+    // 整块的宽高
+    pub(crate) size: Vec2<f32>,
+    // 排版后的左上角
+    pub(crate) pos: Vec2<f32>,
+}
+
+impl Label {
+    pub(crate) fn new(anchor: Vec2<f32>, side: Side, color: [f32; 4]) -> Self {
+        Self {
+            anchor,
+            side,
+            color,
+            ..Default::default()
+        }
+    }
+
+    // size 是这一行文字的宽高
+    pub(crate) fn push(&mut self, line: String, size: [f32; 2]) {
+        self.lines.push(line);
+        self.line_height = self.line_height.max(size[1]);
+        self.size = Vec2::new(
+            self.size.x.max(size[0]),
+            self.line_height * self.lines.len() as f32,
+        );
+    }
+
+    // 锚点正上方或正下方，水平居中
+    pub(crate) fn natural_pos(&self) -> Vec2<f32> {
+        let x = self.anchor.x - self.size.x / 2.0;
+
+        match self.side {
+            Side::Above => Vec2::new(x, self.anchor.y - self.size.y),
+            Side::Below => Vec2::new(x, self.anchor.y),
+        }
+    }
+
+    fn overlaps(&self, other: &Label) -> bool {
+        self.pos.x < other.pos.x + other.size.x + LABEL_GAP
+            && other.pos.x < self.pos.x + self.size.x + LABEL_GAP
+            && self.pos.y < other.pos.y + other.size.y + LABEL_GAP
+            && other.pos.y < self.pos.y + self.size.y + LABEL_GAP
+    }
+}
+
+// 按顺序摆放，前面的优先待在原位，后面的和已经摆好的重叠时沿 side 方向挤开
+// 调用方先按距离排序，近处的标签就不会被远处的挤走
+pub(crate) fn layout(labels: &mut [Label]) {
+    for i in 0..labels.len() {
+        let (placed, rest) = labels.split_at_mut(i);
+        let label = &mut rest[0];
+
+        label.pos = label.natural_pos();
+
+        // 每次至少越过一个已经摆好的标签，最多挪 placed.len() 次
+        for _ in 0..=placed.len() {
+            let other = match placed.iter().find(|other| label.overlaps(other)) {
+                Some(val) => val,
+                None => break,
+            };
+
+            label.pos.y = match label.side {
+                Side::Above => other.pos.y - LABEL_GAP - label.size.y,
+                Side::Below => other.pos.y + other.size.y + LABEL_GAP,
+            };
+        }
+    }
+}
+
+pub(crate) fn draw_labels<D: Draw>(draw: &mut D, labels: &[Label], leader_lines: bool) {
+    for label in labels {
+        if leader_lines && label.pos.distance(label.natural_pos()) > LEADER_MIN_OFFSET {
+            let end = match label.side {
+                Side::Above => label.pos + Vec2::new(label.size.x / 2.0, label.size.y),
+                Side::Below => label.pos + Vec2::new(label.size.x / 2.0, 0.0),
+            };
+
+            draw.line(
+                Layer::Background,
+                label.anchor.to_array(),
+                end.to_array(),
+                label.color,
+                1.0,
+            );
+        }
+
+        for (i, line) in label.lines.iter().enumerate() {
+            draw.text(
+                Layer::Background,
+                [label.pos.x, label.pos.y + label.line_height * i as f32],
+                label.color,
+                line.clone(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        draw::{DrawCmd, Recorder},
+        esp::{EspEntity, label_anchor},
+        world::Obj,
+    };
+
+    const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+    fn label_at(anchor: Vec2<f32>, side: Side, text: &str) -> Label {
+        let mut label = Label::new(anchor, side, WHITE);
+        label.push(text.to_string(), [40.0, 12.0]);
+        label
+    }
+
+    fn entity(obj: &Obj) -> EspEntity<'_> {
+        EspEntity {
+            obj,
+            color: WHITE,
+            screen_pos: [500.0, 600.0],
+            distance: 10.0,
+            logo: 0,
+            bones: Vec::new(),
+            bone_thickness: 1.0,
+            bone_joints: false,
+            visible: None,
+            bone_visible: Vec::new(),
+            visible_line: None,
+            box_2d: Some(([480.0, 490.0], [520.0, 600.0])),
+            box_3d: None,
+            health: None,
+            head_pos: Some([500.0, 500.0]),
+        }
+    }
+
+    #[test]
+    fn overlapping_labels_are_pushed_apart() {
+        for (side, dy) in [(Side::Above, -14.0), (Side::Below, 14.0)] {
+            let anchor = Vec2::new(100.0, 100.0);
+            let mut labels = vec![
+                label_at(anchor, side, "a"),
+                label_at(anchor + Vec2::new(5.0, 0.0), side, "b"),
+                label_at(Vec2::new(300.0, 100.0), side, "c"),
+            ];
+
+            layout(&mut labels);
+
+            // 第一个待在原位，第二个沿 side 方向挪开一行加空隙，不重叠的不动
+            assert_eq!(labels[0].pos, labels[0].natural_pos());
+            assert_eq!(labels[1].pos.x, labels[1].natural_pos().x);
+            assert_eq!(labels[1].pos.y, labels[1].natural_pos().y + dy);
+            assert_eq!(labels[2].pos, labels[2].natural_pos());
+            assert!(!labels[0].overlaps(&labels[1]));
+        }
+    }
+
+    #[test]
+    fn stacked_labels_do_not_overlap() {
+        let anchor = Vec2::new(100.0, 100.0);
+        let mut labels: Vec<Label> = (0..5)
+            .map(|i| label_at(anchor, Side::Above, &i.to_string()))
+            .collect();
+
+        layout(&mut labels);
+
+        for i in 0..labels.len() {
+            for j in 0..i {
+                assert!(!labels[i].overlaps(&labels[j]), "{} {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn leader_line_drawn_for_moved_label_at_each_anchor() {
+        let obj = Obj::default();
+        let entity = entity(&obj);
+
+        for anchor in [LabelAnchor::Head, LabelAnchor::Feet, LabelAnchor::BoxTop] {
+            let (pos, side) = label_anchor(&entity, anchor);
+            let mut labels = vec![label_at(pos, side, "a"), label_at(pos, side, "b")];
+
+            layout(&mut labels);
+
+            let mut recorder = Recorder::default();
+            draw_labels(&mut recorder, &labels, true);
+            let cmds = recorder.take();
+
+            // 原位的标签只有文字，被挤开的先画引线再画文字
+            assert_eq!(cmds.len(), 3, "{}", anchor);
+            assert!(matches!(cmds[0], DrawCmd::Text { .. }), "{}", anchor);
+
+            // 引线从锚点连到挪开后标签靠近锚点那条边的中间
+            let dy = match side {
+                Side::Above => -14.0,
+                Side::Below => 14.0,
+            };
+            match &cmds[1] {
+                DrawCmd::Line {
+                    layer, from, to, ..
+                } => {
+                    assert_eq!(*layer, Layer::Background);
+                    assert_eq!(*from, pos.to_array(), "{}", anchor);
+                    assert_eq!(*to, [pos.x, pos.y + dy], "{}", anchor);
+                }
+                cmd => panic!("{} {:?}", anchor, cmd),
+            }
+
+            match &cmds[2] {
+                DrawCmd::Text { pos: text_pos, .. } => {
+                    assert_eq!(*text_pos, labels[1].pos.to_array());
+                }
+                cmd => panic!("{} {:?}", anchor, cmd),
+            }
+
+            // 关掉引线后只剩文字
+            let mut recorder = Recorder::default();
+            draw_labels(&mut recorder, &labels, false);
+            assert!(
+                recorder
+                    .take()
+                    .iter()
+                    .all(|cmd| matches!(cmd, DrawCmd::Text { .. }))
+            );
+        }
+    }
+
+    #[test]
+    fn anchors_pick_expected_side() {
+        let obj = Obj::default();
+        let entity = entity(&obj);
+
+        assert_eq!(
+            label_anchor(&entity, LabelAnchor::Head),
+            (Vec2::new(500.0, 500.0), Side::Above)
+        );
+        assert_eq!(
+            label_anchor(&entity, LabelAnchor::Feet),
+            (Vec2::new(500.0, 600.0), Side::Below)
+        );
+
+        let (pos, side) = label_anchor(&entity, LabelAnchor::BoxTop);
+        assert_eq!(side, Side::Above);
+        assert_eq!(pos.x, 500.0);
+        assert!(pos.y <= 490.0);
+    }
+}
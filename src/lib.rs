@@ -1,33 +1,97 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 #![allow(static_mut_refs)]
 
+mod bone_probe;
+mod clock;
+mod draw;
+mod esp;
+mod exports;
+mod freecam;
+mod health;
+mod imgui_draw;
 mod impls;
+mod items;
+mod label;
+mod math;
+mod offsets;
+mod plugin;
+mod pointer_scan;
+mod process;
+mod radar;
+mod script;
+mod skeleton;
+mod snapshot;
+mod speed;
+mod teleport;
+mod trail;
+mod visibility;
+mod vitals;
+mod waypoint;
+mod world;
 
 use hudhook::{
     imgui::{
         internal::RawCast,
         sys::{ImFontAtlas_AddFontFromFileTTF, ImFontAtlas_GetGlyphRangesChineseFull},
     },
+    mh::{MH_ApplyQueued, MhHook},
     windows::Win32::{
-        Foundation::HWND,
+        Foundation::{FreeLibrary, HMODULE, HWND, LPARAM, LRESULT, WPARAM},
         Graphics::Gdi::ScreenToClient,
-        System::Memory::IsBadReadPtr,
+        System::{
+            LibraryLoader::{GetModuleHandleA, GetProcAddress, LoadLibraryW},
+            Memory::IsBadReadPtr,
+        },
         UI::{
-            Input::KeyboardAndMouse::GetAsyncKeyState,
-            WindowsAndMessaging::{FindWindowA, GetCursorPos},
+            Input::{
+                GetRawInputData, HRAWINPUT, KeyboardAndMouse::GetAsyncKeyState, RAWINPUT,
+                RAWINPUTHEADER, RID_INPUT, RIM_TYPEKEYBOARD,
+            },
+            WindowsAndMessaging::{
+                CallWindowProcW, DefWindowProcW, FindWindowA, GWLP_WNDPROC, GetCursorPos,
+                SetWindowLongPtrW, WM_INPUT, WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
+                WNDPROC,
+            },
         },
     },
 };
 
+use bone_probe::{ProbePoint, ProbeState};
+use clock::{ClockRate, NightRange};
+use draw::{Draw, Layer};
+use esp::{BoxMode, EspEntity, EspFlags};
+use exports::{CallArg, CallResult, Export, ExportShape, ReturnType, ScalarType, ThisObject};
+use freecam::FreeCamInput;
+use health::HealthTracker;
+use imgui_draw::{ImguiDraw, ImguiPluginUi};
 use impls::{
-    get_bone_joint_pos, get_screen_height, get_screen_width, is_in_frustum, point_to_screen,
-    raytest_to_target,
+    FoundMethod, bone_count_method, clock_methods, get_bone_count, get_bone_joint_pos,
+    get_level_name, get_screen_height, get_screen_width, get_time_of_day, get_time_of_day_speed,
+    is_in_frustum, is_multiplayer, level_name_method, point_to_screen, raytest_to_target,
+    session_method, set_position, set_position_export, set_time_of_day, set_time_of_day_speed,
 };
+use items::{ITEM_CATEGORIES, ITEM_ICONS, ItemStyle};
+use label::{Label, LabelAnchor, Side};
+use math::{Camera, Vec2, Vec3};
+use offsets::{OffsetStatus, Offsets};
+use plugin::{AbiVersionFn, InitFn, PLUGIN_ABI_VERSION, PluginHost, PluginSetting};
+use pointer_scan::{PointerPath, ScanConfig};
+use radar::{EntityCache, RadarConfig, RadarDot, RadarShape};
+use script::{ScriptFrame, ScriptHost};
+use skeleton::{Rig, Skeletons};
+use speed::SpeedClock;
 use std::{
-    f32::consts::PI,
+    collections::HashSet,
     ptr::{null, null_mut},
+    sync::{Arc, Mutex},
     thread::spawn,
 };
+use teleport::TELEPORT_SLOTS;
+use trail::{SavedTrails, Trail};
+use visibility::{Visibility, VisibilityCache};
+use vitals::{Vital, VitalAddr, WriteGate};
+use waypoint::{Waypoint, Waypoints};
+use world::{Array, CGame, MODEL_TYPES, ModelObject, ModelType, Obj, World};
 
 use crate::impls::{get_distance_to, get_position};
 
@@ -49,6 +113,8 @@ static mut GAME_DLL_INFO: libmem::Module = libmem::Module {
 
 static mut CGAME_PP: *const *const CGame = null_mut();
 
+static mut OFFSETS: Offsets = Offsets::new();
+
 const BONE_LIST: [EBones; 15] = [
     EBones::Head,
     EBones::LClavicle,
@@ -67,31 +133,36 @@ const BONE_LIST: [EBones; 15] = [
     EBones::RFoot,
 ];
 
-const BONE_LISTS: &[&[EBones]] = &[
-    &[
-        EBones::Head,
-        EBones::Neck,
-        EBones::Spine3,
-        EBones::Spine2,
-        EBones::Spine1,
-        EBones::Pelvis,
-    ],
-    &[
-        EBones::Neck,
-        EBones::LUpperarm,
-        EBones::LForearm,
-        EBones::LHand,
-    ],
-    &[
-        EBones::Neck,
-        EBones::RUpperarm,
-        EBones::RForearm,
-        EBones::RHand,
-    ],
-    &[EBones::Pelvis, EBones::LThigh, EBones::LCalf, EBones::LFoot],
-    &[EBones::Pelvis, EBones::RThigh, EBones::RCalf, EBones::RFoot],
+const POINTER_SCAN_FILE: &str = "pointer_scan.txt";
+const SKELETON_FILE: &str = "skeletons.txt";
+const WAYPOINT_FILE: &str = "waypoints.txt";
+const VITALS_FILE: &str = "vitals.txt";
+const TRAIL_FILE: &str = "trails.txt";
+// 指针扫描的快照是在游戏进程里分配的，太大会把游戏拖死
+const PTR_SCAN_MAX_SNAPSHOT_MB: i32 = 1024;
+const SCRIPT_DIR: &str = "scripts";
+const PLUGIN_DIR: &str = "plugins";
+
+// 热键可选的按键，0 是不用热键
+const HOTKEYS: [(i32, &str); 13] = [
+    (0x0, "无"),
+    (0x70, "F1"),
+    (0x71, "F2"),
+    (0x72, "F3"),
+    (0x73, "F4"),
+    (0x74, "F5"),
+    (0x75, "F6"),
+    (0x76, "F7"),
+    (0x77, "F8"),
+    (0x78, "F9"),
+    (0x79, "F10"),
+    (0x7A, "F11"),
+    (0x7B, "F12"),
 ];
 
+// 会话状态之外多一道保险，最近见过别的玩家也当作联机
+const OTHER_PLAYER_TIMEOUT: f64 = 60.0;
+
 const NOP_8: [u8; 8] = [0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90];
 const PITCH_ORIGINAL: [u8; 8] = [0xF3, 0x0F, 0x11, 0x83, 0x78, 0x11, 0x00, 0x00];
 const YAW_ORIGINAL: [u8; 8] = [0xF3, 0x0F, 0x11, 0xB3, 0x74, 0x11, 0x00, 0x00];
@@ -110,36 +181,6 @@ impl<T> Ptr for *mut T {
     }
 }
 
-#[repr(C)]
-#[derive(Default, PartialEq, Debug, Clone, Copy)]
-enum ModelType {
-    ZombieNormal,
-    ZombieSpecial,
-    ZombieHunter,
-    SurvivorNormal,
-    SurvivorSpecial,
-    SurvivorShopkeeper,
-    PlayerHuman,
-    PlayerHunter,
-    #[default]
-    Other,
-}
-impl std::fmt::Display for ModelType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ModelType::ZombieNormal => write!(f, "丧尸"),
-            ModelType::ZombieSpecial => write!(f, "特感"),
-            ModelType::ZombieHunter => write!(f, "夜魔"),
-            ModelType::SurvivorNormal => write!(f, "NPC"),
-            ModelType::SurvivorShopkeeper => write!(f, "商贩"),
-            ModelType::SurvivorSpecial => write!(f, "强盗"),
-            ModelType::PlayerHuman => write!(f, "人类"),
-            ModelType::PlayerHunter => write!(f, "猎手"),
-            ModelType::Other => write!(f, "其他"),
-        }
-    }
-}
-
 #[allow(dead_code)]
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
 #[repr(u8)]
@@ -222,103 +263,22 @@ impl std::fmt::Display for AimKeys {
     }
 }
 
-#[derive(Debug, Default)]
-struct World {
-    game_p: *const CGame,
-    game_di_p: *const GameDI,
-    session_cooperative_di_p: *const SessionCooperativeDI,
-    level_di_p: *const LevelDI,
-    c_level_p: *const CLevel,
-    local_client_di_p: *const LocalClientDI,
-    player_di_p: *mut PlayerDI,
-    player_c_model_obj_p: *const CModelObject,
-    camera_manage_di_p: *const CameraManagerDI,
-    camera_fpp_di_p: *const CameraFPPDI,
-    player_world_pos_p: *mut Vec3<f32>,
-    camera_angle_p: *mut Vec2<f32>,
-}
-
-static mut WORLD_MODEL_OBJ_ARRAY: Array<*const ModelObject> = Array {
-    ptr: null(),
-    len: 0,
-    max: 0,
-};
-
-#[derive(Debug, Default, Clone)]
-struct Obj {
-    model_obj_p: *const ModelObject,
-    c_model_obj_p: *const CModelObject,
-    c_model_obj_logo_p: *const u32,
-    c_model_obj_world_pos: Vec3<f32>,
-    model_obj_health_p: *mut f32,
-    model_obj_str_p: *const i8,
-    model_obj_str: String,
-    model_obj_type: ModelType,
-}
-
-#[derive(Debug, Default)]
-#[repr(C)]
-struct Array<T> {
-    ptr: *const T,
-    len: u32,
-    max: u32,
-}
+static mut WORLD_MODEL_OBJ_ARRAY: Array<*const ModelObject> = Array::new();
 
-unsafe impl Send for Array<*const ModelObject> {}
-unsafe impl Sync for Array<*const ModelObject> {}
+static mut KNOWN_MODEL_OBJS: EntityCache = EntityCache::new();
 
-#[derive(Clone, Copy, Default)]
-#[repr(C)]
-struct Vec2<T> {
-    x: T,
-    y: T,
-}
+// 游戏速度: hook QueryPerformanceCounter，游戏的计时器都是从它来的
+static mut SPEED_HOOK: Option<MhHook> = None;
+static SPEED_CLOCK: Mutex<SpeedClock> = Mutex::new(SpeedClock::new());
 
-#[derive(Clone, Copy, Default, Debug)]
-#[repr(C)]
-struct Vec3<T> {
-    x: T,
-    y: T,
-    z: T,
+#[derive(Debug, Default)]
+struct PointerScanState {
+    is_running: bool,
+    status: String,
+    paths: Vec<PointerPath>,
 }
 
-#[repr(C)]
-struct CGame;
-
-#[repr(C)]
-struct GameDI;
-
-#[repr(C)]
-struct ModelObject;
-
-#[repr(C)]
-struct CModelObject;
-
-#[repr(C)]
-struct SessionCooperativeDI;
-
-#[repr(C)]
-struct LevelDI;
-
-#[repr(C)]
-struct CLevel;
-
-#[repr(C)]
-struct LocalClientDI;
-
-#[repr(C)]
-struct PlayerDI;
-
-#[repr(C)]
-struct CameraManagerDI;
-
-#[repr(C)]
-struct CameraFPPDI;
-
-#[repr(C)]
-struct HealthModule;
-
-#[derive(Debug, Clone)]
+// 不能 Clone: ScriptHost 里的 Rc 只能有一个主人，PluginHost 见 plugin.rs
 struct Game {
     game_window: HWND,
 
@@ -342,6 +302,47 @@ struct Game {
     toggle_draw_model_obj_p: bool,
     toggle_draw_model_obj_p_array: bool,
     toggle_draw_world_data: bool,
+    toggle_draw_offscreen: bool,
+    offscreen_max_range: f32,
+
+    item_toggle: bool,
+    item_draw_preset: bool,
+    item_styles: [ItemStyle; ITEM_CATEGORIES.len()],
+
+    visibility_toggle: bool,
+    visibility_per_bone: bool,
+    visibility_max_age: i32,
+    visibility_budget: i32,
+    visibility_cache: VisibilityCache,
+    color_visible: [f32; 4],
+    color_occluded: [f32; 4],
+
+    box_mode: BoxMode,
+    box_thickness: f32,
+    box_fill_alpha: f32,
+    box_categories: [bool; MODEL_TYPES.len()],
+
+    lod_toggle: bool,
+    lod_near: f32,
+    lod_mid: f32,
+    lod_fade_start: f32,
+    lod_max_distances: [f32; MODEL_TYPES.len()],
+
+    label_anchor: LabelAnchor,
+    label_leader_lines: bool,
+
+    skeletons: Skeletons,
+    skeleton_fallback: String,
+    skeleton_status: String,
+
+    toggle_draw_health_bar: bool,
+    toggle_draw_health_text: bool,
+    health_tracker: HealthTracker,
+
+    radar_toggle: bool,
+    radar_shape: RadarShape,
+    radar_range: f32,
+    radar_rings: i32,
 
     aim_vk_code: i32,
     aim_is_key_down: bool,
@@ -374,6 +375,151 @@ struct Game {
     color_player_human: [f32; 4],
     color_player_hunter: [f32; 4],
     color_other: [f32; 4],
+
+    ptr_scan_target: String,
+    ptr_scan_max_depth: i32,
+    ptr_scan_max_offset: String,
+    ptr_scan_max_snapshot_mb: i32,
+    ptr_scan_state: Arc<Mutex<PointerScanState>>,
+
+    snapshot_depth: i32,
+    snapshot_max_mb: i32,
+    snapshot_status: Arc<Mutex<String>>,
+
+    projection_use_rust: bool,
+    projection_fov: f32,
+    projection_fit_fov: bool,
+    projection_error: f32,
+
+    // 离准星最近的实体，每帧更新
+    crosshair_target: usize,
+
+    // 所有写游戏内存的功能都要先打开这个
+    write_enable: bool,
+    // 引擎会话里是不是联机，读不到时是 None
+    session_multiplayer: Option<bool>,
+    other_player_seen: Option<f64>,
+    // (SessionCooperativeDI, LevelDI)，变了说明换了地图
+    world_key: Option<(usize, usize)>,
+
+    teleport_slots: [Option<Vec3<f32>>; TELEPORT_SLOTS],
+    teleport_slot: usize,
+    teleport_save_vk_code: i32,
+    teleport_save_key_down: bool,
+    teleport_load_vk_code: i32,
+    teleport_load_key_down: bool,
+    teleport_step: f32,
+    teleport_step_vk_code: i32,
+    teleport_step_key_down: bool,
+    teleport_coord: String,
+    teleport_status: String,
+
+    freecam_toggle: bool,
+    freecam_active: bool,
+    freecam_speed: f32,
+    freecam_vk_code: i32,
+    freecam_key_down: bool,
+    freecam_pos: Vec3<f32>,
+    // 进入自由视角前的状态，退出时写回去
+    freecam_player_pos: Vec3<f32>,
+    // 进入时的 PlayerDI，变了说明存下的坐标不是这个玩家的
+    freecam_player_di: usize,
+    // 进入时的相机位置，退出时写回
+    freecam_camera_pos: Vec3<f32>,
+    freecam_angle: Vec2<f32>,
+    freecam_status: String,
+
+    // 第一个固定是血量，后面是从 VITALS_FILE 读的
+    vitals: Vec<Vital>,
+    vital_name: String,
+    vital_base: usize,
+    vital_offset: String,
+    vital_status: String,
+
+    clock_hour: Option<f32>,
+    clock_rate: ClockRate,
+    // 引擎说的是不是晚上，导出表里没有对应函数时是 None
+    clock_night: Option<bool>,
+    clock_night_range: NightRange,
+    clock_overlay: bool,
+    clock_input: String,
+    clock_speed: f32,
+    // 改流速前的值，恢复或者不能写入时写回去
+    clock_original_speed: Option<f32>,
+    clock_status: String,
+
+    speed_toggle: bool,
+    speed_scale: f32,
+    speed_vk_code: i32,
+    speed_key_down: bool,
+    speed_slower_vk_code: i32,
+    speed_slower_key_down: bool,
+    speed_faster_vk_code: i32,
+    speed_faster_key_down: bool,
+    speed_status: String,
+
+    trail: Trail,
+    trail_record: bool,
+    trail_draw: bool,
+    trail_interval: f32,
+    trail_max_points: i32,
+    trail_thickness: f32,
+    trail_color_new: [f32; 4],
+    trail_color_old: [f32; 4],
+    trail_compare_color: [f32; 4],
+    // 录的时候所在的地图，换地图时清空
+    trail_map: String,
+    trails: SavedTrails,
+    trail_shown: Vec<String>,
+    trail_name: String,
+    trail_status: String,
+
+    script_host: ScriptHost,
+    script_toggle: bool,
+    script_last_scan: f64,
+    script_status: String,
+
+    plugin_host: PluginHost,
+    plugin_status: String,
+
+    export_list: Vec<Export>,
+    export_filter: String,
+    export_callable_only: bool,
+    export_selected: Option<usize>,
+    export_this: ThisObject,
+    export_this_addr: String,
+    // this 和方法的类对不上时要手动确认
+    export_this_override: bool,
+    export_arg: String,
+    export_results: Vec<String>,
+    export_status: String,
+
+    waypoints: Waypoints,
+    waypoint_toggle: bool,
+    waypoint_color: [f32; 4],
+    waypoint_max_range: f32,
+    waypoint_name: String,
+    waypoint_vk_code: i32,
+    waypoint_key_down: bool,
+    waypoint_level_p: usize,
+    waypoint_level_name: String,
+    // 地图名来自引擎导出时是 true，在 CLevel 里猜的是 false
+    waypoint_level_verified: bool,
+    waypoint_map_override: String,
+    waypoint_status: String,
+
+    bone_probe_toggle: bool,
+    bone_probe_max: i32,
+    bone_probe_locked: usize,
+    bone_probe_target: usize,
+    bone_probe_type: ModelType,
+    bone_probe_points: Vec<ProbePoint>,
+    bone_probe_names: Vec<String>,
+    bone_probe_rig_name: String,
+    bone_probe_bind_type: bool,
+    bone_probe_status: String,
+    // 目标的骨骼数，导出表里没有骨骼数函数时是 None
+    bone_probe_count: Option<u32>,
 }
 
 impl Default for Game {
@@ -423,6 +569,47 @@ impl Default for Game {
             toggle_draw_model_obj_p: false,
             toggle_draw_model_obj_p_array: false,
             toggle_draw_world_data: false,
+            toggle_draw_offscreen: false,
+            offscreen_max_range: 100.0,
+
+            item_toggle: false,
+            item_draw_preset: false,
+            item_styles: items::default_styles(),
+
+            visibility_toggle: false,
+            visibility_per_bone: false,
+            visibility_max_age: 10,
+            visibility_budget: 32,
+            visibility_cache: VisibilityCache::default(),
+            color_visible: [0.0, 1.0, 0.0, 1.0],  // 绿色
+            color_occluded: [1.0, 0.5, 0.0, 1.0], // 橙色
+
+            box_mode: BoxMode::Off,
+            box_thickness: 1.5,
+            box_fill_alpha: 0.0,
+            box_categories: [true; MODEL_TYPES.len()],
+
+            lod_toggle: false,
+            lod_near: 30.0,
+            lod_mid: 80.0,
+            lod_fade_start: 0.75,
+            lod_max_distances: [300.0; MODEL_TYPES.len()],
+
+            label_anchor: LabelAnchor::Feet,
+            label_leader_lines: false,
+
+            skeletons: Skeletons::builtin(),
+            skeleton_fallback: "humanoid".to_string(),
+            skeleton_status: String::new(),
+
+            toggle_draw_health_bar: false,
+            toggle_draw_health_text: false,
+            health_tracker: HealthTracker::default(),
+
+            radar_toggle: false,
+            radar_shape: RadarShape::Circle,
+            radar_range: 100.0,
+            radar_rings: 3,
 
             aim_toggle: false,
             aim_toggle_draw_fov: false,
@@ -446,10 +633,144 @@ impl Default for Game {
             color_player_human: [0.0, 1.0, 0.0, 1.0], // 绿色
             color_player_hunter: [1.0, 0.0, 0.0, 1.0], // 红色
             color_other: [1.0, 0.0, 0.0, 1.0],        // 白色
+
+            ptr_scan_target: String::new(),
+            ptr_scan_max_depth: ScanConfig::default().max_depth as i32,
+            ptr_scan_max_offset: format!("{:X}", ScanConfig::default().max_offset),
+            ptr_scan_max_snapshot_mb: 256,
+            ptr_scan_state: Arc::new(Mutex::new(PointerScanState::default())),
+
+            snapshot_depth: 1,
+            snapshot_max_mb: 256,
+            snapshot_status: Arc::new(Mutex::new(String::new())),
+
+            projection_use_rust: false,
+            projection_fov: 60.0,
+            projection_fit_fov: false,
+            projection_error: 0.0,
+
+            crosshair_target: 0,
+
+            write_enable: false,
+            session_multiplayer: None,
+            other_player_seen: None,
+            world_key: None,
+
+            teleport_slots: [None; TELEPORT_SLOTS],
+            teleport_slot: 0,
+            teleport_save_vk_code: 0x78,
+            teleport_save_key_down: false,
+            teleport_load_vk_code: 0x79,
+            teleport_load_key_down: false,
+            teleport_step: 5.0,
+            teleport_step_vk_code: 0,
+            teleport_step_key_down: false,
+            teleport_coord: String::new(),
+            teleport_status: String::new(),
+
+            freecam_toggle: false,
+            freecam_active: false,
+            freecam_speed: 10.0,
+            freecam_vk_code: 0x75,
+            freecam_key_down: false,
+            freecam_pos: Vec3::default(),
+            freecam_player_pos: Vec3::default(),
+            freecam_player_di: 0,
+            freecam_camera_pos: Vec3::default(),
+            freecam_angle: Vec2::default(),
+            freecam_status: String::new(),
+
+            vitals: vec![Vital::new("血量", VitalAddr::Health)],
+            vital_name: String::new(),
+            vital_base: 0,
+            vital_offset: String::new(),
+            vital_status: String::new(),
+
+            clock_hour: None,
+            clock_rate: ClockRate::default(),
+            clock_night: None,
+            clock_night_range: NightRange::default(),
+            clock_overlay: false,
+            clock_input: "12:00".to_string(),
+            clock_speed: 1.0,
+            clock_original_speed: None,
+            clock_status: String::new(),
+
+            speed_toggle: false,
+            speed_scale: 0.5,
+            speed_vk_code: 0x76,
+            speed_key_down: false,
+            speed_slower_vk_code: 0,
+            speed_slower_key_down: false,
+            speed_faster_vk_code: 0,
+            speed_faster_key_down: false,
+            speed_status: String::new(),
+
+            trail: Trail::default(),
+            trail_record: false,
+            trail_draw: true,
+            trail_interval: 1.0,
+            trail_max_points: 2000,
+            trail_thickness: 2.0,
+            trail_color_new: [0.0, 1.0, 0.5, 1.0],     // 青绿
+            trail_color_old: [0.0, 0.3, 1.0, 1.0],     // 蓝色
+            trail_compare_color: [1.0, 0.0, 1.0, 1.0], // 紫色
+            trail_map: String::new(),
+            trails: SavedTrails::default(),
+            trail_shown: Vec::new(),
+            trail_name: String::new(),
+            trail_status: String::new(),
+
+            script_host: ScriptHost::new(),
+            script_toggle: true,
+            script_last_scan: 0.0,
+            script_status: String::new(),
+
+            plugin_host: PluginHost::new(),
+            plugin_status: String::new(),
+
+            export_list: Vec::new(),
+            export_filter: String::new(),
+            export_callable_only: true,
+            export_selected: None,
+            export_this: ThisObject::GameDI,
+            export_this_addr: String::new(),
+            export_this_override: false,
+            export_arg: String::new(),
+            export_results: Vec::new(),
+            export_status: String::new(),
+
+            waypoints: Waypoints::default(),
+            waypoint_toggle: false,
+            waypoint_color: [1.0, 0.5, 0.0, 1.0], // 橙色
+            waypoint_max_range: 1000.0,
+            waypoint_name: String::new(),
+            waypoint_vk_code: 0x74,
+            waypoint_key_down: false,
+            waypoint_level_p: 0,
+            waypoint_level_name: String::new(),
+            waypoint_level_verified: false,
+            waypoint_map_override: String::new(),
+            waypoint_status: String::new(),
+
+            bone_probe_toggle: false,
+            bone_probe_max: 63,
+            bone_probe_locked: 0,
+            bone_probe_target: 0,
+            bone_probe_type: ModelType::Other,
+            bone_probe_points: Vec::new(),
+            bone_probe_names: vec![String::new(); u8::MAX as usize + 1],
+            bone_probe_rig_name: "probe".to_string(),
+            bone_probe_bind_type: false,
+            bone_probe_status: String::new(),
+            bone_probe_count: None,
         }
     }
 }
 
+// 只在注入线程里创建，交给 hudhook 之后只有渲染线程碰它
+// ScriptHost 里的 Rc 全都在 Game 里面，跟着 Game 一起移动，不会有两个线程同时拿着
+// PluginHost 里的裸指针和插件 DLL 也一样只在渲染线程里用
 unsafe impl Send for Game {}
 
 unsafe impl Sync for Game {}
@@ -461,6 +782,7 @@ impl hudhook::ImguiRenderLoop for Game {
         _: &'a mut dyn hudhook::RenderContext,
     ) {
         self.game_window = FindWindowA(hudhook::windows::core::s!("techland_game_class"), None);
+        hook_wnd_proc(self.game_window);
 
         ImFontAtlas_AddFontFromFileTTF(
             ctx.fonts().raw_mut(),
@@ -472,6 +794,26 @@ impl hudhook::ImguiRenderLoop for Game {
 
         ctx.style_mut().use_light_colors();
         ctx.set_ini_filename(None);
+
+        if std::path::Path::new(SKELETON_FILE).exists() {
+            load_skeletons(self);
+        }
+
+        if std::path::Path::new(WAYPOINT_FILE).exists() {
+            load_waypoints(self);
+        }
+
+        if std::path::Path::new(VITALS_FILE).exists() {
+            load_vitals(self);
+        }
+
+        if std::path::Path::new(TRAIL_FILE).exists() {
+            load_trails(self);
+        }
+
+        if std::path::Path::new(PLUGIN_DIR).exists() {
+            load_plugins(self);
+        }
     }
 
     unsafe fn render(&mut self, ctx: &mut hudhook::imgui::Context) {
@@ -536,11 +878,35 @@ impl hudhook::ImguiRenderLoop for Game {
 unsafe fn on_frame_draw(game: &mut Game, ui: &hudhook::imgui::Ui) {
     let world = match get_world() {
         Some(val) => val,
-        None => return,
+        None => {
+            game.session_multiplayer = None;
+            forget_world(game);
+            return;
+        }
     };
 
+    let world_key = (
+        world.session_cooperative_di_p as usize,
+        world.level_di_p as usize,
+    );
+    if game.world_key != Some(world_key) {
+        forget_world(game);
+        game.world_key = Some(world_key);
+    }
+
+    // 写入开关每帧都看这个，要在所有写内存的功能前面更新
+    game.session_multiplayer = is_multiplayer(world.session_cooperative_di_p);
+
+    // 先移动相机，这一帧的投影就用新的相机位置
+    update_freecam(game, ui, &world);
+
+    let camera = get_camera(game, &world);
+
+    let mut draw = ImguiDraw::new(ui);
+
     if game.toggle_draw_model_obj_p_array {
-        ui.get_background_draw_list().add_text(
+        draw.text(
+            Layer::Background,
             [0.0, 0.0],
             game.color_zombie_normal,
             format!(
@@ -551,7 +917,8 @@ unsafe fn on_frame_draw(game: &mut Game, ui: &hudhook::imgui::Ui) {
     }
 
     if game.toggle_draw_world_data {
-        ui.get_background_draw_list().add_text(
+        draw.text(
+            Layer::Background,
             [0.0, 0.0],
             game.color_zombie_normal,
             format!("{:#?}", world),
@@ -559,35 +926,62 @@ unsafe fn on_frame_draw(game: &mut Game, ui: &hudhook::imgui::Ui) {
     }
 
     if game.aim_toggle_draw_fov {
-        ui.get_background_draw_list()
-            .add_circle(
-                [
-                    get_screen_width(world.game_di_p) as f32 / 2.0,
-                    get_screen_height(world.game_di_p) as f32 / 2.0,
-                ],
-                game.aim_fov,
-                game.color_zombie_normal,
-            )
-            .build();
+        draw.circle(
+            Layer::Background,
+            camera.center().to_array(),
+            game.aim_fov,
+            game.color_zombie_normal,
+            1.0,
+            false,
+        );
     }
 
-    for index in 0..WORLD_MODEL_OBJ_ARRAY.len {
-        let model_obj_pp = WORLD_MODEL_OBJ_ARRAY.ptr.add(index as usize);
-        if model_obj_pp.is_bad_read_ptr(8) {
-            continue;
-        }
+    let flags = EspFlags {
+        model_type_name: game.toggle_draw_model_type_name,
+        bones: game.toggle_draw_bones,
+        visible_line: game.toggle_draw_visible_line,
+        type_data: game.toggle_draw_type_data,
+        logo: game.toggle_draw_logo,
+        model_obj_p: game.toggle_draw_model_obj_p,
+        box_mode: game.box_mode,
+        box_thickness: game.box_thickness,
+        box_fill_alpha: game.box_fill_alpha,
+        health_bar: game.toggle_draw_health_bar,
+        health_text: game.toggle_draw_health_text,
+        dot: false,
+        visibility: game.visibility_toggle,
+        visible_color: game.color_visible,
+        occluded_color: game.color_occluded,
+    };
 
-        let mut model_obj_p = model_obj_pp.read();
-        if model_obj_p.is_bad_read_ptr(8) {
-            continue;
-        }
+    game.visibility_cache
+        .begin_frame(game.visibility_budget as u32);
 
-        model_obj_p = model_obj_p.byte_sub(0x18);
-        if model_obj_p.is_bad_read_ptr(8) {
-            continue;
-        }
+    let now = ui.time();
+    game.health_tracker.forget_old(now);
+
+    let mut projection_error: f32 = 0.0;
+
+    let model_obj_addrs =
+        world::model_obj_addrs(&process::LiveMemory, &OFFSETS, &WORLD_MODEL_OBJ_ARRAY);
+
+    // 不管开没开雷达都要记，雷达和屏幕外箭头都要用到视锥外的实体
+    KNOWN_MODEL_OBJS.extend(model_obj_addrs.iter().copied());
+
+    // 每帧只读一遍，雷达和屏幕外箭头共用
+    let known = match game.radar_toggle || game.toggle_draw_offscreen {
+        true => known_objs(&world),
+        false => Vec::new(),
+    };
+
+    // (距离, 标签)，画完所有实体后再统一排版
+    let mut labels: Vec<(f32, Label)> = Vec::new();
+
+    // 离准星最近的实体 (屏幕距离, model_obj_p)
+    let mut crosshair: (f32, usize) = (f32::MAX, 0);
 
-        let obj = match get_obj(model_obj_p) {
+    for &model_obj_p in &model_obj_addrs {
+        let obj = match get_obj(model_obj_p as *const ModelObject) {
             Some(val) => val,
             None => continue,
         };
@@ -596,65 +990,171 @@ unsafe fn on_frame_draw(game: &mut Game, ui: &hudhook::imgui::Ui) {
             continue;
         }
 
-        if is_in_frustum(obj.model_obj_p) == 0 {
-            continue;
+        if matches!(
+            obj.model_obj_type,
+            ModelType::PlayerHuman | ModelType::PlayerHunter
+        ) {
+            game.other_player_seen = Some(now);
         }
 
-        let (filter, color) = match obj.model_obj_type {
-            ModelType::ZombieNormal => (game.toggle_filter_zombie_normal, game.color_zombie_normal),
-            ModelType::ZombieSpecial => {
-                (game.toggle_filter_zombie_special, game.color_zombie_special)
-            }
-            ModelType::ZombieHunter => (game.toggle_filter_zombie_hunter, game.color_zombie_hunter),
-            ModelType::SurvivorNormal => (
-                game.toggle_filter_survivor_normal,
-                game.color_survivor_nomal,
-            ),
-            ModelType::SurvivorSpecial => (
-                game.toggle_filter_survivor_special,
-                game.color_survivor_special,
-            ),
-            ModelType::SurvivorShopkeeper => (
-                game.toggle_filter_survivor_shopkeeper,
-                game.color_survivor_shopkeeper,
-            ),
-            ModelType::PlayerHuman => (game.toggle_filter_player_human, game.color_player_human),
-            ModelType::PlayerHunter => (game.toggle_filter_player_hunter, game.color_player_hunter),
-            ModelType::Other => (game.toggle_filter_other, game.color_other),
-        };
+        let (filter, color) = model_type_style(game, obj.model_obj_type);
 
         if !filter {
             continue;
         }
 
-        let mut screen_pos: Vec2<f32> = Vec2::default();
-        point_to_screen(
-            world.camera_fpp_di_p,
-            &mut screen_pos,
-            &obj.c_model_obj_world_pos,
-        );
+        let distance = get_distance_to(obj.model_obj_p, world.player_world_pos_p);
+
+        let screen_pos = if is_in_frustum(obj.model_obj_p) != 0 {
+            projection_error = projection_error.max(check_projection(game, &world, &camera, &obj));
+
+            world_to_screen(game, &world, &camera, &obj.c_model_obj_world_pos)
+        } else {
+            None
+        };
+
+        let screen_pos = match screen_pos {
+            Some(val) => val,
+            None => {
+                if game.toggle_draw_offscreen {
+                    esp::draw_offscreen_arrow(
+                        &mut draw,
+                        &camera,
+                        camera.screen_direction(obj.c_model_obj_world_pos),
+                        distance,
+                        game.offscreen_max_range,
+                        color,
+                    );
+                }
+
+                continue;
+            }
+        };
+
+        let center_distance = screen_pos.distance(camera.center());
+        if center_distance < crosshair.0 {
+            crosshair = (center_distance, model_obj_p);
+        }
 
         if game.aim_toggle {
-            aim_update_obj(game, &world, &obj);
+            aim_update_obj(game, &world, &camera, &obj);
         }
 
-        //  ui.get_background_draw_list() 不能 let，否则在下次调用 ui.get_background_draw_list()时会闪退
-        if game.toggle_draw_model_type_name {
-            let data = format!(
-                "{}  {:.2}",
-                obj.model_obj_type,
-                get_distance_to(obj.model_obj_p, world.player_world_pos_p),
-            );
+        let (flags, color) = if game.lod_toggle {
+            let max_distance = game.lod_max_distances[obj.model_obj_type as usize];
+            if distance > max_distance {
+                continue;
+            }
+
+            let mut color = color;
+            color[3] *= esp::fade_alpha(distance, max_distance, game.lod_fade_start);
+
+            (
+                flags.with_lod(esp::lod_for(distance, game.lod_near, game.lod_mid)),
+                color,
+            )
+        } else {
+            (flags, color)
+        };
+
+        let mut entity = EspEntity {
+            obj: &obj,
+            color,
+            screen_pos: screen_pos.to_array(),
+            distance,
+            logo: 0,
+            bones: Vec::new(),
+            bone_thickness: 1.5,
+            bone_joints: false,
+            visible: None,
+            bone_visible: Vec::new(),
+            visible_line: None,
+            box_2d: None,
+            box_3d: None,
+            health: None,
+            head_pos: None,
+        };
+
+        if flags.health_bar || flags.health_text {
+            entity.health = Some(game.health_tracker.update(
+                model_obj_p,
+                obj.model_obj_health_p.read(),
+                now,
+            ));
+        }
+
+        let rig = game
+            .skeletons
+            .rig_for(obj.model_obj_type, &game.skeleton_fallback);
+
+        let visibility = if flags.visibility || flags.visible_line {
+            let bones = match rig {
+                Some(rig) if game.visibility_per_bone => rig.chain_bones(),
+                _ => vec![game.aim_selected_bone as u8],
+            };
+
+            game.visibility_cache
+                .get(
+                    model_obj_p,
+                    game.visibility_max_age as u64,
+                    bones.len() as u32,
+                    || test_visibility(&world, &obj, &bones),
+                )
+                .cloned()
+        } else {
+            None
+        };
+
+        entity.visible = visibility.as_ref().map(|val| val.visible);
+
+        if flags.bones
+            && let Some(rig) = rig
+        {
+            let bone_lists = project_bones(game, &world, &camera, &obj, rig);
 
-            ui.get_background_draw_list()
-                .add_text([screen_pos.x, screen_pos.y], color, data);
+            if game.visibility_per_bone
+                && let Some(visibility) = &visibility
+            {
+                entity.bone_visible = bone_lists
+                    .iter()
+                    .map(|bone_list| {
+                        bone_list
+                            .iter()
+                            .map(|(bone, _)| visibility.bone(*bone))
+                            .collect()
+                    })
+                    .collect();
+            }
+
+            entity.bones = bone_lists
+                .into_iter()
+                .map(|bone_list| bone_list.into_iter().map(|(_, pos)| pos).collect())
+                .collect();
+            entity.bone_thickness = rig.thickness;
+            entity.bone_joints = rig.joints;
         }
 
-        if game.toggle_draw_bones {
-            draw_bones(ui, &world, &obj, color);
+        if flags.box_mode != BoxMode::Off && game.box_categories[obj.model_obj_type as usize] {
+            let bones = bone_world_positions(&obj);
+
+            if flags.box_mode == BoxMode::Box3D {
+                entity.box_3d = project_box_3d(game, &world, &camera, &bones);
+            } else {
+                let points: Vec<[f32; 2]> = bones
+                    .iter()
+                    .filter(|val| !val.is_zero())
+                    .filter_map(|val| world_to_screen(game, &world, &camera, val))
+                    .map(|val| val.to_array())
+                    .collect();
+
+                entity.box_2d = esp::screen_bounds(&points);
+            }
         }
 
-        if game.toggle_draw_visible_line {
+        if flags.visible_line
+            && let Some(visibility) = &visibility
+            && visibility.bone(game.aim_selected_bone as u8)
+        {
             let bone_world_pos: Vec3<f32> = Vec3::default();
             get_bone_joint_pos(
                 obj.model_obj_p,
@@ -662,61 +1162,134 @@ unsafe fn on_frame_draw(game: &mut Game, ui: &hudhook::imgui::Ui) {
                 game.aim_selected_bone as u8,
             );
 
-            if raytest_to_target(
-                obj.model_obj_p,
-                obj.model_obj_p,
-                get_position(world.camera_fpp_di_p),
-                &bone_world_pos,
-                4,
-            ) != 0
-            {
-                let mut bone_screen_pos: Vec2<f32> = Vec2::default();
-                point_to_screen(world.camera_fpp_di_p, &mut bone_screen_pos, &bone_world_pos);
-
-                ui.get_background_draw_list()
-                    .add_line(
-                        [
-                            get_screen_width(world.game_di_p) as f32 / 2.0,
-                            get_screen_height(world.game_di_p) as f32,
-                        ],
-                        [bone_screen_pos.x, bone_screen_pos.y],
-                        color,
-                    )
-                    .thickness(2.0)
-                    .build();
+            if let Some(bone_screen_pos) = world_to_screen(game, &world, &camera, &bone_world_pos) {
+                entity.visible_line = Some((
+                    [camera.width / 2.0, camera.height],
+                    bone_screen_pos.to_array(),
+                ));
             }
         }
 
-        if game.toggle_draw_type_data {
-            ui.get_background_draw_list().add_text(
-                [screen_pos.x, screen_pos.y],
-                color,
-                obj.model_obj_str.as_str(),
-            );
+        if flags.logo {
+            entity.logo = obj.c_model_obj_logo_p.read();
         }
 
-        if game.toggle_draw_logo {
-            ui.get_background_draw_list().add_text(
-                [screen_pos.x, screen_pos.y],
-                color,
-                format!("{:#X?}", obj.c_model_obj_logo_p.read()),
-            );
+        esp::draw_entity(&mut draw, &flags, &entity);
+
+        let lines = esp::entity_lines(&flags, &entity);
+        if lines.is_empty() {
+            continue;
+        }
+
+        if game.label_anchor == LabelAnchor::Head {
+            let head_world_pos: Vec3<f32> = Vec3::default();
+            get_bone_joint_pos(obj.model_obj_p, &head_world_pos, EBones::Head as u8);
+
+            if !head_world_pos.is_zero() {
+                entity.head_pos = world_to_screen(game, &world, &camera, &head_world_pos)
+                    .map(|val| val.to_array());
+            }
+        }
+
+        let (anchor, side) = esp::label_anchor(&entity, game.label_anchor);
+
+        let mut label = Label::new(anchor, side, esp::state_color(&flags, &entity));
+        for line in lines {
+            let size = ui.calc_text_size(&line);
+            label.push(line, size);
         }
 
-        if game.toggle_draw_model_obj_p {
-            ui.get_background_draw_list().add_text(
-                [screen_pos.x, screen_pos.y],
+        labels.push((distance, label));
+    }
+
+    if game.item_toggle {
+        draw_items(
+            game,
+            &mut draw,
+            ui,
+            &world,
+            &camera,
+            &model_obj_addrs,
+            &mut labels,
+        );
+    }
+
+    // 视锥数组里只有看得见的实体，身后的从记住的实体里找
+    if game.toggle_draw_offscreen {
+        let in_view: HashSet<usize> = model_obj_addrs.iter().copied().collect();
+
+        for obj in &known {
+            if in_view.contains(&(obj.model_obj_p as usize)) {
+                continue;
+            }
+
+            let (filter, color) = model_type_style(game, obj.model_obj_type);
+            if !filter {
+                continue;
+            }
+
+            esp::draw_offscreen_arrow(
+                &mut draw,
+                &camera,
+                camera.screen_direction(obj.c_model_obj_world_pos),
+                get_distance_to(obj.model_obj_p, world.player_world_pos_p),
+                game.offscreen_max_range,
                 color,
-                format!(
-                    "model_obj_p: {:p}\nc_model_obj_p: {:p}",
-                    obj.model_obj_p, obj.c_model_obj_p,
-                ),
             );
         }
     }
 
+    labels.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut labels: Vec<Label> = labels.into_iter().map(|(_, label)| label).collect();
+    label::layout(&mut labels);
+    label::draw_labels(&mut draw, &labels, game.label_leader_lines);
+
+    game.projection_error = projection_error;
+    game.crosshair_target = crosshair.1;
+
+    update_waypoint_map(game, &world);
+
+    if key_pressed(game.waypoint_vk_code, &mut game.waypoint_key_down) {
+        add_waypoint(game, world.player_world_pos_p.read());
+    }
+
+    update_teleport_hotkeys(game, &world, now);
+
+    update_vitals(game, &world, now);
+
+    update_clock(game, &world, now);
+
+    update_game_speed(game, now);
+
+    if game.clock_overlay {
+        draw_clock(game, &mut draw, &camera);
+    }
+
+    if game.waypoint_toggle {
+        draw_waypoints(game, &mut draw, &world, &camera);
+    }
+
+    update_trail(game, &world);
+
+    if game.trail_draw {
+        draw_trails(game, &mut draw, &world, &camera);
+    }
+
+    run_scripts(game, &mut draw, &world, &camera, &model_obj_addrs, now);
+
+    if game.bone_probe_toggle {
+        probe_bones(game, &mut draw, ui, &world, &camera);
+    } else {
+        game.bone_probe_points.clear();
+    }
+
+    if game.radar_toggle {
+        draw_radar_window(game, ui, &world, &known);
+    }
+
     if game.aim_toggle {
-        aim_lock_obj(game, &world, game.aim_selected_bone as u8);
+        aim_lock_obj(game, &world, &camera, game.aim_selected_bone as u8);
     }
 }
 
@@ -835,6 +1408,34 @@ unsafe fn on_frame_draw_ui(game: &mut Game, ui: &hudhook::imgui::Ui) {
             &mut game.toggle_draw_visible_line,
         );
 
+        ui.checkbox("可见性颜色##visibility_toggle", &mut game.visibility_toggle);
+        ui.same_line();
+        ui.color_edit4_config("##color_visible", &mut game.color_visible)
+            .inputs(false)
+            .build();
+        ui.same_line();
+        ui.color_edit4_config("##color_occluded", &mut game.color_occluded)
+            .inputs(false)
+            .build();
+        ui.same_line();
+        ui.checkbox("逐骨骼##visibility_per_bone", &mut game.visibility_per_bone);
+
+        if game.visibility_toggle || game.toggle_draw_visible_line {
+            ui.slider(
+                "缓存帧数##visibility_max_age",
+                0,
+                60,
+                &mut game.visibility_max_age,
+            );
+            ui.slider(
+                "每帧检测上限##visibility_budget",
+                1,
+                256,
+                &mut game.visibility_budget,
+            );
+            ui.text(format!("已缓存的实体: {}", game.visibility_cache.len()));
+        }
+
         ui.checkbox(
             "模型名##toggle_draw_type_data",
             &mut game.toggle_draw_type_data,
@@ -857,144 +1458,3115 @@ unsafe fn on_frame_draw_ui(game: &mut Game, ui: &hudhook::imgui::Ui) {
             &mut game.toggle_draw_world_data,
         );
 
-        val.end();
-    }
-
-    if let Some(val) = ui.tab_item("自瞄") {
-        ui.checkbox("开启##toggle_aim", &mut game.aim_toggle);
-
-        if let Some(cb) =
-            ui.begin_combo("按键##aim_selected_key", game.aim_selected_key.to_string())
-        {
-            for current in game.aim_key_list.as_slice() {
-                if game.aim_selected_key == *current {
-                    ui.set_item_default_focus();
-                }
-
-                if ui
-                    .selectable_config(current.to_string())
-                    .selected(game.aim_selected_key == *current)
-                    .build()
-                {
-                    game.aim_selected_key = *current;
-                    game.aim_vk_code = game.aim_selected_key as i32;
-                }
-            }
-            cb.end();
+        for mode in [
+            BoxMode::Off,
+            BoxMode::Box2D,
+            BoxMode::Corners,
+            BoxMode::Box3D,
+        ] {
+            ui.radio_button(
+                format!("{}##box_mode_{:?}", mode, mode),
+                &mut game.box_mode,
+                mode,
+            );
+            ui.same_line();
         }
+        ui.new_line();
 
-        if let Some(cb) = ui.begin_combo(
-            "部位##aim_selected_bone",
-            game.aim_selected_bone.to_string(),
-        ) {
-            for current in BONE_LIST.as_slice() {
-                if game.aim_selected_bone == *current {
-                    ui.set_item_default_focus();
-                }
+        ui.slider("方框粗细##box_thickness", 1.0, 4.0, &mut game.box_thickness);
+        ui.slider(
+            "填充透明度##box_fill_alpha",
+            0.0,
+            1.0,
+            &mut game.box_fill_alpha,
+        );
 
-                if ui
-                    .selectable_config(current.to_string())
-                    .selected(game.aim_selected_bone == *current)
-                    .build()
-                {
-                    game.aim_selected_bone = *current;
-                }
-            }
-            cb.end();
+        for (model_type, toggle) in MODEL_TYPES.iter().zip(game.box_categories.iter_mut()) {
+            ui.checkbox(
+                format!("{}##box_category_{:?}", model_type, model_type),
+                toggle,
+            );
+            ui.same_line();
+        }
+        ui.new_line();
+
+        ui.text("标签位置");
+        for anchor in [LabelAnchor::Head, LabelAnchor::Feet, LabelAnchor::BoxTop] {
+            ui.same_line();
+            ui.radio_button(
+                format!("{}##label_anchor_{:?}", anchor, anchor),
+                &mut game.label_anchor,
+                anchor,
+            );
         }
-
-        ui.checkbox("FOV##toggle_aim_fov", &mut game.aim_toggle_draw_fov);
         ui.same_line();
-        ui.slider("##aim_fov", 50.0, 500.0, &mut game.aim_fov);
+        ui.checkbox("引线##label_leader_lines", &mut game.label_leader_lines);
 
         ui.checkbox(
-            "丧尸##toggle_aim_filter_zombie_normal",
-            &mut game.aim_toggle_filter_zombie_normal,
+            "血条##toggle_draw_health_bar",
+            &mut game.toggle_draw_health_bar,
         );
-
+        ui.same_line();
         ui.checkbox(
-            "特感##toggle_aim_filter_zombie_special",
-            &mut game.aim_toggle_filter_zombie_special,
+            "血量##toggle_draw_health_text",
+            &mut game.toggle_draw_health_text,
         );
 
-        ui.checkbox(
-            "夜魔##aim_toggle_filter_zombie_hunter",
-            &mut game.aim_toggle_filter_zombie_hunter,
-        );
+        ui.checkbox("距离分级##lod_toggle", &mut game.lod_toggle);
+        if game.lod_toggle {
+            ui.slider("骨骼距离##lod_near", 5.0, 200.0, &mut game.lod_near);
+            ui.slider("方框距离##lod_mid", 5.0, 500.0, &mut game.lod_mid);
+            ui.slider(
+                "开始淡出##lod_fade_start",
+                0.0,
+                1.0,
+                &mut game.lod_fade_start,
+            );
 
-        ui.checkbox(
-            "强盗##toggle_aim_filter_survivor_special",
-            &mut game.aim_toggle_filter_survivor_special,
-        );
+            for (model_type, max_distance) in
+                MODEL_TYPES.iter().zip(game.lod_max_distances.iter_mut())
+            {
+                ui.slider(
+                    format!("{}最远##lod_max_distance_{:?}", model_type, model_type),
+                    10.0,
+                    1000.0,
+                    max_distance,
+                );
+            }
+        }
 
         ui.checkbox(
-            "人类##toggle_aim_filter_player_human",
-            &mut game.aim_toggle_filter_player_human,
+            "屏幕外指示##toggle_draw_offscreen",
+            &mut game.toggle_draw_offscreen,
         );
-
-        ui.checkbox(
+        ui.same_line();
+        ui.slider(
+            "范围##offscreen_max_range",
+            10.0,
+            500.0,
+            &mut game.offscreen_max_range,
+        );
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("玩家") {
+        on_frame_draw_ui_player(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("时间") {
+        on_frame_draw_ui_clock(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("速度") {
+        on_frame_draw_ui_speed(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("物品") {
+        on_frame_draw_ui_items(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("路点") {
+        on_frame_draw_ui_waypoints(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("轨迹") {
+        on_frame_draw_ui_trail(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("脚本") {
+        on_frame_draw_ui_scripts(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("插件") {
+        on_frame_draw_ui_plugins(game, ui);
+
+        val.end();
+    }
+
+    // 每个有菜单的插件一页
+    for index in 0..game.plugin_host.plugins.len() {
+        let plugin = &game.plugin_host.plugins[index];
+        if !plugin.enabled || !plugin.has_ui() {
+            continue;
+        }
+
+        if let Some(val) = ui.tab_item(format!("{}##plugin_tab_{}", plugin.name, index)) {
+            let _id = ui.push_id_usize(index);
+            game.plugin_host.run_ui(index, &mut ImguiPluginUi::new(ui));
+
+            val.end();
+        }
+    }
+
+    if let Some(val) = ui.tab_item("传送") {
+        on_frame_draw_ui_teleport(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("自由视角") {
+        on_frame_draw_ui_freecam(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("自瞄") {
+        ui.checkbox("开启##toggle_aim", &mut game.aim_toggle);
+
+        if let Some(cb) =
+            ui.begin_combo("按键##aim_selected_key", game.aim_selected_key.to_string())
+        {
+            for current in game.aim_key_list.as_slice() {
+                if game.aim_selected_key == *current {
+                    ui.set_item_default_focus();
+                }
+
+                if ui
+                    .selectable_config(current.to_string())
+                    .selected(game.aim_selected_key == *current)
+                    .build()
+                {
+                    game.aim_selected_key = *current;
+                    game.aim_vk_code = game.aim_selected_key as i32;
+                }
+            }
+            cb.end();
+        }
+
+        if let Some(cb) = ui.begin_combo(
+            "部位##aim_selected_bone",
+            game.aim_selected_bone.to_string(),
+        ) {
+            for current in BONE_LIST.as_slice() {
+                if game.aim_selected_bone == *current {
+                    ui.set_item_default_focus();
+                }
+
+                if ui
+                    .selectable_config(current.to_string())
+                    .selected(game.aim_selected_bone == *current)
+                    .build()
+                {
+                    game.aim_selected_bone = *current;
+                }
+            }
+            cb.end();
+        }
+
+        ui.checkbox("FOV##toggle_aim_fov", &mut game.aim_toggle_draw_fov);
+        ui.same_line();
+        ui.slider("##aim_fov", 50.0, 500.0, &mut game.aim_fov);
+
+        ui.checkbox(
+            "丧尸##toggle_aim_filter_zombie_normal",
+            &mut game.aim_toggle_filter_zombie_normal,
+        );
+
+        ui.checkbox(
+            "特感##toggle_aim_filter_zombie_special",
+            &mut game.aim_toggle_filter_zombie_special,
+        );
+
+        ui.checkbox(
+            "夜魔##aim_toggle_filter_zombie_hunter",
+            &mut game.aim_toggle_filter_zombie_hunter,
+        );
+
+        ui.checkbox(
+            "强盗##toggle_aim_filter_survivor_special",
+            &mut game.aim_toggle_filter_survivor_special,
+        );
+
+        ui.checkbox(
+            "人类##toggle_aim_filter_player_human",
+            &mut game.aim_toggle_filter_player_human,
+        );
+
+        ui.checkbox(
             "猎手##toggle_aim_filter_player_hunter",
             &mut game.aim_toggle_filter_player_hunter,
         );
 
-        val.end();
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("骨架") {
+        on_frame_draw_ui_skeletons(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("骨骼探测") {
+        on_frame_draw_ui_bone_probe(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("雷达") {
+        on_frame_draw_ui_radar(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("投影") {
+        on_frame_draw_ui_projection(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("偏移") {
+        on_frame_draw_ui_offsets(ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("指针扫描") {
+        on_frame_draw_ui_pointer_scan(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("导出") {
+        on_frame_draw_ui_exports(game, ui);
+
+        val.end();
+    }
+
+    if let Some(val) = ui.tab_item("快照") {
+        on_frame_draw_ui_snapshot(game, ui);
+
+        val.end();
+    }
+}
+
+unsafe fn on_frame_draw_ui_snapshot(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.input_int("指针层数##snapshot_depth", &mut game.snapshot_depth)
+        .build();
+    game.snapshot_depth = game.snapshot_depth.clamp(0, 4);
+
+    ui.input_int("上限(MB)##snapshot_max_mb", &mut game.snapshot_max_mb)
+        .build();
+    game.snapshot_max_mb = game.snapshot_max_mb.max(16);
+
+    if ui.button("抓取##snapshot_capture") {
+        match get_world() {
+            Some(world) => {
+                // 在渲染线程里抓取，保证是同一帧的数据，写文件放到后台
+                let snapshot = process::capture_world(
+                    &world,
+                    &raw const WORLD_MODEL_OBJ_ARRAY,
+                    CGAME_PP as usize,
+                    game.snapshot_depth as usize,
+                    game.snapshot_max_mb as usize * 1024 * 1024,
+                );
+
+                let status = game.snapshot_status.clone();
+
+                spawn(move || {
+                    let secs = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    let path = format!("snapshot_{}.dlsnap", secs);
+
+                    let result = std::fs::File::create(&path).and_then(|file| {
+                        let mut writer = std::io::BufWriter::new(file);
+                        snapshot.write_to(&mut writer)?;
+                        std::io::Write::flush(&mut writer)
+                    });
+
+                    *status.lock().unwrap() = match result {
+                        Ok(_) => format!(
+                            "已保存 {} ({} MB)",
+                            path,
+                            snapshot.total_bytes() / 1024 / 1024
+                        ),
+                        Err(err) => format!("保存失败: {}", err),
+                    };
+                });
+            }
+            None => *game.snapshot_status.lock().unwrap() = "World 无效".to_string(),
+        }
+    }
+
+    ui.text(game.snapshot_status.lock().unwrap().as_str());
+}
+
+unsafe fn on_frame_draw_ui_items(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox("开启##item_toggle", &mut game.item_toggle);
+    ui.same_line();
+    ui.checkbox("模型名##item_draw_preset", &mut game.item_draw_preset);
+
+    for (category, style) in ITEM_CATEGORIES.iter().zip(game.item_styles.iter_mut()) {
+        ui.separator();
+
+        ui.checkbox(
+            format!("{}##item_enabled_{:?}", category, category),
+            &mut style.enabled,
+        );
+        ui.same_line();
+        ui.color_edit4_config(format!("##item_color_{:?}", category), &mut style.color)
+            .inputs(false)
+            .build();
+
+        for icon in ITEM_ICONS {
+            ui.same_line();
+            ui.radio_button(
+                format!("{}##item_icon_{:?}_{:?}", icon, category, icon),
+                &mut style.icon,
+                icon,
+            );
+        }
+
+        ui.slider(
+            format!("最远##item_max_distance_{:?}", category),
+            5.0,
+            500.0,
+            &mut style.max_distance,
+        );
+    }
+}
+
+unsafe fn on_frame_draw_ui_waypoints(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox("显示##waypoint_toggle", &mut game.waypoint_toggle);
+    ui.same_line();
+    ui.color_edit4_config("##waypoint_color", &mut game.waypoint_color)
+        .inputs(false)
+        .build();
+    ui.same_line();
+    hotkey_combo(ui, "热键##waypoint_vk_code", &mut game.waypoint_vk_code);
+
+    ui.slider(
+        "屏幕外指示范围##waypoint_max_range",
+        10.0,
+        5000.0,
+        &mut game.waypoint_max_range,
+    );
+
+    ui.text(format!("当前地图: {}", waypoint_map(game)));
+    if !game.waypoint_level_name.is_empty() && game.waypoint_map_override.trim().is_empty() {
+        ui.same_line();
+        match level_name_method() {
+            Some(method) if game.waypoint_level_verified => ui.text_disabled(format!(
+                "(来自 {}::{})",
+                method.shape.class, method.shape.method
+            )),
+            _ => ui.text_colored(
+                [0.8, 0.5, 0.0, 1.0],
+                "(未确认: 在 CLevel 里猜的路径，不对时填手动地图名)",
+            ),
+        }
+    }
+    ui.input_text(
+        "手动地图名##waypoint_map_override",
+        &mut game.waypoint_map_override,
+    )
+    .hint(&game.waypoint_level_name)
+    .build();
+
+    ui.input_text("名字##waypoint_name", &mut game.waypoint_name)
+        .build();
+
+    if let Some(world) = get_world() {
+        if ui.button("在脚下添加##waypoint_add_player") {
+            add_waypoint(game, world.player_world_pos_p.read());
+        }
+
+        ui.same_line();
+        if ui.button("在准星目标处添加##waypoint_add_target")
+            && let Some(obj) = get_obj(game.crosshair_target as *const ModelObject)
+        {
+            add_waypoint(game, obj.c_model_obj_world_pos);
+        }
+    }
+
+    ui.same_line();
+    if ui.button("读取##waypoint_load") {
+        load_waypoints(game);
+    }
+
+    ui.same_line();
+    if ui.button("保存##waypoint_save") {
+        save_waypoints(game);
+    }
+
+    ui.text(&game.waypoint_status);
+    ui.separator();
+
+    let map = waypoint_map(game);
+    let mut remove = None;
+
+    for (index, waypoint) in game.waypoints.get_mut(&map).iter_mut().enumerate() {
+        ui.set_next_item_width(200.0);
+        ui.input_text(format!("##waypoint_name_{}", index), &mut waypoint.name)
+            .build();
+        ui.same_line();
+        ui.text(format!(
+            "({:.1}, {:.1}, {:.1})",
+            waypoint.pos.x, waypoint.pos.y, waypoint.pos.z
+        ));
+        ui.same_line();
+        if ui.button(format!("删除##waypoint_remove_{}", index)) {
+            remove = Some(index);
+        }
+    }
+
+    if let Some(index) = remove {
+        game.waypoints.get_mut(&map).remove(index);
+        save_waypoints(game);
+    }
+}
+
+unsafe fn on_frame_draw_ui_teleport(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    let now = ui.time();
+
+    write_enable_checkbox(game, ui, now);
+
+    let world = match get_world() {
+        Some(val) => val,
+        None => return,
+    };
+
+    let player_world_pos = world.player_world_pos_p.read();
+    ui.text(format!(
+        "当前位置: {:.2} {:.2} {:.2}",
+        player_world_pos.x, player_world_pos.y, player_world_pos.z
+    ));
+
+    ui.separator();
+
+    for index in 0..TELEPORT_SLOTS {
+        let text = match game.teleport_slots[index] {
+            Some(val) => format!("{}: {:.1} {:.1} {:.1}", index + 1, val.x, val.y, val.z),
+            None => format!("{}: 空", index + 1),
+        };
+
+        ui.radio_button(
+            format!("{}##teleport_slot_{}", text, index),
+            &mut game.teleport_slot,
+            index,
+        );
+    }
+
+    if ui.button("保存到槽位##teleport_slot_save") {
+        save_teleport_slot(game, &world);
+    }
+    ui.same_line();
+    if ui.button("传送到槽位##teleport_slot_load") {
+        load_teleport_slot(game, &world, now);
+    }
+
+    hotkey_combo(
+        ui,
+        "保存热键##teleport_save_vk_code",
+        &mut game.teleport_save_vk_code,
+    );
+    hotkey_combo(
+        ui,
+        "传送热键##teleport_load_vk_code",
+        &mut game.teleport_load_vk_code,
+    );
+
+    ui.separator();
+
+    ui.input_text("坐标##teleport_coord", &mut game.teleport_coord)
+        .hint("x y z")
+        .build();
+    ui.same_line();
+    if ui.button("传送##teleport_coord_go") {
+        match teleport::parse_coord(&game.teleport_coord) {
+            Some(pos) => teleport_to(game, &world, pos, now),
+            None => game.teleport_status = "坐标格式是 x y z".to_string(),
+        }
+    }
+
+    ui.slider("距离##teleport_step", 1.0, 100.0, &mut game.teleport_step);
+    ui.same_line();
+    if ui.button("向前##teleport_step_go") {
+        step_forward(game, &world, now);
+    }
+    hotkey_combo(
+        ui,
+        "向前热键##teleport_step_vk_code",
+        &mut game.teleport_step_vk_code,
+    );
+
+    if ui.button("传送到准星目标##teleport_target")
+        && let Some(obj) = get_obj(game.crosshair_target as *const ModelObject)
+    {
+        let pos = teleport::approach(player_world_pos, obj.c_model_obj_world_pos);
+        teleport_to(game, &world, pos, now);
+    }
+
+    let map = waypoint_map(game);
+    let waypoints = game.waypoints.get(&map).to_vec();
+    for (index, waypoint) in waypoints.iter().enumerate() {
+        if ui.button(format!("{}##teleport_waypoint_{}", waypoint.name, index)) {
+            teleport_to(game, &world, waypoint.pos, now);
+        }
+        ui.same_line();
+    }
+    ui.new_line();
+
+    ui.text(&game.teleport_status);
+}
+
+unsafe fn on_frame_draw_ui_freecam(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox("开启##freecam_toggle", &mut game.freecam_toggle);
+    ui.same_line();
+    hotkey_combo(ui, "热键##freecam_vk_code", &mut game.freecam_vk_code);
+
+    ui.slider("速度##freecam_speed", 1.0, 100.0, &mut game.freecam_speed);
+
+    ui.text("WASD 移动，空格上升，左Ctrl 下降，左Shift 加速，左Alt 减速");
+    ui.text("开启时 WASD、空格、Ctrl 不会传给游戏");
+
+    match set_position_export() {
+        Some(export) => ui.text_disabled(format!("相机位置用 {}", export.name)),
+        None => ui.text_colored(
+            [1.0, 0.0, 0.0, 1.0],
+            "导出表里没有 IBaseCamera 的 Set*Position(const vec3 &)，不能用",
+        ),
+    }
+
+    if game.freecam_active {
+        ui.text(format!(
+            "相机位置: {:.2} {:.2} {:.2}",
+            game.freecam_pos.x, game.freecam_pos.y, game.freecam_pos.z
+        ));
+    }
+
+    ui.text(&game.freecam_status);
+}
+
+// 相机用 IBaseCamera::SetPosition 移走，玩家按着进入时的位置不动，
+// 视角还是由游戏自己根据鼠标写到 camera_angle_p
+unsafe fn update_freecam(game: &mut Game, ui: &hudhook::imgui::Ui, world: &World) {
+    if key_pressed(game.freecam_vk_code, &mut game.freecam_key_down) {
+        game.freecam_toggle = !game.freecam_toggle;
+    }
+
+    // 联机时一个字节都不写，存下的状态直接丢掉
+    if game.freecam_active {
+        if let Some(reason) = session_blocked(game, ui.time()) {
+            drop_freecam(game, reason);
+            return;
+        }
+
+        if world.player_di_p as usize != game.freecam_player_di {
+            drop_freecam(game, "玩家对象变了，已退出自由视角");
+            return;
+        }
+    }
+
+    if !game.freecam_toggle {
+        if game.freecam_active {
+            exit_freecam(game, world);
+            game.freecam_status = "已退出自由视角".to_string();
+        }
+
+        return;
+    }
+
+    if let Some(reason) = write_blocked(game, ui.time()) {
+        if game.freecam_active {
+            exit_freecam(game, world);
+        }
+
+        game.freecam_toggle = false;
+        game.freecam_status = reason.to_string();
+        return;
+    }
+
+    if set_position_export().is_none() {
+        game.freecam_toggle = false;
+        game.freecam_status = "没找到 IBaseCamera 的 SetPosition 导出".to_string();
+        return;
+    }
+
+    if !game.freecam_active {
+        game.freecam_active = true;
+        game.freecam_pos = get_position(world.camera_fpp_di_p).read();
+        game.freecam_camera_pos = game.freecam_pos;
+        game.freecam_player_pos = world.player_world_pos_p.read();
+        game.freecam_player_di = world.player_di_p as usize;
+        game.freecam_angle = world.camera_angle_p.read();
+        game.freecam_status = "自由视角".to_string();
+        BLOCK_MOVE_KEYS = true;
+    }
+
+    // 在菜单里打字时不动
+    if !ui.io().want_capture_keyboard {
+        let axis = |positive: i32, negative: i32| {
+            key_down(positive) as i32 as f32 - key_down(negative) as i32 as f32
+        };
+
+        let input = FreeCamInput {
+            forward: axis(0x57, 0x53), // W S
+            right: axis(0x44, 0x41),   // D A
+            up: axis(0x20, 0xA2),      // 空格 左Ctrl
+            fast: key_down(0xA0),      // 左Shift
+            slow: key_down(0xA4),      // 左Alt
+        };
+
+        let angle = world.camera_angle_p.read();
+        game.freecam_pos = freecam::move_camera(
+            game.freecam_pos,
+            angle.x,
+            angle.y,
+            &input,
+            game.freecam_speed,
+            ui.io().delta_time,
+        );
+    }
+
+    world.player_world_pos_p.write(game.freecam_player_pos);
+    set_position(world.camera_fpp_di_p, &game.freecam_pos);
+}
+
+// 不写任何东西，只把状态清掉，放开移动键
+unsafe fn drop_freecam(game: &mut Game, reason: &str) {
+    game.freecam_active = false;
+    game.freecam_toggle = false;
+    game.freecam_status = reason.to_string();
+    BLOCK_MOVE_KEYS = false;
+}
+
+// 玩家、相机和视角写回进入时的状态，相机交还给游戏
+unsafe fn exit_freecam(game: &mut Game, world: &World) {
+    game.freecam_active = false;
+    BLOCK_MOVE_KEYS = false;
+
+    world.player_world_pos_p.write(game.freecam_player_pos);
+    world.camera_angle_p.write(game.freecam_angle);
+    set_position(world.camera_fpp_di_p, &game.freecam_camera_pos);
+}
+
+// 自由视角时不让移动键传给游戏，在 hudhook 的窗口过程外面再套一层
+// 自己的移动用 GetAsyncKeyState 读，不受影响；游戏要是用 DirectInput 读键盘就拦不住
+static mut GAME_WND_PROC: isize = 0;
+static mut BLOCK_MOVE_KEYS: bool = false;
+
+// W A S D 空格 Ctrl 左Ctrl
+const FREECAM_KEYS: [u16; 7] = [0x57, 0x41, 0x53, 0x44, 0x20, 0x11, 0xA2];
+
+unsafe fn hook_wnd_proc(hwnd: HWND) {
+    if hwnd.0 == 0 || GAME_WND_PROC != 0 {
+        return;
+    }
+
+    GAME_WND_PROC = SetWindowLongPtrW(hwnd, GWLP_WNDPROC, freecam_wnd_proc as usize as isize);
+}
+
+unsafe extern "system" fn freecam_wnd_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if BLOCK_MOVE_KEYS {
+        match msg {
+            WM_KEYDOWN | WM_KEYUP | WM_SYSKEYDOWN | WM_SYSKEYUP
+                if FREECAM_KEYS.contains(&(wparam.0 as u16)) =>
+            {
+                return LRESULT(0);
+            }
+            // WM_INPUT 不传给游戏时要交给 DefWindowProc 清理
+            WM_INPUT if raw_key(lparam).is_some_and(|key| FREECAM_KEYS.contains(&key)) => {
+                return DefWindowProcW(hwnd, msg, wparam, lparam);
+            }
+            _ => (),
+        }
+    }
+
+    CallWindowProcW(
+        std::mem::transmute::<isize, WNDPROC>(GAME_WND_PROC),
+        hwnd,
+        msg,
+        wparam,
+        lparam,
+    )
+}
+
+// 原始输入里的键盘按键，不是键盘时返回 None
+unsafe fn raw_key(lparam: LPARAM) -> Option<u16> {
+    let mut raw = RAWINPUT::default();
+    let mut size = std::mem::size_of::<RAWINPUT>() as u32;
+
+    let result = GetRawInputData(
+        HRAWINPUT(lparam.0),
+        RID_INPUT,
+        Some(&mut raw as *mut RAWINPUT as *mut std::ffi::c_void),
+        &mut size,
+        std::mem::size_of::<RAWINPUTHEADER>() as u32,
+    );
+
+    if result == u32::MAX || raw.header.dwType != RIM_TYPEKEYBOARD.0 {
+        return None;
+    }
+
+    Some(raw.data.keyboard.VKey)
+}
+
+fn write_enable_checkbox(game: &mut Game, ui: &hudhook::imgui::Ui, now: f64) {
+    ui.checkbox("允许写入##write_enable", &mut game.write_enable);
+    ui.same_line();
+    match write_blocked(game, now) {
+        Some(reason) => ui.text_colored([1.0, 0.0, 0.0, 1.0], reason),
+        None => ui.text("单人模式，可以写入"),
+    }
+
+    match session_method() {
+        Some(method) => ui.text_disabled(format!(
+            "会话状态来自 {}::{}",
+            method.shape.class, method.shape.method
+        )),
+        None => ui.text_disabled(
+            "导出表里没有 ISessionCooperative 的玩家数或联机标志函数，写入功能不可用",
+        ),
+    }
+}
+
+// 可以写游戏内存时返回 None，否则返回原因
+fn write_blocked(game: &Game, now: f64) -> Option<&'static str> {
+    if !game.write_enable {
+        return Some("没有打开写入");
+    }
+
+    session_blocked(game, now)
+}
+
+// 不管开没开写入，联机或者读不到会话状态时返回原因
+fn session_blocked(game: &Game, now: f64) -> Option<&'static str> {
+    match game.session_multiplayer {
+        None => return Some("读不到会话状态，不能确认是单人"),
+        Some(true) => return Some("联机中"),
+        Some(false) => (),
+    }
+
+    if let Some(seen) = game.other_player_seen
+        && now - seen < OTHER_PLAYER_TIMEOUT
+    {
+        return Some("最近见过其他玩家，可能是联机");
+    }
+
+    None
+}
+
+unsafe fn teleport_to(game: &mut Game, world: &World, pos: Vec3<f32>, now: f64) {
+    if let Some(reason) = write_blocked(game, now) {
+        game.teleport_status = reason.to_string();
+        return;
+    }
+
+    world.player_world_pos_p.write(pos);
+    if game.freecam_active {
+        game.freecam_player_pos = pos;
+    }
+
+    game.teleport_status = format!("已传送到 {:.2} {:.2} {:.2}", pos.x, pos.y, pos.z);
+}
+
+unsafe fn save_teleport_slot(game: &mut Game, world: &World) {
+    let pos = world.player_world_pos_p.read();
+    game.teleport_slots[game.teleport_slot] = Some(pos);
+    game.teleport_status = format!("已保存到槽位 {}", game.teleport_slot + 1);
+}
+
+unsafe fn load_teleport_slot(game: &mut Game, world: &World, now: f64) {
+    match game.teleport_slots[game.teleport_slot] {
+        Some(pos) => teleport_to(game, world, pos, now),
+        None => game.teleport_status = format!("槽位 {} 是空的", game.teleport_slot + 1),
+    }
+}
+
+unsafe fn step_forward(game: &mut Game, world: &World, now: f64) {
+    let pos = teleport::step_forward(
+        world.player_world_pos_p.read(),
+        world.camera_angle_p.read().x,
+        game.teleport_step,
+    );
+
+    teleport_to(game, world, pos, now);
+}
+
+unsafe fn update_teleport_hotkeys(game: &mut Game, world: &World, now: f64) {
+    if key_pressed(game.teleport_save_vk_code, &mut game.teleport_save_key_down) {
+        save_teleport_slot(game, world);
+    }
+
+    if key_pressed(game.teleport_load_vk_code, &mut game.teleport_load_key_down) {
+        load_teleport_slot(game, world, now);
+    }
+
+    if key_pressed(game.teleport_step_vk_code, &mut game.teleport_step_key_down) {
+        step_forward(game, world, now);
+    }
+}
+
+unsafe fn on_frame_draw_ui_player(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    let now = ui.time();
+
+    write_enable_checkbox(game, ui, now);
+
+    let world = match get_world() {
+        Some(val) => val,
+        None => return,
+    };
+
+    ui.text("锁定后每帧写回锁定值，取消锁定、关闭写入或联机时恢复原来的值");
+
+    ui.separator();
+
+    let mut remove = None;
+    for index in 0..game.vitals.len() {
+        let vital = &game.vitals[index];
+        let val_p = vitals::resolve(
+            &process::LiveMemory,
+            &OFFSETS,
+            world.player_di_p as usize,
+            vital.addr,
+        );
+
+        let Some(val_p) = val_p else {
+            ui.text_disabled(format!("{}: 读不到 ({})", vital.name, vital.addr));
+            if index != 0 {
+                ui.same_line();
+                if ui.button(format!("删除##vital_remove_{}", index)) {
+                    remove = Some(index);
+                }
+            }
+            continue;
+        };
+
+        let current = (val_p as *const f32).read();
+        let locked = vital.lock;
+
+        // 锁定时编辑锁定值，没锁定时回车直接写一次
+        let mut value = if locked { vital.lock_value } else { current };
+        if ui
+            .input_float(format!("{}##vital_value_{}", vital.name, index), &mut value)
+            .enter_returns_true(true)
+            .build()
+        {
+            if locked {
+                game.vitals[index].lock_value = value;
+            } else {
+                match write_blocked(game, now) {
+                    Some(reason) => game.vital_status = reason.to_string(),
+                    None => {
+                        (val_p as *mut f32).write(value);
+                        game.vital_status = format!("{} 已改为 {}", game.vitals[index].name, value);
+                    }
+                }
+            }
+        }
+
+        ui.same_line();
+        let label = if index == 0 { "无敌" } else { "锁定" };
+        let vital = &mut game.vitals[index];
+        if ui.checkbox(format!("{}##vital_lock_{}", label, index), &mut vital.lock) && vital.lock {
+            vital.lock_value = current;
+        }
+
+        // 锁定中的要先恢复原来的值才能删
+        if index != 0 && vital.original.is_none() {
+            ui.same_line();
+            if ui.button(format!("删除##vital_remove_{}", index)) {
+                remove = Some(index);
+            }
+        }
+
+        if locked && let Some(original) = vital.original {
+            ui.text_disabled(format!("原来的值 {}", original));
+        }
+    }
+
+    if let Some(index) = remove {
+        game.vitals.remove(index);
+    }
+
+    ui.separator();
+
+    // 体力等还没找到固定偏移，先手动填
+    ui.input_text("名字##vital_name", &mut game.vital_name)
+        .hint("体力")
+        .build();
+    ui.radio_button("PlayerDI##vital_base_player_di", &mut game.vital_base, 0);
+    ui.same_line();
+    ui.radio_button(
+        "HealthModule##vital_base_health_module",
+        &mut game.vital_base,
+        1,
+    );
+    ui.input_text("偏移##vital_offset", &mut game.vital_offset)
+        .hint("0x...")
+        .build();
+
+    if ui.button("添加##vital_add") {
+        add_vital(game);
+    }
+    ui.same_line();
+    if ui.button("读取##vital_load") {
+        load_vitals(game);
+    }
+    ui.same_line();
+    if ui.button("保存##vital_save") {
+        game.vital_status = match std::fs::write(VITALS_FILE, vitals::to_text(&game.vitals)) {
+            Ok(_) => format!("已保存到 {}", VITALS_FILE),
+            Err(err) => format!("保存失败: {}", err),
+        };
+    }
+
+    ui.text(&game.vital_status);
+}
+
+// 关闭写入或联机时 Vital::update 会返回原来的值，也要写回去
+unsafe fn update_vitals(game: &mut Game, world: &World, now: f64) {
+    let gate = if session_blocked(game, now).is_some() {
+        WriteGate::Blocked
+    } else if !game.write_enable {
+        WriteGate::Disabled
+    } else {
+        WriteGate::Allowed
+    };
+
+    for vital in game.vitals.iter_mut() {
+        let Some(val_p) = vitals::resolve(
+            &process::LiveMemory,
+            &OFFSETS,
+            world.player_di_p as usize,
+            vital.addr,
+        ) else {
+            continue;
+        };
+
+        let val_p = val_p as *mut f32;
+        if let Some(val) = vital.update(val_p.read(), gate) {
+            val_p.write(val);
+        }
+    }
+}
+
+fn add_vital(game: &mut Game) {
+    let name = game.vital_name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        game.vital_status = "名字不能为空或有空格".to_string();
+        return;
+    }
+
+    let Some(offset) = pointer_scan::parse_hex(&game.vital_offset) else {
+        game.vital_status = "偏移不是十六进制数".to_string();
+        return;
+    };
+
+    let addr = match game.vital_base {
+        0 => VitalAddr::PlayerDI(offset),
+        _ => VitalAddr::HealthModule(offset),
+    };
+
+    game.vitals.push(Vital::new(name, addr));
+    game.vital_status = format!("已添加 {}", name);
+}
+
+// 保留第一个血量，其余换成文件里的
+fn load_vitals(game: &mut Game) {
+    if game.vitals[1..].iter().any(|val| val.original.is_some()) {
+        game.vital_status = "先取消锁定再读取".to_string();
+        return;
+    }
+
+    let text = match std::fs::read_to_string(VITALS_FILE) {
+        Ok(val) => val,
+        Err(err) => {
+            game.vital_status = format!("读取失败: {}", err);
+            return;
+        }
+    };
+
+    game.vital_status = match vitals::parse(&text) {
+        Ok(val) => {
+            game.vitals.truncate(1);
+            game.vitals.extend(val);
+            format!("读取 {} 个数值", game.vitals.len() - 1)
+        }
+        Err(err) => format!("{}: {}", VITALS_FILE, err),
+    };
+}
+
+unsafe fn on_frame_draw_ui_clock(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    let now = ui.time();
+
+    write_enable_checkbox(game, ui, now);
+
+    let world = match get_world() {
+        Some(val) => val,
+        None => return,
+    };
+
+    ui.checkbox("屏幕上显示##clock_overlay", &mut game.clock_overlay);
+
+    ui.separator();
+
+    let methods = clock_methods();
+
+    clock_binding(ui, "时间", &methods.get_time, &methods.set_time);
+    clock_binding(ui, "流速", &methods.get_speed, &methods.set_speed);
+    match &methods.is_night {
+        Some(method) => ui.text_disabled(format!(
+            "昼夜来自 {}::{}",
+            method.shape.class, method.shape.method
+        )),
+        None => {
+            ui.text_disabled("导出表里没有 ILevel 的 IsNight 之类的函数，入夜和天亮的时间只能估")
+        }
+    }
+
+    let Some(hour) = game.clock_hour else {
+        ui.text_colored(
+            [1.0, 0.0, 0.0, 1.0],
+            "导出表里没有成对的 ILevel 时间函数，读不到时间",
+        );
+        return;
+    };
+
+    ui.text(format!("当前时间: {}", clock::format_hour(hour)));
+    ui.text(clock_countdown(game, hour));
+
+    let range = &game.clock_night_range;
+    ui.text(format!(
+        "入夜 {} {}  天亮 {} {}",
+        clock::format_hour(range.start),
+        night_bound_source(range.start_seen),
+        clock::format_hour(range.end),
+        night_bound_source(range.end_seen)
+    ));
+
+    match game.clock_rate.hours_per_second() {
+        Some(rate) => ui.text(format!("现实 1 秒 = 游戏 {:.2} 分钟", rate * 60.0)),
+        None => ui.text("正在测量时间流速..."),
+    }
+
+    ui.separator();
+
+    ui.input_text("时间##clock_input", &mut game.clock_input)
+        .hint("HH:MM")
+        .build();
+    ui.same_line();
+    if ui.button("设置##clock_set") {
+        match clock::parse_hour(&game.clock_input) {
+            Some(hour) => set_clock(game, &world, hour, now),
+            None => game.clock_status = "时间格式是 HH:MM".to_string(),
+        }
+    }
+
+    for (name, hour) in [("早上", 7.0), ("中午", 12.0), ("傍晚", 19.0), ("午夜", 0.0)] {
+        if ui.button(format!("{}##clock_preset_{}", name, hour)) {
+            set_clock(game, &world, hour, now);
+        }
+        ui.same_line();
+    }
+    ui.new_line();
+
+    ui.separator();
+
+    match get_time_of_day_speed(world.level_di_p) {
+        Some(speed) => ui.text(format!("当前流速: {:.2}", speed)),
+        None => ui.text_disabled("导出表里没有成对的 ILevel 时间流速函数"),
+    }
+
+    ui.slider("流速##clock_speed", 0.0, 20.0, &mut game.clock_speed);
+    ui.same_line();
+    if ui.button("应用##clock_speed_set") {
+        set_clock_speed(game, &world, now);
+    }
+    ui.same_line();
+    if ui.button("恢复##clock_speed_restore") {
+        restore_clock_speed(game, &world);
+    }
+
+    ui.text(&game.clock_status);
+}
+
+// 和写入开关下面的会话状态一样，显示绑定到了哪两个导出
+fn clock_binding(
+    ui: &hudhook::imgui::Ui,
+    name: &str,
+    getter: &Option<FoundMethod>,
+    setter: &Option<FoundMethod>,
+) {
+    match (getter, setter) {
+        (Some(getter), Some(setter)) => ui.text_disabled(format!(
+            "{}来自 {}::{} / {}",
+            name, getter.shape.class, getter.shape.method, setter.shape.method
+        )),
+        _ => ui.text_disabled(format!("{}: 导出表里没有成对的 ILevel GetX / SetX", name)),
+    }
+}
+
+fn night_bound_source(seen: bool) -> &'static str {
+    match seen {
+        true => "(引擎翻转时记下)",
+        false => "(估计值，未确认)",
+    }
+}
+
+// 引擎能告诉是不是晚上时用引擎的，否则按入夜和天亮的时间算
+fn clock_is_night(game: &Game, hour: f32) -> bool {
+    game.clock_night
+        .unwrap_or_else(|| game.clock_night_range.is_night(hour))
+}
+
+// "夜晚，离天亮 3.5 小时 (约 2分10秒)"
+fn clock_countdown(game: &Game, hour: f32) -> String {
+    let range = &game.clock_night_range;
+    let (text, hours) = match clock_is_night(game, hour) {
+        true => ("夜晚，离天亮", range.hours_until_day(hour)),
+        false => ("白天，离入夜", range.hours_until_night(hour)),
+    };
+
+    match game.clock_rate.real_seconds(hours) {
+        Some(seconds) => format!(
+            "{} {:.1} 小时 (约 {})",
+            text,
+            hours,
+            clock::format_duration(seconds)
+        ),
+        None => format!("{} {:.1} 小时", text, hours),
+    }
+}
+
+unsafe fn update_clock(game: &mut Game, world: &World, now: f64) {
+    game.clock_hour = get_time_of_day(world.level_di_p);
+    game.clock_night = impls::is_night(world.level_di_p);
+
+    if let Some(hour) = game.clock_hour {
+        game.clock_rate.sample(now, hour);
+
+        if let Some(night) = game.clock_night {
+            game.clock_night_range.observe(hour, night);
+        }
+    }
+
+    if game.clock_original_speed.is_none() {
+        return;
+    }
+
+    // 联机时不写，原来的流速直接丢掉；只是关掉写入时才恢复
+    if let Some(reason) = session_blocked(game, now) {
+        game.clock_original_speed = None;
+        game.clock_status = reason.to_string();
+    } else if !game.write_enable {
+        restore_clock_speed(game, world);
+    }
+}
+
+fn draw_clock<D: Draw>(game: &Game, draw: &mut D, camera: &Camera) {
+    let Some(hour) = game.clock_hour else {
+        return;
+    };
+
+    let color = match clock_is_night(game, hour) {
+        true => [1.0, 0.3, 0.3, 1.0],
+        false => [1.0, 1.0, 1.0, 1.0],
+    };
+
+    draw.text(
+        Layer::Background,
+        [camera.width / 2.0 - 150.0, 10.0],
+        color,
+        format!(
+            "{}  {}",
+            clock::format_hour(hour),
+            clock_countdown(game, hour)
+        ),
+    );
+}
+
+unsafe fn set_clock(game: &mut Game, world: &World, hour: f32, now: f64) {
+    if let Some(reason) = write_blocked(game, now) {
+        game.clock_status = reason.to_string();
+        return;
+    }
+
+    game.clock_status = match set_time_of_day(world.level_di_p, hour) {
+        true => {
+            game.clock_rate.reset();
+            game.clock_night_range.skip_jump();
+            format!("时间已设为 {}", clock::format_hour(hour))
+        }
+        false => "导出表里没有成对的 ILevel 时间函数".to_string(),
+    };
+}
+
+unsafe fn set_clock_speed(game: &mut Game, world: &World, now: f64) {
+    if let Some(reason) = write_blocked(game, now) {
+        game.clock_status = reason.to_string();
+        return;
+    }
+
+    let Some(speed) = get_time_of_day_speed(world.level_di_p) else {
+        game.clock_status = "导出表里没有成对的 ILevel 时间流速函数".to_string();
+        return;
+    };
+
+    if !set_time_of_day_speed(world.level_di_p, game.clock_speed) {
+        game.clock_status = "导出表里没有成对的 ILevel 时间流速函数".to_string();
+        return;
+    }
+
+    if game.clock_original_speed.is_none() {
+        game.clock_original_speed = Some(speed);
+    }
+
+    game.clock_rate.reset();
+    game.clock_status = format!("流速已设为 {:.2}", game.clock_speed);
+}
+
+unsafe fn restore_clock_speed(game: &mut Game, world: &World) {
+    let Some(speed) = game.clock_original_speed.take() else {
+        return;
+    };
+
+    set_time_of_day_speed(world.level_di_p, speed);
+
+    game.clock_rate.reset();
+    game.clock_status = format!("流速已恢复为 {:.2}", speed);
+}
+
+fn on_frame_draw_ui_speed(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    write_enable_checkbox(game, ui, ui.time());
+
+    ui.checkbox("开启##speed_toggle", &mut game.speed_toggle);
+    ui.same_line();
+    hotkey_combo(ui, "热键##speed_vk_code", &mut game.speed_vk_code);
+
+    ui.slider(
+        "倍数##speed_scale",
+        speed::SPEED_MIN,
+        speed::SPEED_MAX,
+        &mut game.speed_scale,
+    );
+
+    for scale in [0.1, 0.25, 0.5, 2.0, 4.0] {
+        if ui.button(format!("{}x##speed_preset_{}", scale, scale)) {
+            game.speed_scale = scale;
+            game.speed_toggle = true;
+        }
+        ui.same_line();
+    }
+    ui.new_line();
+
+    hotkey_combo(
+        ui,
+        "减速热键##speed_slower_vk_code",
+        &mut game.speed_slower_vk_code,
+    );
+    hotkey_combo(
+        ui,
+        "加速热键##speed_faster_vk_code",
+        &mut game.speed_faster_vk_code,
+    );
+
+    let scale = match SPEED_CLOCK.lock() {
+        Ok(val) => val.scale(),
+        Err(_) => 1.0,
+    };
+    ui.text(format!("当前速度: {:.2}x", scale));
+
+    ui.text(&game.speed_status);
+}
+
+// 关闭时倍数改回 1.0 而不是卸掉 hook，计数从当前值接着走，游戏里的时间不会跳
+unsafe fn update_game_speed(game: &mut Game, now: f64) {
+    if key_pressed(game.speed_vk_code, &mut game.speed_key_down) {
+        game.speed_toggle = !game.speed_toggle;
+    }
+
+    if key_pressed(game.speed_slower_vk_code, &mut game.speed_slower_key_down) {
+        game.speed_scale = speed::step_speed(game.speed_scale, false);
+        game.speed_toggle = true;
+    }
+
+    if key_pressed(game.speed_faster_vk_code, &mut game.speed_faster_key_down) {
+        game.speed_scale = speed::step_speed(game.speed_scale, true);
+        game.speed_toggle = true;
+    }
+
+    if game.speed_toggle
+        && let Some(reason) = write_blocked(game, now)
+    {
+        game.speed_toggle = false;
+        game.speed_status = reason.to_string();
+    }
+
+    if game.speed_toggle
+        && let Err(err) = install_speed_hook()
+    {
+        game.speed_toggle = false;
+        game.speed_status = err;
+    }
+
+    let target = match game.speed_toggle {
+        true => game.speed_scale.clamp(speed::SPEED_MIN, speed::SPEED_MAX),
+        false => 1.0,
+    };
+
+    let Ok(mut clock) = SPEED_CLOCK.lock() else {
+        return;
+    };
+
+    if clock.scale() != target {
+        clock.set_scale(target);
+        game.speed_status = format!("速度已设为 {:.2}x", target);
+    }
+}
+
+unsafe fn install_speed_hook() -> Result<(), String> {
+    if SPEED_HOOK.is_some() {
+        return Ok(());
+    }
+
+    let kernel32 = GetModuleHandleA(hudhook::windows::core::s!("kernel32.dll"))
+        .map_err(|err| format!("没找到 kernel32.dll: {}", err))?;
+
+    let target = GetProcAddress(
+        kernel32,
+        hudhook::windows::core::s!("QueryPerformanceCounter"),
+    )
+    .ok_or("没找到 QueryPerformanceCounter")?;
+
+    let hook = MhHook::new(target as *mut _, query_performance_counter_hook as *mut _)
+        .map_err(|err| format!("创建 hook 失败: {:?}", err))?;
+
+    // hook 里要用 trampoline，先存起来再启用
+    SPEED_HOOK = Some(hook);
+
+    let result = SPEED_HOOK
+        .as_ref()
+        .unwrap()
+        .queue_enable()
+        .and_then(|_| MH_ApplyQueued().ok_context());
+
+    if let Err(err) = result {
+        SPEED_HOOK = None;
+        return Err(format!("启用 hook 失败: {:?}", err));
+    }
+
+    Ok(())
+}
+
+unsafe extern "system" fn query_performance_counter_hook(count: *mut i64) -> i32 {
+    type Prototype = unsafe extern "system" fn(*mut i64) -> i32;
+
+    let trampoline: Prototype = std::mem::transmute(SPEED_HOOK.as_ref().unwrap().trampoline());
+
+    let result = trampoline(count);
+    if result == 0 || count.is_null() {
+        return result;
+    }
+
+    if let Ok(mut clock) = SPEED_CLOCK.lock() {
+        count.write(clock.now(count.read()));
+    }
+
+    result
+}
+
+fn on_frame_draw_ui_trail(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox("记录##trail_record", &mut game.trail_record);
+    ui.same_line();
+    ui.checkbox("显示##trail_draw", &mut game.trail_draw);
+
+    ui.slider(
+        "间隔(米)##trail_interval",
+        0.2,
+        10.0,
+        &mut game.trail_interval,
+    );
+    ui.slider(
+        "最多点数##trail_max_points",
+        100,
+        20000,
+        &mut game.trail_max_points,
+    );
+    ui.slider("粗细##trail_thickness", 1.0, 6.0, &mut game.trail_thickness);
+
+    ui.color_edit4_config("新##trail_color_new", &mut game.trail_color_new)
+        .inputs(false)
+        .build();
+    ui.same_line();
+    ui.color_edit4_config("旧##trail_color_old", &mut game.trail_color_old)
+        .inputs(false)
+        .build();
+    ui.same_line();
+    ui.color_edit4_config("对比##trail_compare_color", &mut game.trail_compare_color)
+        .inputs(false)
+        .build();
+
+    ui.text(format!(
+        "当前地图: {}  {} 个点，长 {:.1} 米",
+        waypoint_map(game),
+        game.trail.points.len(),
+        game.trail.length()
+    ));
+
+    if ui.button("清空##trail_clear") {
+        game.trail = Trail::default();
+    }
+
+    ui.separator();
+
+    ui.input_text("名字##trail_name", &mut game.trail_name)
+        .build();
+    ui.same_line();
+    if ui.button("保存当前轨迹##trail_save") {
+        save_current_trail(game);
+    }
+
+    if ui.button("读取##trail_load") {
+        load_trails(game);
+    }
+
+    ui.text(&game.trail_status);
+    ui.separator();
+
+    let map = waypoint_map(game);
+    let mut remove = None;
+
+    for (index, (name, trail)) in game.trails.get(&map).iter().enumerate() {
+        let mut shown = game.trail_shown.contains(name);
+        if ui.checkbox(
+            format!("{}  {:.1} 米##trail_shown_{}", name, trail.length(), index),
+            &mut shown,
+        ) {
+            match shown {
+                true => game.trail_shown.push(name.clone()),
+                false => game.trail_shown.retain(|val| val != name),
+            }
+        }
+
+        ui.same_line();
+        if ui.button(format!("删除##trail_remove_{}", index)) {
+            remove = Some(index);
+        }
+    }
+
+    if let Some(index) = remove {
+        let (name, _) = game.trails.get_mut(&map).remove(index);
+        game.trail_shown.retain(|val| *val != name);
+        write_trails(game);
+    }
+}
+
+unsafe fn update_trail(game: &mut Game, world: &World) {
+    let map = waypoint_map(game);
+    if map != game.trail_map {
+        game.trail = Trail::default();
+        game.trail_shown.clear();
+        game.trail_map = map;
+    }
+
+    if !game.trail_record {
+        return;
+    }
+
+    game.trail.record(
+        world.player_world_pos_p.read(),
+        game.trail_interval,
+        game.trail_max_points.max(2) as usize,
+    );
+}
+
+unsafe fn draw_trails<D: Draw>(game: &Game, draw: &mut D, world: &World, camera: &Camera) {
+    draw_trail(
+        game,
+        draw,
+        world,
+        camera,
+        &game.trail,
+        game.trail_color_new,
+        game.trail_color_old,
+    );
+
+    for (name, trail) in game.trails.get(&game.trail_map) {
+        if !game.trail_shown.contains(name) {
+            continue;
+        }
+
+        let mut old = game.trail_compare_color;
+        old[3] *= 0.5;
+
+        draw_trail(
+            game,
+            draw,
+            world,
+            camera,
+            trail,
+            game.trail_compare_color,
+            old,
+        );
+    }
+}
+
+unsafe fn draw_trail<D: Draw>(
+    game: &Game,
+    draw: &mut D,
+    world: &World,
+    camera: &Camera,
+    trail: &Trail,
+    color_new: [f32; 4],
+    color_old: [f32; 4],
+) {
+    for (from, to, age) in trail.segments() {
+        // 有一头在身后时引擎投影会翻到前面来，整段不画
+        if camera.world_to_screen(from).screen().is_none()
+            || camera.world_to_screen(to).screen().is_none()
+        {
+            continue;
+        }
+
+        let (Some(from), Some(to)) = (
+            world_to_screen(game, world, camera, &from),
+            world_to_screen(game, world, camera, &to),
+        ) else {
+            continue;
+        };
+
+        draw.line(
+            Layer::Background,
+            [from.x, from.y],
+            [to.x, to.y],
+            trail::fade_color(color_new, color_old, age),
+            game.trail_thickness,
+        );
+    }
+}
+
+fn save_current_trail(game: &mut Game) {
+    if game.trail.points.len() < 2 {
+        game.trail_status = "当前轨迹太短".to_string();
+        return;
+    }
+
+    let map = waypoint_map(game);
+    let trails = game.trails.get_mut(&map);
+
+    let name = match game.trail_name.trim() {
+        "" => format!("轨迹{}", trails.len() + 1),
+        val => val.to_string(),
+    };
+
+    // 同名的覆盖
+    trails.retain(|(val, _)| *val != name);
+    trails.push((name, game.trail.clone()));
+
+    write_trails(game);
+}
+
+fn load_trails(game: &mut Game) {
+    let text = match std::fs::read_to_string(TRAIL_FILE) {
+        Ok(val) => val,
+        Err(err) => {
+            game.trail_status = format!("读取失败: {}", err);
+            return;
+        }
+    };
+
+    game.trail_status = match SavedTrails::parse(&text) {
+        Ok(val) => {
+            game.trails = val;
+            game.trail_shown.clear();
+            format!("读取 {} 张地图的轨迹", game.trails.maps.len())
+        }
+        Err(err) => format!("{}: {}", TRAIL_FILE, err),
+    };
+}
+
+fn write_trails(game: &mut Game) {
+    game.trail_status = match std::fs::write(TRAIL_FILE, game.trails.to_text()) {
+        Ok(_) => format!("已保存到 {}", TRAIL_FILE),
+        Err(err) => format!("保存失败: {}", err),
+    };
+}
+
+fn on_frame_draw_ui_scripts(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox("运行脚本##script_toggle", &mut game.script_toggle);
+    ui.same_line();
+    if ui.button("重新加载##script_reload") {
+        game.script_host.scripts.clear();
+        scan_scripts(game);
+    }
+
+    ui.text(format!("目录: {}", SCRIPT_DIR));
+    ui.text(&game.script_status);
+
+    for (index, script) in game.script_host.scripts.iter_mut().enumerate() {
+        ui.separator();
+
+        if ui.checkbox(
+            format!("{}##script_enabled_{}", script.name, index),
+            &mut script.enabled,
+        ) && script.enabled
+        {
+            script.error = None;
+        }
+
+        if let Some(err) = &script.error {
+            ui.text_colored([1.0, 0.0, 0.0, 1.0], err);
+        }
+
+        for (name, val) in &mut script.settings {
+            let label = format!("{}##script_setting_{}_{}", name, index, name);
+
+            if let Ok(mut bool_val) = val.as_bool() {
+                if ui.checkbox(&label, &mut bool_val) {
+                    *val = bool_val.into();
+                }
+            } else if let Ok(float_val) = val.as_float() {
+                let mut float_val = float_val as f32;
+                if ui.input_float(&label, &mut float_val).build() {
+                    *val = (float_val as f64).into();
+                }
+            } else if let Ok(int_val) = val.as_int() {
+                let mut int_val = int_val as i32;
+                if ui.input_int(&label, &mut int_val).build() {
+                    *val = (int_val as i64).into();
+                }
+            } else if let Ok(mut string_val) = val.clone().into_string() {
+                if ui.input_text(&label, &mut string_val).build() {
+                    *val = string_val.into();
+                }
+            } else {
+                ui.text(format!("{}: {}", name, val));
+            }
+        }
+
+        if !script.logs.is_empty() {
+            if let Some(_val) = ui.tree_node(format!("日志##script_logs_{}", index)) {
+                for log in &script.logs {
+                    ui.text(log);
+                }
+            }
+        }
+    }
+}
+
+// 新加的脚本默认关着，要在菜单里打开
+fn scan_scripts(game: &mut Game) {
+    game.script_status = match game.script_host.scan(std::path::Path::new(SCRIPT_DIR)) {
+        Ok(val) if val.is_empty() => return,
+        Ok(val) => format!("已加载: {}", val.join(", ")),
+        Err(err) => format!("读取目录失败: {}", err),
+    };
+}
+
+// 脚本和插件共用这一帧的实体快照
+unsafe fn run_scripts<D: Draw>(
+    game: &mut Game,
+    draw: &mut D,
+    world: &World,
+    camera: &Camera,
+    model_obj_addrs: &[usize],
+    now: f64,
+) {
+    // 每秒看一次文件有没有改
+    if game.script_toggle && (now - game.script_last_scan >= 1.0 || now < game.script_last_scan) {
+        game.script_last_scan = now;
+        scan_scripts(game);
+    }
+
+    let scripts_enabled =
+        game.script_toggle && game.script_host.scripts.iter().any(|script| script.enabled);
+    let plugins_enabled = game.plugin_host.plugins.iter().any(|plugin| plugin.enabled);
+
+    if scripts_enabled || plugins_enabled {
+        let player_pos = world.player_world_pos_p.read();
+
+        let mut settings = rhai::Map::new();
+        settings.insert("map".into(), waypoint_map(game).into());
+        settings.insert("aim_fov".into(), (game.aim_fov as f64).into());
+        settings.insert("write_enable".into(), game.write_enable.into());
+
+        let frame = ScriptFrame {
+            time: now,
+            player_pos,
+            camera: *camera,
+            entities: script::collect_entities(
+                &process::LiveMemory,
+                &OFFSETS,
+                model_obj_addrs,
+                player_pos,
+                world.player_c_model_obj_p as usize,
+            ),
+            settings,
+        };
+
+        if scripts_enabled {
+            game.script_host.run_frame(&frame, draw);
+        }
+
+        if plugins_enabled {
+            game.plugin_host.run_frame(&frame, draw);
+        }
+    }
+
+    // 出错被关掉的脚本和插件在画面上提示
+    let script_errors = game
+        .script_host
+        .scripts
+        .iter()
+        .filter_map(|script| Some(format!("脚本 {}: {}", script.name, script.error.as_ref()?)));
+    let plugin_errors = game
+        .plugin_host
+        .plugins
+        .iter()
+        .filter_map(|plugin| Some(format!("插件 {}: {}", plugin.name, plugin.error.as_ref()?)));
+
+    for (index, text) in script_errors.chain(plugin_errors).enumerate() {
+        draw.text(
+            Layer::Background,
+            [camera.width - 600.0, index as f32 * 25.0],
+            [1.0, 0.0, 0.0, 1.0],
+            text,
+        );
+    }
+}
+
+fn on_frame_draw_ui_plugins(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    if ui.button("重新加载##plugin_reload") {
+        unload_plugins(game);
+        load_plugins(game);
+    }
+
+    ui.text(format!(
+        "目录: {}  ABI 版本: {}",
+        PLUGIN_DIR, PLUGIN_ABI_VERSION
+    ));
+    ui.text(&game.plugin_status);
+
+    for (file, err) in &game.plugin_host.failures {
+        ui.text_colored([1.0, 0.0, 0.0, 1.0], format!("{}: {}", file, err));
+    }
+
+    for (index, plugin) in game.plugin_host.plugins.iter_mut().enumerate() {
+        ui.separator();
+
+        if ui.checkbox(
+            format!("{}##plugin_enabled_{}", plugin.name, index),
+            &mut plugin.enabled,
+        ) && plugin.enabled
+        {
+            plugin.error = None;
+        }
+
+        ui.same_line();
+        ui.text_disabled(plugin.path.display().to_string());
+
+        if let Some(err) = &plugin.error {
+            ui.text_colored([1.0, 0.0, 0.0, 1.0], err);
+        }
+
+        for (name, val) in &mut plugin.settings {
+            let label = format!("{}##plugin_setting_{}_{}", name, index, name);
+
+            match val {
+                PluginSetting::Bool(val) => {
+                    ui.checkbox(&label, val);
+                }
+                PluginSetting::Float(val) => {
+                    ui.input_float(&label, val).build();
+                }
+            }
+        }
+
+        if !plugin.logs.is_empty() {
+            if let Some(_val) = ui.tree_node(format!("日志##plugin_logs_{}", index)) {
+                for log in &plugin.logs {
+                    ui.text(log);
+                }
+            }
+        }
+    }
+}
+
+// 一个插件加载失败不影响其他的，原因记在 failures 里
+unsafe fn load_plugins(game: &mut Game) {
+    let entries = match std::fs::read_dir(PLUGIN_DIR) {
+        Ok(val) => val,
+        Err(err) => {
+            game.plugin_status = format!("读取目录失败: {}: {}", PLUGIN_DIR, err);
+            return;
+        }
+    };
+
+    let mut paths: Vec<std::path::PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|val| val.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"))
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let wide: Vec<u16> = path
+            .as_os_str()
+            .to_string_lossy()
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let library = match LoadLibraryW(hudhook::windows::core::PCWSTR(wide.as_ptr())) {
+            Ok(val) => val,
+            Err(err) => {
+                let file = path
+                    .file_name()
+                    .map(|val| val.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                game.plugin_host
+                    .failures
+                    .push((file, format!("LoadLibrary 失败: {}", err)));
+                continue;
+            }
+        };
+
+        let abi_version = GetProcAddress(
+            library,
+            hudhook::windows::core::PCSTR(plugin::PLUGIN_ABI_VERSION_SYMBOL.as_ptr()),
+        )
+        .map(|val| std::mem::transmute::<_, AbiVersionFn>(val));
+        let init = GetProcAddress(
+            library,
+            hudhook::windows::core::PCSTR(plugin::PLUGIN_INIT_SYMBOL.as_ptr()),
+        )
+        .map(|val| std::mem::transmute::<_, InitFn>(val));
+
+        if !game
+            .plugin_host
+            .add(path, library.0 as usize, abi_version, init)
+        {
+            let _ = FreeLibrary(library);
+        }
+    }
+
+    game.plugin_status = format!(
+        "已加载 {} 个插件，{} 个失败",
+        game.plugin_host.plugins.len(),
+        game.plugin_host.failures.len()
+    );
+}
+
+unsafe fn unload_plugins(game: &mut Game) {
+    for library in game.plugin_host.unload_all() {
+        let _ = FreeLibrary(HMODULE(library as isize));
+    }
+
+    game.plugin_status.clear();
+}
+
+// 手动填了地图名就用手动的，否则用 CLevel 里找到的
+fn waypoint_map(game: &Game) -> String {
+    if !game.waypoint_map_override.trim().is_empty() {
+        return game.waypoint_map_override.trim().to_string();
+    }
+
+    if game.waypoint_level_name.is_empty() {
+        return "未知地图".to_string();
+    }
+
+    game.waypoint_level_name.clone()
+}
+
+// CLevel 变了才重新找地图名，优先用引擎导出的
+unsafe fn update_waypoint_map(game: &mut Game, world: &World) {
+    if world.c_level_p as usize == game.waypoint_level_p {
+        return;
+    }
+
+    game.waypoint_level_p = world.c_level_p as usize;
+
+    if let Some(name) = get_level_name(world.level_di_p) {
+        game.waypoint_level_name = name;
+        game.waypoint_level_verified = true;
+        return;
+    }
+
+    game.waypoint_level_name =
+        world::level_name(&process::LiveMemory, world.c_level_p as usize).unwrap_or_default();
+    game.waypoint_level_verified = false;
+}
+
+fn add_waypoint(game: &mut Game, pos: Vec3<f32>) {
+    let map = waypoint_map(game);
+    let waypoints = game.waypoints.get_mut(&map);
+
+    let name = match game.waypoint_name.trim() {
+        "" => format!("路点{}", waypoints.len() + 1),
+        val => val.to_string(),
+    };
+
+    waypoints.push(Waypoint { name, pos });
+
+    save_waypoints(game);
+}
+
+fn load_waypoints(game: &mut Game) {
+    let text = match std::fs::read_to_string(WAYPOINT_FILE) {
+        Ok(val) => val,
+        Err(err) => {
+            game.waypoint_status = format!("读取失败: {}", err);
+            return;
+        }
+    };
+
+    game.waypoint_status = match Waypoints::parse(&text) {
+        Ok(val) => {
+            game.waypoints = val;
+            format!("读取 {} 张地图的路点", game.waypoints.maps.len())
+        }
+        Err(err) => format!("{}: {}", WAYPOINT_FILE, err),
+    };
+}
+
+fn save_waypoints(game: &mut Game) {
+    game.waypoint_status = match std::fs::write(WAYPOINT_FILE, game.waypoints.to_text()) {
+        Ok(_) => format!("已保存到 {}", WAYPOINT_FILE),
+        Err(err) => format!("保存失败: {}", err),
+    };
+}
+
+// 按下的那一帧返回 true，is_down 记着上一帧的状态
+unsafe fn key_pressed(vk_code: i32, is_down: &mut bool) -> bool {
+    if vk_code == 0 {
+        return false;
+    }
+
+    let down = key_down(vk_code);
+    let pressed = down && !*is_down;
+    *is_down = down;
+
+    pressed
+}
+
+unsafe fn key_down(vk_code: i32) -> bool {
+    GetAsyncKeyState(vk_code) & 0x8000u16 as i16 != 0
+}
+
+fn hotkey_combo(ui: &hudhook::imgui::Ui, label: &str, vk_code: &mut i32) {
+    let current = match HOTKEYS.iter().find(|(val, _)| val == vk_code) {
+        Some((_, name)) => *name,
+        None => "?",
+    };
+
+    if let Some(cb) = ui.begin_combo(label, current) {
+        for (val, name) in HOTKEYS {
+            if ui.selectable_config(name).selected(*vk_code == val).build() {
+                *vk_code = val;
+            }
+        }
+        cb.end();
+    }
+}
+
+fn load_skeletons(game: &mut Game) {
+    let text = match std::fs::read_to_string(SKELETON_FILE) {
+        Ok(val) => val,
+        Err(err) => {
+            game.skeleton_status = format!("读取失败: {}", err);
+            return;
+        }
+    };
+
+    game.skeleton_status = match Skeletons::parse(&text) {
+        Ok(val) => {
+            game.skeletons = val;
+            format!("读取 {} 个骨架", game.skeletons.rigs.len())
+        }
+        Err(err) => format!("{}: {}", SKELETON_FILE, err),
+    };
+}
+
+unsafe fn on_frame_draw_ui_skeletons(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    if ui.button("读取##skeleton_load") {
+        load_skeletons(game);
+    }
+
+    ui.same_line();
+    if ui.button("保存##skeleton_save") {
+        game.skeleton_status = match std::fs::write(SKELETON_FILE, game.skeletons.to_text()) {
+            Ok(_) => format!("已保存到 {}", SKELETON_FILE),
+            Err(err) => format!("保存失败: {}", err),
+        };
+    }
+
+    ui.same_line();
+    if ui.button("恢复默认##skeleton_builtin") {
+        game.skeletons = Skeletons::builtin();
+        game.skeleton_status = "已恢复默认骨架".to_string();
+    }
+
+    ui.text(&game.skeleton_status);
+
+    let fallback = if game.skeleton_fallback.is_empty() {
+        "不画".to_string()
+    } else {
+        game.skeleton_fallback.clone()
+    };
+
+    // 分类没有对应的骨架时用这个
+    if let Some(cb) = ui.begin_combo("默认骨架##skeleton_fallback", fallback) {
+        if ui
+            .selectable_config("不画")
+            .selected(game.skeleton_fallback.is_empty())
+            .build()
+        {
+            game.skeleton_fallback.clear();
+        }
+
+        for rig in &game.skeletons.rigs {
+            if ui
+                .selectable_config(&rig.name)
+                .selected(game.skeleton_fallback == rig.name)
+                .build()
+            {
+                game.skeleton_fallback = rig.name.clone();
+            }
+        }
+        cb.end();
+    }
+
+    ui.separator();
+
+    for rig in game.skeletons.rigs.iter_mut() {
+        let types: Vec<String> = rig.types.iter().map(|val| val.to_string()).collect();
+
+        ui.text(format!(
+            "{}  {} 条链  {}",
+            rig.name,
+            rig.chains.len(),
+            types.join(" ")
+        ));
+
+        ui.slider(
+            format!("粗细##skeleton_thickness_{}", rig.name),
+            0.5,
+            5.0,
+            &mut rig.thickness,
+        );
+        ui.same_line();
+        ui.checkbox(
+            format!("关节##skeleton_joints_{}", rig.name),
+            &mut rig.joints,
+        );
+    }
+}
+
+unsafe fn on_frame_draw_ui_bone_probe(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox("开启##bone_probe_toggle", &mut game.bone_probe_toggle);
+    ui.same_line();
+    ui.slider(
+        "最大索引##bone_probe_max",
+        0,
+        u8::MAX as i32,
+        &mut game.bone_probe_max,
+    );
+    game.bone_probe_max = game.bone_probe_max.clamp(0, u8::MAX as i32);
+
+    if game.bone_probe_target == 0 {
+        ui.text("目标: 无");
+    } else {
+        ui.text(format!(
+            "目标: {} {:#X}",
+            game.bone_probe_type, game.bone_probe_target
+        ));
+    }
+
+    match (bone_count_method(), game.bone_probe_count) {
+        (Some(method), Some(count)) => {
+            ui.text(format!("骨骼数: {} ({})", count, method.shape.method))
+        }
+        (Some(method), None) => ui.text(format!("骨骼数: 未知 ({})", method.shape.method)),
+        (None, _) => ui.text_colored(
+            [0.8, 0.5, 0.0, 1.0],
+            format!(
+                "导出表里没有骨骼数函数，只探测前 {} 个索引",
+                EBones::RFoot as u32 + 1
+            ),
+        ),
+    }
+
+    ui.same_line();
+    if game.bone_probe_locked == 0 {
+        if ui.button("锁定##bone_probe_lock") {
+            game.bone_probe_locked = game.bone_probe_target;
+        }
+    } else if ui.button("解锁##bone_probe_unlock") {
+        game.bone_probe_locked = 0;
+    }
+
+    ui.input_text("骨架名##bone_probe_rig_name", &mut game.bone_probe_rig_name)
+        .build();
+    ui.same_line();
+    ui.checkbox(
+        format!("用于{}##bone_probe_bind_type", game.bone_probe_type),
+        &mut game.bone_probe_bind_type,
+    );
+    ui.same_line();
+    if ui.button("保存骨架##bone_probe_save") {
+        save_probe_rig(game);
+    }
+
+    ui.text(&game.bone_probe_status);
+    ui.separator();
+
+    ui.child_window("##bone_probe_points").build(|| {
+        for point in &game.bone_probe_points {
+            ui.text(format!(
+                "{:3} {:8} ({:.2}, {:.2}, {:.2})",
+                point.index,
+                point.state.to_string(),
+                point.world_pos.x,
+                point.world_pos.y,
+                point.world_pos.z
+            ));
+            ui.same_line_with_pos(480.0);
+            ui.set_next_item_width(200.0);
+            ui.input_text(
+                format!("##bone_probe_name_{}", point.index),
+                &mut game.bone_probe_names[point.index as usize],
+            )
+            .build();
+        }
+    });
+}
+
+// 起了名字的索引存成一个骨架，同名的骨架只换骨骼名，然后整个写回骨架文件
+fn save_probe_rig(game: &mut Game) {
+    let name = game.bone_probe_rig_name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        game.bone_probe_status = "骨架名不能为空或带空格".to_string();
+        return;
+    }
+
+    let new_rig = bone_probe::to_rig(name, &game.bone_probe_names);
+    if new_rig.bones.is_empty() {
+        game.bone_probe_status = "还没有给任何索引起名".to_string();
+        return;
+    }
+
+    let bind_type = game.bone_probe_bind_type.then_some(game.bone_probe_type);
+    let has_chains = bone_probe::merge_rig(&mut game.skeletons.rigs, new_rig, bind_type);
+
+    let note = match game.bone_probe_bind_type && !has_chains {
+        true => "，补上骨骼链后再绑定分类",
+        false => "",
+    };
+
+    game.bone_probe_status = match std::fs::write(SKELETON_FILE, game.skeletons.to_text()) {
+        Ok(_) if !has_chains => {
+            format!("已保存到 {}，骨骼链需要在文件里补上{}", SKELETON_FILE, note)
+        }
+        Ok(_) => format!("已保存到 {}", SKELETON_FILE),
+        Err(err) => format!("保存失败: {}", err),
+    };
+}
+
+unsafe fn on_frame_draw_ui_radar(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox("开启##toggle_radar", &mut game.radar_toggle);
+
+    for shape in [RadarShape::Circle, RadarShape::Square] {
+        ui.radio_button(
+            format!("{}##radar_shape_{:?}", shape, shape),
+            &mut game.radar_shape,
+            shape,
+        );
+        ui.same_line();
+    }
+    ui.new_line();
+
+    ui.slider("范围##radar_range", 10.0, 500.0, &mut game.radar_range);
+    ui.slider("圈数##radar_rings", 1, 6, &mut game.radar_rings);
+
+    ui.text(format!("已记住的实体: {}", KNOWN_MODEL_OBJS.len()));
+    ui.same_line();
+    if ui.button("清空##radar_clear") {
+        KNOWN_MODEL_OBJS.clear();
+    }
+}
+
+unsafe fn on_frame_draw_ui_projection(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.checkbox(
+        "使用 Rust 投影##projection_use_rust",
+        &mut game.projection_use_rust,
+    );
+
+    ui.slider(
+        "垂直视角##projection_fov",
+        30.0,
+        120.0,
+        &mut game.projection_fov,
+    );
+    ui.same_line();
+    if ui.button("从引擎校准##projection_fit_fov") {
+        game.projection_fit_fov = true;
+    }
+
+    if game.projection_fit_fov {
+        ui.text("等待一个离屏幕中心足够远的实体...");
+    }
+
+    ui.text(format!(
+        "和引擎投影的最大误差: {:.1} 像素",
+        game.projection_error
+    ));
+}
+
+unsafe fn on_frame_draw_ui_offsets(ui: &hudhook::imgui::Ui) {
+    for offset in OFFSETS.all() {
+        let color = match offset.status {
+            OffsetStatus::Confirmed => [0.0, 0.6, 0.0, 1.0],
+            OffsetStatus::Rederived => [0.8, 0.5, 0.0, 1.0],
+            OffsetStatus::Unverified => [0.5, 0.5, 0.5, 1.0],
+        };
+
+        let derived = match offset.derived {
+            Some(val) if offset.status == OffsetStatus::Unverified => {
+                format!("  (特征码给出 {:#X}，已忽略)", val)
+            }
+            _ if offset.sig.is_none() => {
+                format!("  (无特征码: {})", offset.no_sig.unwrap_or_default())
+            }
+            _ => String::new(),
+        };
+
+        ui.text_colored(
+            color,
+            format!(
+                "[{}] {}: {:#X} (配置 {:#X}){}",
+                offset.status, offset.name, offset.value, offset.profile, derived
+            ),
+        );
+    }
+}
+
+unsafe fn on_frame_draw_ui_pointer_scan(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    ui.input_text("目标地址##ptr_scan_target", &mut game.ptr_scan_target)
+        .build();
+    ui.same_line();
+    if ui.button("玩家坐标##ptr_scan_player_pos")
+        && let Some(world) = get_world()
+    {
+        game.ptr_scan_target = format!("{:X}", world.player_world_pos_p as usize);
+    }
+
+    ui.input_int("深度##ptr_scan_max_depth", &mut game.ptr_scan_max_depth)
+        .build();
+    game.ptr_scan_max_depth = game.ptr_scan_max_depth.clamp(1, 10);
+
+    ui.input_text(
+        "最大偏移##ptr_scan_max_offset",
+        &mut game.ptr_scan_max_offset,
+    )
+    .build();
+
+    ui.input_int(
+        "快照上限(MB)##ptr_scan_max_snapshot_mb",
+        &mut game.ptr_scan_max_snapshot_mb,
+    )
+    .build();
+    game.ptr_scan_max_snapshot_mb = game
+        .ptr_scan_max_snapshot_mb
+        .clamp(64, PTR_SCAN_MAX_SNAPSHOT_MB);
+
+    let target = pointer_scan::parse_hex(&game.ptr_scan_target);
+
+    let is_running = game.ptr_scan_state.lock().unwrap().is_running;
+
+    if ui.button("扫描##ptr_scan_start")
+        && !is_running
+        && let Some(target) = target
+    {
+        let config = ScanConfig {
+            max_depth: game.ptr_scan_max_depth as usize,
+            max_offset: pointer_scan::parse_hex(&game.ptr_scan_max_offset)
+                .unwrap_or(ScanConfig::default().max_offset),
+            ..Default::default()
+        };
+        let max_bytes = game.ptr_scan_max_snapshot_mb as usize * 1024 * 1024;
+        let state = game.ptr_scan_state.clone();
+
+        {
+            let mut state = state.lock().unwrap();
+            state.is_running = true;
+            state.status = "正在抓取内存...".to_string();
+        }
+
+        spawn(move || {
+            let snapshot = process::capture(max_bytes);
+
+            state.lock().unwrap().status =
+                format!("正在扫描 {} MB...", snapshot.total_bytes() / 1024 / 1024);
+
+            let paths = pointer_scan::scan(&snapshot, target, &config);
+
+            let mut state = state.lock().unwrap();
+            state.status = format!("找到 {} 条路径", paths.len());
+            state.paths = paths;
+            state.is_running = false;
+        });
+    }
+
+    ui.same_line();
+    if ui.button("过滤##ptr_scan_filter")
+        && !is_running
+        && let Some(target) = target
+    {
+        let mut state = game.ptr_scan_state.lock().unwrap();
+        pointer_scan::filter(
+            &mut state.paths,
+            &process::LiveMemory,
+            &process::modules(),
+            target,
+        );
+        state.status = format!("剩余 {} 条路径", state.paths.len());
+    }
+
+    ui.same_line();
+    if ui.button("保存##ptr_scan_save") && !is_running {
+        let mut state = game.ptr_scan_state.lock().unwrap();
+        state.status = match std::fs::write(POINTER_SCAN_FILE, pointer_scan::save(&state.paths)) {
+            Ok(_) => format!("已保存到 {}", POINTER_SCAN_FILE),
+            Err(err) => format!("保存失败: {}", err),
+        };
+    }
+
+    ui.same_line();
+    if ui.button("读取##ptr_scan_load") && !is_running {
+        let mut state = game.ptr_scan_state.lock().unwrap();
+        match std::fs::read_to_string(POINTER_SCAN_FILE) {
+            Ok(text) => {
+                state.paths = pointer_scan::load(&text);
+                state.status = format!("读取 {} 条路径", state.paths.len());
+            }
+            Err(err) => state.status = format!("读取失败: {}", err),
+        }
+    }
+
+    let state = game.ptr_scan_state.lock().unwrap();
+
+    ui.text(&state.status);
+    ui.separator();
+
+    ui.child_window("##ptr_scan_paths").build(|| {
+        for path in state.paths.iter().take(500) {
+            ui.text(path.to_string());
+        }
+    });
+}
+
+unsafe fn on_frame_draw_ui_exports(game: &mut Game, ui: &hudhook::imgui::Ui) {
+    if ui.button("读取导出表##export_load") {
+        match exports::read_exports(&process::LiveMemory, ENGINE_DLL_INFO.base) {
+            Some(val) => {
+                game.export_status = format!(
+                    "{} 个导出，{} 个可以调用",
+                    val.len(),
+                    val.iter().filter(|export| export.shape.is_some()).count()
+                );
+                game.export_list = val;
+                game.export_selected = None;
+            }
+            None => game.export_status = "读取 engine_x64_rwdi.dll 导出表失败".to_string(),
+        }
+    }
+
+    ui.same_line();
+    ui.checkbox(
+        "只显示可调用##export_callable_only",
+        &mut game.export_callable_only,
+    );
+
+    ui.input_text("过滤##export_filter", &mut game.export_filter)
+        .hint("GetScreen")
+        .build();
+
+    ui.text(&game.export_status);
+
+    let filter = game.export_filter.to_lowercase();
+    let mut select = None;
+
+    ui.child_window("##export_list")
+        .size([0.0, 250.0])
+        .build(|| {
+            let mut shown = 0;
+
+            for (index, export) in game.export_list.iter().enumerate() {
+                if game.export_callable_only && export.shape.is_none() {
+                    continue;
+                }
+
+                let text = match &export.shape {
+                    Some(shape) => shape.to_string(),
+                    None => export.name.clone(),
+                };
+
+                if !filter.is_empty()
+                    && !text.to_lowercase().contains(&filter)
+                    && !export.name.to_lowercase().contains(&filter)
+                {
+                    continue;
+                }
+
+                shown += 1;
+                if shown > 500 {
+                    ui.text_disabled("只显示前 500 个，请加过滤条件");
+                    break;
+                }
+
+                if ui
+                    .selectable_config(format!("{}##export_{}", text, index))
+                    .selected(game.export_selected == Some(index))
+                    .build()
+                {
+                    select = Some(index);
+                }
+            }
+        });
+
+    if let Some(index) = select {
+        game.export_selected = Some(index);
+        game.export_arg.clear();
+        game.export_this_override = false;
+
+        if let Some(shape) = &game.export_list[index].shape
+            && let Some(this) = ThisObject::for_class(&shape.class)
+        {
+            game.export_this = this;
+        }
+    }
+
+    let Some(export) = game
+        .export_selected
+        .and_then(|index| game.export_list.get(index))
+        .cloned()
+    else {
+        return;
+    };
+
+    ui.separator();
+
+    ui.text(&export.name);
+    ui.same_line();
+    if ui.button("复制##export_copy") {
+        ui.set_clipboard_text(&export.name);
+    }
+
+    ui.text(format!(
+        "地址: {:#X} (engine + {:#X})",
+        export.addr,
+        export.addr - ENGINE_DLL_INFO.base
+    ));
+
+    let Some(shape) = &export.shape else {
+        ui.text_disabled("不支持的签名，不能调用");
+        return;
+    };
+
+    ui.text(shape.to_string());
+
+    if let Some(cb) = ui.begin_combo("this##export_this", game.export_this.to_string()) {
+        for this in exports::THIS_OBJECTS {
+            if ui
+                .selectable_config(this.to_string())
+                .selected(game.export_this == this)
+                .build()
+            {
+                game.export_this = this;
+                game.export_this_override = false;
+            }
+        }
+        cb.end();
+    }
+
+    if let Some(warning) = game.export_this.mismatch(&shape.class) {
+        ui.text_colored([1.0, 0.5, 0.0, 1.0], warning);
+        ui.checkbox(
+            format!("确认 this 就是 {}##export_this_override", shape.class),
+            &mut game.export_this_override,
+        );
+    }
+
+    if game.export_this == ThisObject::Manual {
+        ui.input_text("地址##export_this_addr", &mut game.export_this_addr)
+            .hint("0x...")
+            .build();
+    }
+
+    if let Some(arg) = &shape.arg {
+        ui.input_text(format!("参数 ({})##export_arg", arg), &mut game.export_arg)
+            .build();
+    }
+
+    if !shape.is_read_only() {
+        ui.text_colored(
+            [1.0, 0.5, 0.0, 1.0],
+            "不是 const 也不是 Get 开头，可能会改游戏状态，需要允许写入并且不在联机",
+        );
+    }
+
+    if ui.button("调用##export_call") {
+        run_export(game, &export, shape, ui.time());
+    }
+    ui.same_line();
+    if ui.button("清空结果##export_results_clear") {
+        game.export_results.clear();
+    }
+
+    ui.child_window("##export_results").build(|| {
+        for result in game.export_results.iter().rev() {
+            ui.text(result);
+        }
+    });
+}
+
+unsafe fn run_export(game: &mut Game, export: &Export, shape: &ExportShape, now: f64) {
+    if !shape.is_read_only()
+        && let Some(reason) = write_blocked(game, now)
+    {
+        game.export_status = reason.to_string();
+        return;
+    }
+
+    if !game.export_this_override
+        && let Some(warning) = game.export_this.mismatch(&shape.class)
+    {
+        game.export_status = warning;
+        return;
+    }
+
+    let world = match get_world() {
+        Some(val) => val,
+        None => {
+            game.export_status = "读不到 World".to_string();
+            return;
+        }
+    };
+
+    let this = match game.export_this {
+        ThisObject::GameDI => world.game_di_p as usize,
+        ThisObject::LevelDI => world.level_di_p as usize,
+        ThisObject::SessionCooperativeDI => world.session_cooperative_di_p as usize,
+        ThisObject::LocalClientDI => world.local_client_di_p as usize,
+        ThisObject::PlayerDI => world.player_di_p as usize,
+        ThisObject::CameraManagerDI => world.camera_manage_di_p as usize,
+        ThisObject::CameraFPPDI => world.camera_fpp_di_p as usize,
+        ThisObject::CrosshairTarget => game.crosshair_target,
+        ThisObject::Manual => pointer_scan::parse_hex(&game.export_this_addr).unwrap_or(0),
+    };
+
+    if this == 0 || (this as *const u8).is_bad_read_ptr(8) {
+        game.export_status = format!("this 不可读: {:#X}", this);
+        return;
+    }
+
+    let arg = match &shape.arg {
+        Some(ty) => match exports::parse_arg(ty, &game.export_arg) {
+            Some(val) => Some(val),
+            None => {
+                game.export_status = format!("参数不是 {}", ty);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let result = match call_export(export.addr, this, &shape.ret, arg) {
+        Some(val) => exports::format_result(&shape.ret, val),
+        None => {
+            game.export_status = "不支持的调用方式".to_string();
+            return;
+        }
+    };
+
+    game.export_results.push(format!(
+        "{}::{}({})  this={:#X}  -> {}",
+        shape.class,
+        shape.method,
+        game.export_arg.trim(),
+        this,
+        result
+    ));
+
+    // 只留最近的
+    if game.export_results.len() > 50 {
+        game.export_results.remove(0);
+    }
+
+    game.export_status = format!("已调用 {}", shape.method);
+}
+
+// 按值返回 vec2/vec3 时 this 后面是输出指针，和 impls::get_position 一样
+unsafe fn call_export(
+    addr: usize,
+    this: usize,
+    ret: &ReturnType,
+    arg: Option<CallArg>,
+) -> Option<CallResult> {
+    let mut out = [0.0f32; 3];
+    let mut args = Vec::new();
+
+    if matches!(ret, ReturnType::Vec2 | ReturnType::Vec3) {
+        args.push(CallArg::Int(out.as_mut_ptr() as i64));
+    }
+    args.extend(arg);
+
+    let result = match ret {
+        ReturnType::Void => {
+            invoke::<()>(addr, this, &args)?;
+            CallResult::Void
+        }
+        ReturnType::Scalar(ScalarType::F32) => CallResult::F32(invoke(addr, this, &args)?),
+        ReturnType::Scalar(ScalarType::F64) => CallResult::F64(invoke(addr, this, &args)?),
+        ReturnType::Scalar(_) => CallResult::Int(invoke(addr, this, &args)?),
+        ReturnType::Vec2 => {
+            invoke::<usize>(addr, this, &args)?;
+            CallResult::Vec2([out[0], out[1]])
+        }
+        ReturnType::Vec3 => {
+            invoke::<usize>(addr, this, &args)?;
+            CallResult::Vec3(out)
+        }
+    };
+
+    Some(result)
+}
+
+// x64 上整数和指针走 rcx rdx r8，浮点走 xmm，参数类型对了寄存器就对
+unsafe fn invoke<R>(addr: usize, this: usize, args: &[CallArg]) -> Option<R> {
+    use std::mem::transmute;
+
+    let result = match *args {
+        [] => transmute::<usize, unsafe extern "system" fn(usize) -> R>(addr)(this),
+        [CallArg::Int(a)] => {
+            transmute::<usize, unsafe extern "system" fn(usize, i64) -> R>(addr)(this, a)
+        }
+        [CallArg::F32(a)] => {
+            transmute::<usize, unsafe extern "system" fn(usize, f32) -> R>(addr)(this, a)
+        }
+        [CallArg::F64(a)] => {
+            transmute::<usize, unsafe extern "system" fn(usize, f64) -> R>(addr)(this, a)
+        }
+        [CallArg::Int(a), CallArg::Int(b)] => {
+            transmute::<usize, unsafe extern "system" fn(usize, i64, i64) -> R>(addr)(this, a, b)
+        }
+        [CallArg::Int(a), CallArg::F32(b)] => {
+            transmute::<usize, unsafe extern "system" fn(usize, i64, f32) -> R>(addr)(this, a, b)
+        }
+        [CallArg::Int(a), CallArg::F64(b)] => {
+            transmute::<usize, unsafe extern "system" fn(usize, i64, f64) -> R>(addr)(this, a, b)
+        }
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+// 换地图或者读不到 World 时，上一张地图的实体地址都已经释放了
+unsafe fn forget_world(game: &mut Game) {
+    game.world_key = None;
+    KNOWN_MODEL_OBJS.clear();
+
+    // 存下的坐标是上一张地图的，不能写进新的 PlayerDI
+    if game.freecam_active {
+        drop_freecam(game, "换地图了，已退出自由视角");
+    }
+}
+
+// 记住的实体里还读得出来的，不含自己，读不出来的顺便删掉
+unsafe fn known_objs(world: &World) -> Vec<Obj> {
+    let mut objs = Vec::new();
+
+    KNOWN_MODEL_OBJS.retain(|model_obj_p| {
+        let obj = match get_obj(*model_obj_p as *const ModelObject) {
+            Some(val) => val,
+            None => return false,
+        };
+
+        if obj.c_model_obj_p != world.player_c_model_obj_p {
+            objs.push(obj);
+        }
+
+        true
+    });
+
+    objs
+}
+
+// 雷达窗口在菜单关闭时也显示，但只有菜单打开时才能拖动和缩放
+unsafe fn draw_radar_window(game: &Game, ui: &hudhook::imgui::Ui, world: &World, known: &[Obj]) {
+    let player_pos = world.player_world_pos_p.read();
+    let yaw = world.camera_angle_p.read().x;
+
+    let mut dots = Vec::new();
+
+    for obj in known {
+        let (filter, color) = model_type_style(game, obj.model_obj_type);
+        if filter {
+            dots.push(RadarDot {
+                pos: obj.c_model_obj_world_pos,
+                color,
+            });
+        }
     }
+
+    let config = RadarConfig {
+        shape: game.radar_shape,
+        range: game.radar_range,
+        rings: game.radar_rings as u32,
+        background: [0.0, 0.0, 0.0, 0.35],
+        foreground: [1.0, 1.0, 1.0, 0.6],
+    };
+
+    ui.window("雷达##radar")
+        .title_bar(false)
+        .position([20.0, 200.0], hudhook::imgui::Condition::FirstUseEver)
+        .size([260.0, 260.0], hudhook::imgui::Condition::FirstUseEver)
+        .bg_alpha(0.0)
+        .scroll_bar(false)
+        .scrollable(false)
+        .movable(game.is_menu_on)
+        .resizable(game.is_menu_on)
+        .mouse_inputs(game.is_menu_on)
+        .build(|| {
+            let min = ui.cursor_screen_pos();
+            let size = ui.content_region_avail();
+
+            radar::draw_radar(
+                &mut ImguiDraw::new(ui),
+                Vec2::new(min[0], min[1]),
+                Vec2::new(size[0], size[1]),
+                &config,
+                player_pos,
+                yaw,
+                &dots,
+            );
+        });
 }
 
 #[inline(always)]
-unsafe fn draw_bones(ui: &hudhook::imgui::Ui, world: &World, obj: &Obj, color: [f32; 4]) {
-    let mut previous_world_pos: Vec3<f32> = Vec3::default();
+fn model_type_style(game: &Game, model_type: ModelType) -> (bool, [f32; 4]) {
+    match model_type {
+        ModelType::ZombieNormal => (game.toggle_filter_zombie_normal, game.color_zombie_normal),
+        ModelType::ZombieSpecial => (game.toggle_filter_zombie_special, game.color_zombie_special),
+        ModelType::ZombieHunter => (game.toggle_filter_zombie_hunter, game.color_zombie_hunter),
+        ModelType::SurvivorNormal => (
+            game.toggle_filter_survivor_normal,
+            game.color_survivor_nomal,
+        ),
+        ModelType::SurvivorSpecial => (
+            game.toggle_filter_survivor_special,
+            game.color_survivor_special,
+        ),
+        ModelType::SurvivorShopkeeper => (
+            game.toggle_filter_survivor_shopkeeper,
+            game.color_survivor_shopkeeper,
+        ),
+        ModelType::PlayerHuman => (game.toggle_filter_player_human, game.color_player_human),
+        ModelType::PlayerHunter => (game.toggle_filter_player_hunter, game.color_player_hunter),
+        ModelType::Other => (game.toggle_filter_other, game.color_other),
+    }
+}
 
-    let mut current_world_pos: Vec3<f32> = Vec3::default();
+// 把 0..=bone_probe_max 的每个骨骼索引都投影出来，标出零坐标和重复的索引
+// 超出骨架的索引交给引擎自己处理，目前见到的都是不写坐标
+unsafe fn probe_bones<D: Draw>(
+    game: &mut Game,
+    draw: &mut D,
+    ui: &hudhook::imgui::Ui,
+    world: &World,
+    camera: &Camera,
+) {
+    game.bone_probe_points.clear();
+
+    // 没锁定目标时用离准星最近的实体
+    game.bone_probe_target = if game.bone_probe_locked != 0 {
+        game.bone_probe_locked
+    } else {
+        game.crosshair_target
+    };
+
+    if game.bone_probe_target == 0 {
+        return;
+    }
+
+    let obj = match get_obj(game.bone_probe_target as *const ModelObject) {
+        Some(val) => val,
+        None => {
+            game.bone_probe_locked = 0;
+            game.bone_probe_target = 0;
+            return;
+        }
+    };
 
-    for bone_list in BONE_LISTS {
-        previous_world_pos.x = 0.0;
-        previous_world_pos.y = 0.0;
-        previous_world_pos.z = 0.0;
+    game.bone_probe_type = obj.model_obj_type;
+
+    // 超出骨架的索引不去调用 GetBoneJointPos，没有骨骼数时只探测 EBones 里的
+    game.bone_probe_count = get_bone_count(obj.model_obj_p);
+    let count = game
+        .bone_probe_count
+        .unwrap_or(EBones::RFoot as u32 + 1)
+        .min(game.bone_probe_max as u32 + 1);
+
+    let mut positions = Vec::with_capacity(count as usize);
+    // count 最大是 256，不能先转成 u8
+    for index in 0..count {
+        let world_pos: Vec3<f32> = Vec3::default();
+        get_bone_joint_pos(obj.model_obj_p, &world_pos, index as u8);
+        positions.push(world_pos);
+    }
 
-        for bone in *bone_list {
-            get_bone_joint_pos(obj.model_obj_p, &mut current_world_pos, *bone as u8);
+    let states = bone_probe::classify(&positions);
 
-            if previous_world_pos.x == 0.0
-                && previous_world_pos.y == 0.0
-                && previous_world_pos.z == 0.0
-            {
-                previous_world_pos = current_world_pos;
-                continue;
+    for (index, (world_pos, state)) in positions.into_iter().zip(states).enumerate() {
+        let screen_pos = if state == ProbeState::Zero {
+            None
+        } else {
+            world_to_screen(game, world, camera, &world_pos).map(|val| val.to_array())
+        };
+
+        game.bone_probe_points.push(ProbePoint {
+            index: index as u8,
+            world_pos,
+            screen_pos,
+            state,
+        });
+    }
+
+    bone_probe::draw_probe(draw, &game.bone_probe_points);
+
+    let mut labels =
+        bone_probe::probe_labels(&game.bone_probe_points, &game.bone_probe_names, |text| {
+            ui.calc_text_size(text)
+        });
+    label::layout(&mut labels);
+    label::draw_labels(draw, &labels, true);
+}
+
+// 当前地图的路点，屏幕外的画成边缘箭头
+unsafe fn draw_waypoints<D: Draw>(game: &Game, draw: &mut D, world: &World, camera: &Camera) {
+    let player_world_pos = world.player_world_pos_p.read();
+
+    for waypoint in game.waypoints.get(&waypoint_map(game)) {
+        let distance = (waypoint.pos - player_world_pos).length();
+
+        // 引擎投影对身后的点也会返回坐标，先用 Rust 投影判断在不在屏幕里
+        let on_screen = match camera.world_to_screen(waypoint.pos).screen() {
+            Some(val) => {
+                val.x >= 0.0 && val.x <= camera.width && val.y >= 0.0 && val.y <= camera.height
             }
+            None => false,
+        };
+
+        match world_to_screen(game, world, camera, &waypoint.pos) {
+            Some(screen_pos) if on_screen => waypoint::draw_marker(
+                draw,
+                screen_pos,
+                &waypoint.name,
+                distance,
+                game.waypoint_color,
+            ),
+            _ => esp::draw_offscreen_arrow(
+                draw,
+                camera,
+                camera.screen_direction(waypoint.pos),
+                distance,
+                game.waypoint_max_range,
+                game.waypoint_color,
+            ),
+        }
+    }
+}
+
+// 没有血量的物件，按分类的样式画图标，文字和实体的标签一起排版
+unsafe fn draw_items<D: Draw>(
+    game: &Game,
+    draw: &mut D,
+    ui: &hudhook::imgui::Ui,
+    world: &World,
+    camera: &Camera,
+    model_obj_addrs: &[usize],
+    labels: &mut Vec<(f32, Label)>,
+) {
+    let player_world_pos = world.player_world_pos_p.read();
+
+    for &model_obj_p in model_obj_addrs {
+        let item = match world::decode_item(&process::LiveMemory, &OFFSETS, model_obj_p) {
+            Some(val) => val,
+            None => continue,
+        };
 
-            let mut previous_screen_pos: Vec2<f32> = Vec2::default();
+        let style = game.item_styles[item.category as usize];
+        if !style.enabled {
+            continue;
+        }
 
-            let mut current_screen_pos: Vec2<f32> = Vec2::default();
+        let distance = (item.world_pos - player_world_pos).length();
+        if distance > style.max_distance {
+            continue;
+        }
 
-            point_to_screen(
-                world.camera_fpp_di_p,
-                &mut previous_screen_pos,
-                &mut previous_world_pos,
-            );
+        let screen_pos = match world_to_screen(game, world, camera, &item.world_pos) {
+            Some(val) => val,
+            None => continue,
+        };
 
-            point_to_screen(
-                world.camera_fpp_di_p,
-                &mut current_screen_pos,
-                &mut current_world_pos,
-            );
+        let mut color = style.color;
+        if game.lod_toggle {
+            color[3] *= esp::fade_alpha(distance, style.max_distance, game.lod_fade_start);
+        }
 
-            ui.get_background_draw_list()
-                .add_line(
-                    [previous_screen_pos.x, previous_screen_pos.y],
-                    [current_screen_pos.x, current_screen_pos.y],
-                    color,
-                )
-                .thickness(1.5)
-                .build();
+        items::draw_icon(draw, style.icon, screen_pos.to_array(), color);
+
+        let mut label = Label::new(screen_pos + Vec2::new(0.0, 6.0), Side::Below, color);
+
+        let line = format!("{}  {:.2}", item.category, distance);
+        let size = ui.calc_text_size(&line);
+        label.push(line, size);
+
+        if game.item_draw_preset {
+            let line = format!("{}  {:#X}", item.preset, item.logo);
+            let size = ui.calc_text_size(&line);
+            label.push(line, size);
+        }
+
+        labels.push((distance, label));
+    }
+}
+
+// 从相机到每个骨骼做一次 raytest，有一个骨骼看得到就算可见
+unsafe fn test_visibility(world: &World, obj: &Obj, bones: &[u8]) -> Visibility {
+    let camera_pos = get_position(world.camera_fpp_di_p);
+
+    let bones: Vec<(u8, bool)> = bones
+        .iter()
+        .map(|bone| {
+            let bone_world_pos: Vec3<f32> = Vec3::default();
+            get_bone_joint_pos(obj.model_obj_p, &bone_world_pos, *bone);
+
+            let visible = !bone_world_pos.is_zero()
+                && raytest_to_target(
+                    obj.model_obj_p,
+                    obj.model_obj_p,
+                    camera_pos,
+                    &bone_world_pos,
+                    4,
+                ) != 0;
+
+            (*bone, visible)
+        })
+        .collect();
+
+    Visibility {
+        visible: bones.iter().any(|(_, visible)| *visible),
+        bones,
+    }
+}
+
+// BONE_LIST 里每个骨骼的世界坐标，取不到的是 0
+#[inline(always)]
+unsafe fn bone_world_positions(obj: &Obj) -> [Vec3<f32>; BONE_LIST.len()] {
+    let mut positions = [Vec3::default(); BONE_LIST.len()];
+
+    for (bone, pos) in BONE_LIST.iter().zip(positions.iter_mut()) {
+        get_bone_joint_pos(obj.model_obj_p, pos, *bone as u8);
+    }
+
+    positions
+}
 
-            previous_world_pos = current_world_pos;
+#[inline(always)]
+unsafe fn project_box_3d(
+    game: &Game,
+    world: &World,
+    camera: &Camera,
+    bones: &[Vec3<f32>; BONE_LIST.len()],
+) -> Option<[[f32; 2]; 8]> {
+    let bone_at = |bone: EBones| match BONE_LIST.iter().position(|val| *val == bone) {
+        Some(index) => bones[index],
+        None => Vec3::default(),
+    };
+
+    // 肩膀和大腿从左到右的方向作为盒子的 x 轴
+    let right = bone_at(EBones::RClavicle) - bone_at(EBones::LClavicle) + bone_at(EBones::RThigh)
+        - bone_at(EBones::LThigh);
+
+    let valid: Vec<Vec3<f32>> = bones.iter().copied().filter(|val| !val.is_zero()).collect();
+
+    let corners = esp::oriented_box(&valid, right)?;
+
+    let mut screen = [[0.0; 2]; 8];
+    for (corner, screen_pos) in corners.iter().zip(screen.iter_mut()) {
+        *screen_pos = world_to_screen(game, world, camera, corner)?.to_array();
+    }
+
+    Some(screen)
+}
+
+#[inline(always)]
+unsafe fn project_bones(
+    game: &Game,
+    world: &World,
+    camera: &Camera,
+    obj: &Obj,
+    rig: &Rig,
+) -> Vec<Vec<(u8, [f32; 2])>> {
+    let mut current_world_pos: Vec3<f32> = Vec3::default();
+
+    let mut bone_lists = Vec::with_capacity(rig.chains.len());
+
+    for bone_list in &rig.chains {
+        let mut points = Vec::with_capacity(bone_list.len());
+
+        for bone in bone_list {
+            get_bone_joint_pos(obj.model_obj_p, &mut current_world_pos, *bone);
+
+            // 链头的骨骼还没取到坐标时跳过
+            if points.is_empty() && current_world_pos.is_zero() {
+                continue;
+            }
+
+            // 身后的骨骼不画，否则连线会穿过整个屏幕
+            if let Some(current_screen_pos) =
+                world_to_screen(game, world, camera, &current_world_pos)
+            {
+                points.push((*bone, current_screen_pos.to_array()));
+            }
         }
+
+        bone_lists.push(points);
     }
+
+    bone_lists
 }
 
 #[inline(always)]
-unsafe fn aim_update_obj(game: &mut Game, world: &World, obj: &Obj) {
+unsafe fn aim_update_obj(game: &mut Game, world: &World, camera: &Camera, obj: &Obj) {
     if !match obj.model_obj_type {
         ModelType::Other | ModelType::SurvivorNormal | ModelType::SurvivorShopkeeper => return,
         ModelType::ZombieNormal => game.aim_toggle_filter_zombie_normal,
@@ -1007,19 +4579,16 @@ unsafe fn aim_update_obj(game: &mut Game, world: &World, obj: &Obj) {
         return;
     }
 
-    let mut screen_pos: Vec2<f32> = Vec2::default();
-
     let world_pos: Vec3<f32> = Vec3::default();
 
     get_bone_joint_pos(obj.model_obj_p, &world_pos, game.aim_selected_bone as u8);
 
-    point_to_screen(world.camera_fpp_di_p, &mut screen_pos, &world_pos);
-
-    let pow_x = (screen_pos.x - get_screen_width(world.game_di_p) as f32 / 2.0).powf(2.0);
-
-    let pow_y = (screen_pos.y - get_screen_height(world.game_di_p) as f32 / 2.0).powf(2.0);
+    let screen_pos = match world_to_screen(game, world, camera, &world_pos) {
+        Some(val) => val,
+        None => return,
+    };
 
-    let distance = (pow_x + pow_y).sqrt();
+    let distance = screen_pos.distance(camera.center());
 
     if distance > game.aim_fov || distance > game.aim_best_closest_distance {
         return;
@@ -1030,7 +4599,7 @@ unsafe fn aim_update_obj(game: &mut Game, world: &World, obj: &Obj) {
 }
 
 #[inline(always)]
-unsafe fn aim_lock_obj(game: &mut Game, world: &World, selected_bone: u8) {
+unsafe fn aim_lock_obj(game: &mut Game, world: &World, camera: &Camera, selected_bone: u8) {
     if !game.aim_is_key_down {
         if game.aim_is_mouse_patched {
             game.aim_is_mouse_patched = false;
@@ -1091,27 +4660,10 @@ unsafe fn aim_lock_obj(game: &mut Game, world: &World, selected_bone: u8) {
 
         get_bone_joint_pos(game.aim_locking_model_obj_p, &mut world_pos, selected_bone);
 
-        let mut pos: Vec3<f32> = Vec3::default();
-
-        let camera_world_pos_p = get_position(world.camera_fpp_di_p);
-
-        pos.x = world_pos.x - camera_world_pos_p.read().x;
-        pos.y = world_pos.z - camera_world_pos_p.read().z;
-        pos.z = world_pos.y - camera_world_pos_p.read().y;
+        let angle = math::yaw_pitch(world_pos - camera.pos);
 
-        let mut yaw = (pos.y / pos.x).atan() * 180.0 / PI;
-        let pitch = (pos.z / (pos.x.powi(2) + pos.y.powi(2)).sqrt()).atan() * 180.0 / PI;
-
-        if yaw < 0.0 && pos.y > 0.0 {
-            yaw += 180.0;
-        }
-
-        if yaw > 0.0 && pos.y < 0.0 {
-            yaw -= 180.0;
-        }
-
-        (*world.camera_angle_p).x = yaw;
-        (*world.camera_angle_p).y = pitch;
+        (*world.camera_angle_p).x = angle.x;
+        (*world.camera_angle_p).y = angle.y;
     } else {
         game.aim_is_key_down = false;
 
@@ -1155,154 +4707,63 @@ unsafe fn mouse_unpatch(game: &mut Game) {
 }
 
 #[inline(always)]
-unsafe fn get_world() -> Option<World> {
-    let mut world = World {
-        game_p: null(),
-        game_di_p: null(),
-        session_cooperative_di_p: null(),
-        level_di_p: null(),
-        c_level_p: null(),
-        local_client_di_p: null(),
-        player_di_p: null_mut(),
-        player_c_model_obj_p: null(),
-
-        camera_manage_di_p: null(),
-        camera_fpp_di_p: null(),
-
-        player_world_pos_p: null_mut(),
-        camera_angle_p: null_mut(),
-    };
-
-    // CGame
-    world.game_p = CGAME_PP.read();
-
-    // GameDI
-    let game_di_pp = world.game_p.byte_add(0x98).cast::<*const GameDI>();
-    if game_di_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.game_di_p = game_di_pp.read();
-    if world.game_di_p.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    // SessionCooperativeDI
-    let session_cooperative_di_pp = world
-        .game_di_p
-        .byte_add(0x540)
-        .cast::<*const SessionCooperativeDI>();
-    if session_cooperative_di_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.session_cooperative_di_p = session_cooperative_di_pp.read();
-    if world.session_cooperative_di_p.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    // LevelDI
-    let level_di_pp = world
-        .session_cooperative_di_p
-        .byte_add(0xB0)
-        .cast::<*const LevelDI>();
-    if level_di_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.level_di_p = level_di_pp.read();
-    if world.level_di_p.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    // CLevel
-    let c_level_pp = world.level_di_p.byte_add(0x8).cast::<*const CLevel>();
-    if c_level_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.c_level_p = c_level_pp.read();
-    if world.c_level_p.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    // LocalClientDI
-    let local_client_pp = world
-        .session_cooperative_di_p
-        .byte_add(0xB8)
-        .cast::<*const LocalClientDI>();
-    if local_client_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.local_client_di_p = local_client_pp.read();
-    if world.local_client_di_p.is_bad_read_ptr(8) {
-        return None;
+unsafe fn get_camera(game: &Game, world: &World) -> Camera {
+    let angle = world.camera_angle_p.read();
+
+    Camera {
+        pos: get_position(world.camera_fpp_di_p).read(),
+        yaw: angle.x,
+        pitch: angle.y,
+        fov: game.projection_fov,
+        width: get_screen_width(world.game_di_p) as f32,
+        height: get_screen_height(world.game_di_p) as f32,
     }
+}
 
-    // PlayerDI
-    let player_di_pp = world
-        .local_client_di_p
-        .byte_add(0x50)
-        .cast::<*mut PlayerDI>();
-    if player_di_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.player_di_p = player_di_pp.read();
-    if world.player_di_p.is_bad_read_ptr(8) {
-        return None;
+// 引擎的 PointToScreen 对身后的点也会返回一个镜像过去的坐标，Rust 投影会返回 None
+#[inline(always)]
+unsafe fn world_to_screen(
+    game: &Game,
+    world: &World,
+    camera: &Camera,
+    world_pos: &Vec3<f32>,
+) -> Option<Vec2<f32>> {
+    if game.projection_use_rust {
+        return camera.world_to_screen(*world_pos).screen();
     }
 
-    // PlayerPos
-    world.player_world_pos_p = world.player_di_p.byte_add(0x7B0).cast::<Vec3<f32>>();
-
-    if world.player_world_pos_p.is_bad_read_ptr(12) {
-        return None;
-    }
-    if world.player_world_pos_p.read().y == 0.0 {
-        return None;
-    }
+    let mut screen_pos: Vec2<f32> = Vec2::default();
+    point_to_screen(world.camera_fpp_di_p, &mut screen_pos, world_pos);
 
-    // Angle
-    world.camera_angle_p = world.player_di_p.byte_add(0x111C).cast::<Vec2<f32>>();
+    Some(screen_pos)
+}
 
-    if world.camera_angle_p.is_bad_read_ptr(8) {
-        return None;
-    }
+// 返回 Rust 投影和引擎投影的像素误差，需要时顺便用引擎投影校准视角
+#[inline(always)]
+unsafe fn check_projection(game: &mut Game, world: &World, camera: &Camera, obj: &Obj) -> f32 {
+    let mut engine_screen_pos: Vec2<f32> = Vec2::default();
+    point_to_screen(
+        world.camera_fpp_di_p,
+        &mut engine_screen_pos,
+        &obj.c_model_obj_world_pos,
+    );
 
-    // PlayerCModelObject
-    let player_c_model_obj_pp = world
-        .player_di_p
-        .byte_sub(0x50)
-        .cast::<*const CModelObject>();
-    if player_c_model_obj_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.player_c_model_obj_p = player_c_model_obj_pp.read();
-    if world.player_c_model_obj_p.is_bad_read_ptr(8) {
-        return None;
+    if game.projection_fit_fov
+        && let Some(fov) = camera.fit_fov(obj.c_model_obj_world_pos, engine_screen_pos)
+    {
+        game.projection_fov = fov;
+        game.projection_fit_fov = false;
     }
 
-    // CameraManagerDI
-    let camera_manager_pp = world
-        .session_cooperative_di_p
-        .byte_add(0xC0)
-        .cast::<*const CameraManagerDI>();
-    if camera_manager_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.camera_manage_di_p = camera_manager_pp.read();
-    if world.camera_manage_di_p.is_bad_read_ptr(8) {
-        return None;
+    match camera.world_to_screen(obj.c_model_obj_world_pos).screen() {
+        Some(val) => val.distance(engine_screen_pos),
+        None => 0.0,
     }
+}
 
-    // CameraFPPDI
-    let camera_fpp_di_pp = world
-        .camera_manage_di_p
-        .byte_add(0x50)
-        .cast::<*const CameraFPPDI>();
-    if camera_fpp_di_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    world.camera_fpp_di_p = camera_fpp_di_pp.read();
-    if world.camera_fpp_di_p.is_bad_read_ptr(8) {
-        return None;
-    }
+#[inline(always)]
+unsafe fn get_world() -> Option<World> {
+    let world = world::resolve_world(&process::LiveMemory, &OFFSETS, CGAME_PP as usize)?;
 
     impls::get_objects_in_frustum(world.camera_fpp_di_p, &raw const WORLD_MODEL_OBJ_ARRAY, 0.0);
 
@@ -1311,170 +4772,7 @@ unsafe fn get_world() -> Option<World> {
 
 #[inline(always)]
 unsafe fn get_obj(model_obj_p: *const ModelObject) -> Option<Obj> {
-    let mut obj = Obj {
-        model_obj_p,
-        ..Default::default()
-    };
-
-    let c_model_obj_pp = obj.model_obj_p.byte_add(0x20).cast::<*const CModelObject>();
-    if c_model_obj_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    // CModelObject
-    obj.c_model_obj_p = c_model_obj_pp.read();
-    if obj.c_model_obj_p.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    // Logo
-    obj.c_model_obj_logo_p = obj.c_model_obj_p.byte_add(0x340).cast::<u32>();
-    if obj.c_model_obj_logo_p.is_bad_read_ptr(4) {
-        return None;
-    }
-
-    // 0x1 AI Preset , Shape Box, PlayerFall 等等
-    // 0x2 可能是书信物件，也可能记错了
-    // 0x8 可能是可互动物件，也可能记错了
-    // 0x20 玩家: 人类和猎手
-    // 0x40 站着的僵尸
-    // 0x80 倒地的丧尸
-    // 0x2000 所有NPC，包括商人
-    // 0x40000 正在倒地的丧尸
-
-    match obj.c_model_obj_logo_p.read() {
-        0x0 | 0x1 | 0x2 | 0x8 => return None,
-        _ => (),
-    }
-
-    let world_pos_x = obj.c_model_obj_p.byte_add(0x11C).cast::<f32>();
-    let world_pos_y = obj.c_model_obj_p.byte_add(0x12C).cast::<f32>();
-    let world_pos_z = obj.c_model_obj_p.byte_add(0x13C).cast::<f32>();
-
-    if world_pos_x.is_bad_read_ptr(4)
-        || world_pos_y.is_bad_read_ptr(4)
-        || world_pos_z.is_bad_read_ptr(4)
-    {
-        return None;
-    }
-
-    obj.c_model_obj_world_pos.x = world_pos_x.read();
-    obj.c_model_obj_world_pos.y = world_pos_y.read();
-    obj.c_model_obj_world_pos.z = world_pos_z.read();
-
-    // get_world_position(obj.model_obj_p, &obj.c_model_obj_world_pos);
-
-    if obj.c_model_obj_world_pos.x == 0.0
-        && obj.c_model_obj_world_pos.y == 0.0
-        && obj.c_model_obj_world_pos.z == 0.0
-    {
-        return None;
-    }
-
-    // ModelObjectHealth
-    let health_module_pp = obj
-        .model_obj_p
-        .byte_add(0xCE8)
-        .cast::<*const HealthModule>();
-    if health_module_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-    let model_health_p = health_module_pp.read();
-    if model_health_p.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    obj.model_obj_health_p = model_health_p.byte_add(0x78).cast::<f32>().cast_mut();
-    if obj.model_obj_health_p.is_bad_read_ptr(4) {
-        return None;
-    }
-    if obj.model_obj_health_p.read() == 0.0 {
-        return None;
-    }
-
-    // ModelObjectTypeData
-    let model_type_data_pp = obj.c_model_obj_p.byte_add(0x60).cast::<*const i8>();
-    if model_type_data_pp.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    obj.model_obj_str_p = model_type_data_pp.read();
-    if obj.model_obj_str_p.is_bad_read_ptr(8) {
-        return None;
-    }
-
-    obj.model_obj_str = std::ffi::CStr::from_ptr(obj.model_obj_str_p)
-        .to_string_lossy()
-        .to_string();
-
-    let bytes = obj.model_obj_str.as_bytes();
-
-    let start = bytes.iter().position(|&b| b == b';')? + 1;
-    if start >= bytes.len() {
-        return None;
-    }
-
-    match &bytes[start..] {
-        // b if b.starts_with(b"Nig")
-        //     || b.starts_with(b"Scr")
-        //     || b.starts_with(b"Gas")
-        //     || b.starts_with(b"Dem")
-        //     || b.starts_with(b"Goo")
-        //     || b.starts_with(b"Toa")
-        //     || b.starts_with(b"Bom")
-        //     // BTZ_Su BTZ_Bi
-        //     || b.starts_with(b"BTZ_Su") =>
-        // {
-        //     obj.model_obj_type = ModelType::ZombieSpecial
-        // }
-        b if b.starts_with(b"Bi") || b.starts_with(b"Vi") || b.starts_with(b"Dea") => {
-            obj.model_obj_type = ModelType::ZombieNormal
-        }
-
-        b if b.starts_with(b"Ni")
-            || b.starts_with(b"Sc")
-            || b.starts_with(b"Ga")
-            || b.starts_with(b"Dem")
-            || b.starts_with(b"Go")
-            || b.starts_with(b"To")
-            || b.starts_with(b"Bo")
-            // BTZ_Su BTZ_Bi
-            || b.starts_with(b"BT") =>
-        {
-            obj.model_obj_type = ModelType::ZombieSpecial
-        }
-
-        b if b.starts_with(b"Vo") => obj.model_obj_type = ModelType::ZombieHunter,
-
-        b if b.starts_with(b"Zo") || b.starts_with(b"DW") => {
-            // DW_Zombie
-            obj.model_obj_type = ModelType::PlayerHunter
-        }
-
-        // enc很多是中立    Quest_GoodNight是友好NPC
-        b if b.starts_with(b"en") || b.starts_with(b"0T") => {
-            obj.model_obj_type = ModelType::SurvivorSpecial
-        }
-
-        b if b.starts_with(b"Sh") || b.starts_with(b"Sp") => {
-            obj.model_obj_type = ModelType::SurvivorShopkeeper
-        }
-
-        b if b.starts_with(b"Pl") => obj.model_obj_type = ModelType::PlayerHuman,
-
-        // 塔楼上面的坐在桌子面前操作的机械工
-        // 泽雷博士车门前躺着的马里克
-        // b if b.starts_with(b"Hub") || b.starts_with(b"Maa") => obj.model_obj_type = ModelType::SurvivorNormal,
-        _ => {
-            if obj.c_model_obj_logo_p.read() == 0x2000 {
-                obj.model_obj_type = ModelType::SurvivorNormal
-            } else {
-                obj.model_obj_type = ModelType::Other
-            }
-        }
-    };
-
-    Some(obj)
+    world::decode_obj(&process::LiveMemory, &OFFSETS, model_obj_p as usize)
 }
 
 // #[target_feature(enable = "sse")]
@@ -1516,6 +4814,18 @@ unsafe extern "system" fn DllMain(
                 std::thread::sleep(std::time::Duration::from_secs(5));
             }
 
+            OFFSETS.derive(&process::LiveMemory, |module, pattern| {
+                let info = libmem::find_module(module)?;
+                let addr = libmem::sig_scan(pattern, info.base, info.size)?;
+
+                // 后面还有一处匹配时分不清是哪条指令
+                let next = addr + 1;
+                match libmem::sig_scan(pattern, next, info.base + info.size - next) {
+                    Some(_) => None,
+                    None => Some(addr),
+                }
+            });
+
             let mut game = Game::default();
 
             game.aim_mouse_yaw_p = 8 + libmem::sig_scan(
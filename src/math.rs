@@ -0,0 +1,491 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+#[repr(C)]
+pub(crate) struct Vec2<T> {
+    pub(crate) x: T,
+    pub(crate) y: T,
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+#[repr(C)]
+pub(crate) struct Vec3<T> {
+    pub(crate) x: T,
+    pub(crate) y: T,
+    pub(crate) z: T,
+}
+
+impl Vec2<f32> {
+    pub(crate) const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub(crate) fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub(crate) fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub(crate) fn distance(self, other: Self) -> f32 {
+        (self - other).length()
+    }
+
+    pub(crate) fn normalize(self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            return Self::default();
+        }
+
+        self * (1.0 / len)
+    }
+
+    pub(crate) fn to_array(self) -> [f32; 2] {
+        [self.x, self.y]
+    }
+}
+
+impl Vec3<f32> {
+    pub(crate) const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub(crate) fn is_zero(self) -> bool {
+        self.x == 0.0 && self.y == 0.0 && self.z == 0.0
+    }
+
+    pub(crate) fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub(crate) fn cross(self, other: Self) -> Self {
+        Self {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    pub(crate) fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    // 水平面 (x, z) 上的长度
+    pub(crate) fn length_2d(self) -> f32 {
+        (self.x * self.x + self.z * self.z).sqrt()
+    }
+
+    pub(crate) fn normalize(self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            return Self::default();
+        }
+
+        self * (1.0 / len)
+    }
+}
+
+impl<T: Add<Output = T>> Add for Vec2<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Vec2<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Vec2<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Vec2<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl<T: Add<Output = T>> Add for Vec3<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Vec3<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Vec3<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Vec3<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+// 游戏里 y 轴朝上，yaw 从 +x 转向 +z，pitch 向上为正，单位都是角度
+// 和 PlayerDI + Angle 里的 (yaw, pitch) 一致
+pub(crate) fn yaw_pitch(delta: Vec3<f32>) -> Vec2<f32> {
+    Vec2 {
+        x: delta.z.atan2(delta.x).to_degrees(),
+        y: delta.y.atan2(delta.length_2d()).to_degrees(),
+    }
+}
+
+pub(crate) fn direction(yaw: f32, pitch: f32) -> Vec3<f32> {
+    let (yaw_sin, yaw_cos) = yaw.to_radians().sin_cos();
+    let (pitch_sin, pitch_cos) = pitch.to_radians().sin_cos();
+
+    Vec3 {
+        x: pitch_cos * yaw_cos,
+        y: pitch_sin,
+        z: pitch_cos * yaw_sin,
+    }
+}
+
+// 行主序，列向量: v' = m * v
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Mat4 {
+    pub(crate) m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    // 左手系，相机空间: x 向右，y 向上，z 朝前
+    pub(crate) fn look_to(pos: Vec3<f32>, forward: Vec3<f32>, up: Vec3<f32>) -> Self {
+        let forward = forward.normalize();
+        let right = up.cross(forward).normalize();
+        let up = forward.cross(right);
+
+        Self {
+            m: [
+                [right.x, right.y, right.z, -right.dot(pos)],
+                [up.x, up.y, up.z, -up.dot(pos)],
+                [forward.x, forward.y, forward.z, -forward.dot(pos)],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    // fov 是垂直视角，输出的 w 等于相机空间里的深度
+    pub(crate) fn perspective(fov: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (fov.to_radians() / 2.0).tan();
+
+        Self {
+            m: [
+                [f / aspect, 0.0, 0.0, 0.0],
+                [0.0, f, 0.0, 0.0],
+                [0.0, 0.0, far / (far - near), -near * far / (far - near)],
+                [0.0, 0.0, 1.0, 0.0],
+            ],
+        }
+    }
+
+    pub(crate) fn transform(&self, v: Vec3<f32>) -> [f32; 4] {
+        let mut out = [0.0; 4];
+
+        for (i, row) in self.m.iter().enumerate() {
+            out[i] = row[0] * v.x + row[1] * v.y + row[2] * v.z + row[3];
+        }
+
+        out
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, val) in row.iter_mut().enumerate() {
+                *val = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+
+        Self { m }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Projection {
+    // 在相机前面，坐标可能在屏幕外
+    Screen(Vec2<f32>),
+    Behind,
+}
+
+impl Projection {
+    pub(crate) fn screen(self) -> Option<Vec2<f32>> {
+        match self {
+            Projection::Screen(val) => Some(val),
+            Projection::Behind => None,
+        }
+    }
+}
+
+const NEAR: f32 = 0.1;
+const FAR: f32 = 10000.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Camera {
+    pub(crate) pos: Vec3<f32>,
+    pub(crate) yaw: f32,
+    pub(crate) pitch: f32,
+    // 垂直视角
+    pub(crate) fov: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+impl Camera {
+    pub(crate) fn forward(&self) -> Vec3<f32> {
+        direction(self.yaw, self.pitch)
+    }
+
+    pub(crate) fn view(&self) -> Mat4 {
+        Mat4::look_to(self.pos, self.forward(), Vec3::new(0.0, 1.0, 0.0))
+    }
+
+    pub(crate) fn view_projection(&self) -> Mat4 {
+        Mat4::perspective(self.fov, self.width / self.height, NEAR, FAR) * self.view()
+    }
+
+    pub(crate) fn center(&self) -> Vec2<f32> {
+        Vec2::new(self.width / 2.0, self.height / 2.0)
+    }
+
+    pub(crate) fn world_to_screen(&self, pos: Vec3<f32>) -> Projection {
+        let [x, y, _, w] = self.view_projection().transform(pos);
+
+        if w < NEAR {
+            return Projection::Behind;
+        }
+
+        Projection::Screen(Vec2 {
+            x: (x / w + 1.0) / 2.0 * self.width,
+            y: (1.0 - y / w) / 2.0 * self.height,
+        })
+    }
+
+    // 屏幕中心指向目标的方向，目标在身后时也有意义 (屏幕 y 向下)
+    pub(crate) fn screen_direction(&self, pos: Vec3<f32>) -> Vec2<f32> {
+        let [x, y, z, _] = self.view().transform(pos);
+
+        // 正后方时指向屏幕下方，x/y 只剩浮点误差时归一化出来的方向是乱的
+        if z < 0.0 && x.hypot(y) <= z.abs() * 1e-4 {
+            return Vec2::new(0.0, 1.0);
+        }
+
+        Vec2::new(x, -y).normalize()
+    }
+
+    // 用引擎投影出的一个点反推垂直视角，点离屏幕中心太近时算不准
+    pub(crate) fn fit_fov(&self, pos: Vec3<f32>, engine_screen: Vec2<f32>) -> Option<f32> {
+        let [x, y, z, _] = self.view().transform(pos);
+        if z < NEAR {
+            return None;
+        }
+
+        let center = self.center();
+        // 屏幕上的偏移 = cam / z * center.y / tan(fov / 2)，水平方向也是除以 center.y
+        let (offset, cam) =
+            if (engine_screen.y - center.y).abs() >= (engine_screen.x - center.x).abs() {
+                (center.y - engine_screen.y, y)
+            } else {
+                (engine_screen.x - center.x, x)
+            };
+
+        if offset.abs() < 8.0 || cam == 0.0 || (offset > 0.0) != (cam > 0.0) {
+            return None;
+        }
+
+        let fov = (cam / z * center.y / offset).atan().to_degrees() * 2.0;
+        if fov > 1.0 && fov < 179.0 {
+            Some(fov)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_angle(actual: Vec2<f32>, yaw: f32, pitch: f32) {
+        assert!(
+            (actual.x - yaw).abs() < 1e-3 && (actual.y - pitch).abs() < 1e-3,
+            "{:?} != ({}, {})",
+            actual,
+            yaw,
+            pitch
+        );
+    }
+
+    fn camera(fov: f32) -> Camera {
+        Camera {
+            pos: Vec3::new(1.0, 2.0, 3.0),
+            yaw: 30.0,
+            pitch: -10.0,
+            fov,
+            width: 1920.0,
+            height: 1080.0,
+        }
+    }
+
+    #[test]
+    fn yaw_pitch_quadrants() {
+        assert_angle(yaw_pitch(Vec3::new(1.0, 0.0, 1.0)), 45.0, 0.0);
+        assert_angle(yaw_pitch(Vec3::new(-1.0, 0.0, 1.0)), 135.0, 0.0);
+        assert_angle(yaw_pitch(Vec3::new(-1.0, 0.0, -1.0)), -135.0, 0.0);
+        assert_angle(yaw_pitch(Vec3::new(1.0, 0.0, -1.0)), -45.0, 0.0);
+    }
+
+    #[test]
+    fn yaw_pitch_axes() {
+        // dx = 0
+        assert_angle(yaw_pitch(Vec3::new(0.0, 0.0, 2.0)), 90.0, 0.0);
+        assert_angle(yaw_pitch(Vec3::new(0.0, 0.0, -2.0)), -90.0, 0.0);
+        // dz = 0
+        assert_angle(yaw_pitch(Vec3::new(2.0, 0.0, 0.0)), 0.0, 0.0);
+        assert_angle(yaw_pitch(Vec3::new(-2.0, 0.0, 0.0)), 180.0, 0.0);
+        // 正上方和正下方，水平距离为 0 时 yaw 取 0
+        assert_angle(yaw_pitch(Vec3::new(0.0, 3.0, 0.0)), 0.0, 90.0);
+        assert_angle(yaw_pitch(Vec3::new(0.0, -3.0, 0.0)), 0.0, -90.0);
+        // 斜上方
+        assert_angle(yaw_pitch(Vec3::new(1.0, 1.0, 0.0)), 0.0, 45.0);
+    }
+
+    #[test]
+    fn yaw_pitch_round_trips_through_direction() {
+        for delta in [
+            Vec3::new(3.0, -1.0, 2.0),
+            Vec3::new(-0.5, 4.0, -7.0),
+            Vec3::new(0.0, 0.2, -1.0),
+        ] {
+            let angle = yaw_pitch(delta);
+            let dir = direction(angle.x, angle.y);
+            let expected = delta * (1.0 / delta.length());
+
+            assert!((dir - expected).length() < 1e-5, "{:?} {:?}", dir, expected);
+        }
+    }
+
+    #[test]
+    fn world_to_screen_center_and_behind() {
+        let camera = camera(60.0);
+        let forward = camera.forward();
+
+        let front = camera
+            .world_to_screen(camera.pos + forward * 10.0)
+            .screen()
+            .unwrap();
+        assert!(front.distance(camera.center()) < 0.01);
+
+        // 身后和近平面以内都算 Behind
+        assert_eq!(
+            camera.world_to_screen(camera.pos - forward * 10.0),
+            Projection::Behind
+        );
+        assert_eq!(
+            camera.world_to_screen(camera.pos + forward * 0.05),
+            Projection::Behind
+        );
+
+        // 上方的点在屏幕中心上面 (屏幕 y 向下)
+        let above = camera
+            .world_to_screen(camera.pos + forward * 10.0 + Vec3::new(0.0, 1.0, 0.0))
+            .screen()
+            .unwrap();
+        assert!(above.y < camera.center().y);
+    }
+
+    #[test]
+    fn screen_direction_behind_points_down() {
+        let camera = camera(60.0);
+
+        let dir = camera.screen_direction(camera.pos - camera.forward() * 5.0);
+        assert!((dir - Vec2::new(0.0, 1.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn fit_fov_recovers_engine_fov() {
+        let engine = camera(75.0);
+        let guess = camera(60.0);
+
+        for offset in [Vec3::new(0.0, 3.0, 0.0), Vec3::new(0.0, 0.0, 4.0)] {
+            let pos = engine.pos + engine.forward() * 10.0 + offset;
+            let screen = engine.world_to_screen(pos).screen().unwrap();
+
+            let fov = guess.fit_fov(pos, screen).unwrap();
+            assert!((fov - 75.0).abs() < 0.01, "{}", fov);
+        }
+    }
+
+    #[test]
+    fn fit_fov_rejects_center_and_behind() {
+        let camera = camera(60.0);
+        let forward = camera.forward();
+
+        let pos = camera.pos + forward * 10.0;
+        assert_eq!(camera.fit_fov(pos, camera.center()), None);
+
+        let pos = camera.pos - forward * 10.0;
+        assert_eq!(camera.fit_fov(pos, Vec2::new(100.0, 100.0)), None);
+    }
+}
@@ -0,0 +1,373 @@
+use crate::snapshot::Memory;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum OffsetStatus {
+    #[default]
+    Unverified,
+    Confirmed,
+    Rederived,
+}
+
+impl std::fmt::Display for OffsetStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OffsetStatus::Unverified => write!(f, "未验证"),
+            OffsetStatus::Confirmed => write!(f, "已确认"),
+            OffsetStatus::Rederived => write!(f, "已重新推导"),
+        }
+    }
+}
+
+// 使用该偏移的指令，偏移 = 指令里 disp_pos 处的位移 + bias
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OffsetSig {
+    pub(crate) module: &'static str,
+    pub(crate) pattern: &'static str,
+    pub(crate) disp_pos: usize,
+    pub(crate) disp_size: usize,
+    pub(crate) bias: isize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Offset {
+    pub(crate) name: &'static str,
+    pub(crate) profile: usize,
+    pub(crate) value: usize,
+    pub(crate) sig: Option<OffsetSig>,
+    // 没有特征码时写明原因和更新后怎么重新找
+    pub(crate) no_sig: Option<&'static str>,
+    pub(crate) status: OffsetStatus,
+    pub(crate) derived: Option<isize>,
+}
+
+impl Offset {
+    const fn new(name: &'static str, profile: usize) -> Self {
+        Self {
+            name,
+            profile,
+            value: profile,
+            sig: None,
+            no_sig: None,
+            status: OffsetStatus::Unverified,
+            derived: None,
+        }
+    }
+
+    const fn with_sig(mut self, sig: OffsetSig) -> Self {
+        self.sig = Some(sig);
+        self
+    }
+
+    const fn without_sig(mut self, reason: &'static str) -> Self {
+        self.no_sig = Some(reason);
+        self
+    }
+}
+
+// 没有特征码的偏移各自写了原因和更新后怎么重新找，通用的办法是 "指针扫描" 页:
+//   链上的指针以下一级对象的地址为目标扫描，路径最后一级就是偏移
+//   字段以字段本身的地址为目标扫描，比如用 "传送" 页显示的坐标在内存里搜到的地址
+
+// 重新推导出的值离配置值太远时，多半是特征码匹配到了别的指令
+const MAX_DRIFT: usize = 0x400;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Offsets {
+    pub(crate) game_di: Offset,
+    pub(crate) session_cooperative_di: Offset,
+    pub(crate) level_di: Offset,
+    pub(crate) c_level: Offset,
+    pub(crate) local_client_di: Offset,
+    pub(crate) player_di: Offset,
+    pub(crate) player_world_pos: Offset,
+    pub(crate) camera_angle: Offset,
+    pub(crate) player_c_model_obj: Offset,
+    pub(crate) camera_manager_di: Offset,
+    pub(crate) camera_fpp_di: Offset,
+
+    pub(crate) model_obj_array_entry: Offset,
+    pub(crate) c_model_obj: Offset,
+    pub(crate) c_model_obj_logo: Offset,
+    pub(crate) c_model_obj_world_pos: Offset,
+    pub(crate) health_module: Offset,
+    pub(crate) health: Offset,
+    pub(crate) model_type_data: Offset,
+}
+
+impl Offsets {
+    pub(crate) const fn new() -> Self {
+        Self {
+            game_di: Offset::new("CGame -> GameDI", 0x98).without_sig(
+                "还没在 engine 里确认读它的指令；\
+                 以导出页 IGame 方法能正常返回的 this 为目标，从 CGame 做指针扫描",
+            ),
+            session_cooperative_di: Offset::new("GameDI -> SessionCooperativeDI", 0x540)
+                .without_sig(
+                    "还没确认读它的指令；\
+                     联机和单人各抓一次快照，以 LevelDI 所在对象为目标从 GameDI 扫描",
+                ),
+            level_di: Offset::new("SessionCooperativeDI -> LevelDI", 0xB0).without_sig(
+                "还没确认读它的指令；LevelDI 的虚表在 engine 里，\
+                 从 SessionCooperativeDI 往后找第一个虚表落在 ILevel 导出函数所在段的指针",
+            ),
+            c_level: Offset::new("LevelDI -> CLevel", 0x8).without_sig(
+                "位移只有一个字节，[reg+8] 的指令到处都是，做不出唯一的特征码；\
+                 换地图前后各抓一次快照，CLevel 会跟着变",
+            ),
+            local_client_di: Offset::new("SessionCooperativeDI -> LocalClientDI", 0xB8)
+                .without_sig(
+                    "还没确认读它的指令，和 LevelDI 只差 8 字节，按位移区分不开；\
+                     以 PlayerDI 所在对象为目标从 SessionCooperativeDI 扫描",
+                ),
+            player_di: Offset::new("LocalClientDI -> PlayerDI", 0x50).without_sig(
+                "[reg+0x50] 是一个字节的位移，太常见；\
+                 以玩家坐标的地址减 PlayerPos 偏移为目标从 LocalClientDI 扫描",
+            ),
+            player_world_pos: Offset::new("PlayerDI + PlayerPos", 0x7B0).without_sig(
+                "还没在 gamedll 里确认只写玩家坐标的指令；\
+                 用 \"传送\" 页的坐标搜 float，命中的地址减 PlayerDI 就是偏移，传送一次确认会跟着变",
+            ),
+            // 整段按 aim_mouse_yaw_p 的特征码写死，只认得出当前版本
+            // 位移变了就匹配不到，保持未验证，不会把别处的 movss 当成新偏移
+            // 鼠标写视角的指令是 movss [rbx+0x1178],xmm0 / movss [rbx+0x1174],xmm6
+            // rbx 在 PlayerDI 前 0x58 字节
+            camera_angle: Offset::new("PlayerDI + Angle", 0x111C).with_sig(OffsetSig {
+                module: "gamedll_x64_rwdi.dll",
+                pattern: "F3 0F 11 83 78 11 00 00 F3 0F 11 B3 74 11 00 00",
+                disp_pos: 12,
+                disp_size: 4,
+                bias: -0x58,
+            }),
+            player_c_model_obj: Offset::new("PlayerDI -> PlayerCModelObject (负偏移)", 0x50)
+                .without_sig(
+                    "是从 PlayerDI 往前数的负偏移，还没确认用它的指令；\
+                     以 \"实体\" 页里自己那一项的 CModelObject 地址为目标扫描",
+                ),
+            camera_manager_di: Offset::new("SessionCooperativeDI -> CameraManagerDI", 0xC0)
+                .without_sig(
+                    "还没确认读它的指令；\
+                     以 CameraFPPDI 所在对象为目标从 SessionCooperativeDI 扫描",
+                ),
+            camera_fpp_di: Offset::new("CameraManagerDI -> CameraFPPDI", 0x50).without_sig(
+                "[reg+0x50] 是一个字节的位移，太常见；\
+                 导出页用 IBaseCamera 的 GetPosition 类方法能返回相机坐标的 this 就是它",
+            ),
+
+            model_obj_array_entry: Offset::new("ModelObject 数组项 - ModelObject", 0x18)
+                .without_sig(
+                    "是数组项里的位移，遍历数组的循环不止一处；\
+                     在 \"快照\" 页看数组项，指向 ModelObject 虚表的那个字段前面的长度就是偏移",
+                ),
+            c_model_obj: Offset::new("ModelObject -> CModelObject", 0x20).without_sig(
+                "[reg+0x20] 是一个字节的位移，太常见；\
+                 以实体的 CModelObject 地址 (坐标地址减 WorldPos) 为目标从 ModelObject 扫描",
+            ),
+            c_model_obj_logo: Offset::new("CModelObject + Logo", 0x340).without_sig(
+                "还没确认读它的指令，只在分类时用到；\
+                 对同一类实体抓两次快照，值相同且按类型不同的那个 u32 就是",
+            ),
+            c_model_obj_world_pos: Offset::new("CModelObject + WorldPos", 0x11C).without_sig(
+                "还没确认只写实体坐标的指令；\
+                 盯住一个走动的丧尸，在 CModelObject 里找跟着变的三个 float",
+            ),
+            health_module: Offset::new("ModelObject -> HealthModule", 0xCE8).without_sig(
+                "还没确认读它的指令；\
+                 以血量的地址减 Health 偏移为目标从 ModelObject 扫描",
+            ),
+            health: Offset::new("HealthModule + Health", 0x78).without_sig(
+                "还没在 gamedll 里确认扣血的指令，[reg+0x78] 一个字节的位移太常见；\
+                 受伤前后搜变小的 float，命中的地址减 HealthModule 就是偏移",
+            ),
+            model_type_data: Offset::new("CModelObject -> TypeData", 0x60).without_sig(
+                "[reg+0x60] 是一个字节的位移，太常见；\
+                 指向的对象里有实体的预设名字符串，在快照里按预设名找",
+            ),
+        }
+    }
+
+    pub(crate) fn all(&self) -> [&Offset; 18] {
+        [
+            &self.game_di,
+            &self.session_cooperative_di,
+            &self.level_di,
+            &self.c_level,
+            &self.local_client_di,
+            &self.player_di,
+            &self.player_world_pos,
+            &self.camera_angle,
+            &self.player_c_model_obj,
+            &self.camera_manager_di,
+            &self.camera_fpp_di,
+            &self.model_obj_array_entry,
+            &self.c_model_obj,
+            &self.c_model_obj_logo,
+            &self.c_model_obj_world_pos,
+            &self.health_module,
+            &self.health,
+            &self.model_type_data,
+        ]
+    }
+
+    fn all_mut(&mut self) -> [&mut Offset; 18] {
+        [
+            &mut self.game_di,
+            &mut self.session_cooperative_di,
+            &mut self.level_di,
+            &mut self.c_level,
+            &mut self.local_client_di,
+            &mut self.player_di,
+            &mut self.player_world_pos,
+            &mut self.camera_angle,
+            &mut self.player_c_model_obj,
+            &mut self.camera_manager_di,
+            &mut self.camera_fpp_di,
+            &mut self.model_obj_array_entry,
+            &mut self.c_model_obj,
+            &mut self.c_model_obj_logo,
+            &mut self.c_model_obj_world_pos,
+            &mut self.health_module,
+            &mut self.health,
+            &mut self.model_type_data,
+        ]
+    }
+
+    // find(module, pattern) 返回特征码在当前二进制里唯一的匹配地址，匹配到多处时返回 None
+    pub(crate) fn derive<M: Memory>(
+        &mut self,
+        mem: &M,
+        find: impl Fn(&str, &str) -> Option<usize>,
+    ) {
+        for offset in self.all_mut() {
+            offset.value = offset.profile;
+            offset.status = OffsetStatus::Unverified;
+            offset.derived = None;
+
+            let sig = match offset.sig {
+                Some(val) => val,
+                None => continue,
+            };
+
+            let addr = match find(sig.module, sig.pattern) {
+                Some(val) => val + sig.disp_pos,
+                None => continue,
+            };
+
+            let disp = match sig.disp_size {
+                1 => mem.read::<i8>(addr).map(|val| val as isize),
+                4 => mem.read::<i32>(addr).map(|val| val as isize),
+                _ => None,
+            };
+
+            let derived = match disp {
+                Some(val) => val + sig.bias,
+                None => continue,
+            };
+            offset.derived = Some(derived);
+
+            if derived < 0 || (derived as usize).abs_diff(offset.profile) > MAX_DRIFT {
+                continue;
+            }
+
+            offset.value = derived as usize;
+            offset.status = if offset.value == offset.profile {
+                OffsetStatus::Confirmed
+            } else {
+                OffsetStatus::Rederived
+            };
+        }
+    }
+
+    // 离线重放快照时使用抓取时游戏里生效的偏移
+    #[allow(dead_code)]
+    pub(crate) fn apply(&mut self, value_of: impl Fn(&str) -> Option<usize>) {
+        for offset in self.all_mut() {
+            let value = match value_of(offset.name) {
+                Some(val) => val,
+                None => continue,
+            };
+
+            offset.value = value;
+            offset.status = if value == offset.profile {
+                OffsetStatus::Confirmed
+            } else {
+                OffsetStatus::Rederived
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::Snapshot;
+
+    #[test]
+    fn every_offset_has_sig_or_reason() {
+        for offset in Offsets::new().all() {
+            assert!(
+                offset.sig.is_some() != offset.no_sig.is_some(),
+                "{} 要有特征码或者写明没有的原因",
+                offset.name
+            );
+        }
+    }
+
+    // 模块里放一条 movss [rbx+0x1178],xmm0 / movss [rbx+disp],xmm6
+    fn derive_with_disp(disp: i32) -> Offsets {
+        let mut code = vec![0xF3, 0x0F, 0x11, 0x83, 0x78, 0x11, 0x00, 0x00];
+        code.extend_from_slice(&[0xF3, 0x0F, 0x11, 0xB3]);
+        code.extend_from_slice(&disp.to_le_bytes());
+
+        let mut snapshot = Snapshot::default();
+        snapshot.insert(0x1000, &code);
+
+        let mut offsets = Offsets::new();
+        offsets.derive(&snapshot, |module, pattern| {
+            (module == "gamedll_x64_rwdi.dll" && matches(pattern, &code)).then_some(0x1000)
+        });
+        offsets
+    }
+
+    // 和 libmem::sig_scan 一样，?? 匹配任意字节
+    fn matches(pattern: &str, code: &[u8]) -> bool {
+        let bytes: Vec<&str> = pattern.split_whitespace().collect();
+
+        bytes.len() <= code.len()
+            && bytes.iter().zip(code).all(|(byte, val)| {
+                *byte == "??" || u8::from_str_radix(byte, 16).is_ok_and(|byte| byte == *val)
+            })
+    }
+
+    #[test]
+    fn derive_confirms_exact_sig() {
+        let offsets = derive_with_disp(0x1174);
+        assert_eq!(offsets.camera_angle.status, OffsetStatus::Confirmed);
+        assert_eq!(offsets.camera_angle.value, 0x111C);
+        assert_eq!(offsets.camera_angle.derived, Some(0x111C));
+
+        // 没有特征码的保持配置值
+        assert_eq!(offsets.health.status, OffsetStatus::Unverified);
+        assert_eq!(offsets.health.value, 0x78);
+    }
+
+    #[test]
+    fn derive_ignores_other_movss() {
+        // 位移变了或者是别处的 movss，都不当成新偏移
+        for disp in [0x1184, 0x4000] {
+            let offsets = derive_with_disp(disp);
+            assert_eq!(offsets.camera_angle.status, OffsetStatus::Unverified);
+            assert_eq!(offsets.camera_angle.value, 0x111C);
+            assert_eq!(offsets.camera_angle.derived, None);
+        }
+    }
+
+    #[test]
+    fn no_sig_reasons_are_not_shared() {
+        let offsets = Offsets::new();
+        let reasons: Vec<&str> = offsets.all().iter().filter_map(|val| val.no_sig).collect();
+
+        for (index, reason) in reasons.iter().enumerate() {
+            assert!(!reasons[index + 1..].contains(reason), "{}", reason);
+        }
+    }
+}
@@ -0,0 +1,729 @@
+use crate::{
+    draw::{Draw, DrawCmd, Layer},
+    math::Vec3,
+    script::ScriptFrame,
+};
+use std::{
+    ffi::{CStr, c_char, c_void},
+    path::PathBuf,
+};
+
+// HostApi / PluginApi 的布局有不兼容的改动时加一
+// 只在末尾加函数时不用改，插件可以看 HostApi.size 判断有没有新函数
+// 和 plugins/trainer_plugin.h 保持一致
+pub(crate) const PLUGIN_ABI_VERSION: u32 = 1;
+
+// 插件 DLL 要导出的两个函数
+pub(crate) const PLUGIN_ABI_VERSION_SYMBOL: &str = "trainer_plugin_abi_version\0";
+pub(crate) const PLUGIN_INIT_SYMBOL: &str = "trainer_plugin_init\0";
+
+const MAX_LOGS: usize = 20;
+
+pub(crate) type AbiVersionFn = unsafe extern "C" fn() -> u32;
+pub(crate) type InitFn = unsafe extern "C" fn(host: *const HostApi) -> *const PluginApi;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct PluginEntity {
+    pub(crate) addr: u64,
+    // world::ModelType 的值，见 trainer_plugin.h
+    pub(crate) model_type: u32,
+    pub(crate) pos: [f32; 3],
+    pub(crate) health: f32,
+    pub(crate) distance: f32,
+}
+
+// 宿主交给插件的函数表，第一个参数都是 HostApi.host
+// 字符串都是 UTF-8，以 0 结尾；颜色是 rgba，0.0 ~ 1.0
+#[repr(C)]
+pub(crate) struct HostApi {
+    pub(crate) abi_version: u32,
+    pub(crate) size: u32,
+    pub(crate) host: *mut c_void,
+
+    // 这一帧的实体快照
+    pub(crate) frame_time: unsafe extern "C" fn(host: *mut c_void) -> f64,
+    pub(crate) player_pos: unsafe extern "C" fn(host: *mut c_void, out: *mut [f32; 3]),
+    pub(crate) entity_count: unsafe extern "C" fn(host: *mut c_void) -> u32,
+    pub(crate) entity:
+        unsafe extern "C" fn(host: *mut c_void, index: u32, out: *mut PluginEntity) -> bool,
+    pub(crate) world_to_screen:
+        unsafe extern "C" fn(host: *mut c_void, pos: *const [f32; 3], out: *mut [f32; 2]) -> bool,
+
+    // 绘制，只在 on_frame 里有效
+    pub(crate) draw_text: unsafe extern "C" fn(
+        host: *mut c_void,
+        x: f32,
+        y: f32,
+        color: *const [f32; 4],
+        text: *const c_char,
+    ),
+    pub(crate) draw_line: unsafe extern "C" fn(
+        host: *mut c_void,
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        color: *const [f32; 4],
+        thickness: f32,
+    ),
+    pub(crate) draw_circle: unsafe extern "C" fn(
+        host: *mut c_void,
+        x: f32,
+        y: f32,
+        radius: f32,
+        color: *const [f32; 4],
+        thickness: f32,
+        filled: bool,
+    ),
+    pub(crate) draw_rect: unsafe extern "C" fn(
+        host: *mut c_void,
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        color: *const [f32; 4],
+        thickness: f32,
+        filled: bool,
+    ),
+
+    // 第一次调用时登记设置并返回默认值，之后返回菜单里改过的值
+    pub(crate) setting_bool:
+        unsafe extern "C" fn(host: *mut c_void, name: *const c_char, default: bool) -> bool,
+    pub(crate) setting_float:
+        unsafe extern "C" fn(host: *mut c_void, name: *const c_char, default: f32) -> f32,
+
+    pub(crate) log: unsafe extern "C" fn(host: *mut c_void, text: *const c_char),
+
+    // 菜单控件，只在 on_ui 里有效，其他时候什么都不做
+    pub(crate) ui_text: unsafe extern "C" fn(host: *mut c_void, text: *const c_char),
+    pub(crate) ui_checkbox:
+        unsafe extern "C" fn(host: *mut c_void, label: *const c_char, value: *mut bool) -> bool,
+    pub(crate) ui_button: unsafe extern "C" fn(host: *mut c_void, label: *const c_char) -> bool,
+    pub(crate) ui_slider_float: unsafe extern "C" fn(
+        host: *mut c_void,
+        label: *const c_char,
+        min: f32,
+        max: f32,
+        value: *mut f32,
+    ) -> bool,
+}
+
+// 插件 init 返回的函数表，插件卸载前要一直有效
+#[repr(C)]
+pub(crate) struct PluginApi {
+    pub(crate) abi_version: u32,
+    pub(crate) size: u32,
+    pub(crate) name: *const c_char,
+    // 返回非 0 表示出错，插件会被关掉
+    pub(crate) on_frame: Option<unsafe extern "C" fn(host: *const HostApi) -> i32>,
+    pub(crate) on_ui: Option<unsafe extern "C" fn(host: *const HostApi)>,
+    pub(crate) shutdown: Option<unsafe extern "C" fn()>,
+}
+
+// on_ui 里的控件，游戏里是 imgui，离线时可以自己实现
+pub(crate) trait PluginUi {
+    fn text(&mut self, text: &str);
+    fn checkbox(&mut self, label: &str, value: &mut bool) -> bool;
+    fn button(&mut self, label: &str) -> bool;
+    fn slider_float(&mut self, label: &str, min: f32, max: f32, value: &mut f32) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PluginSetting {
+    Bool(bool),
+    Float(f32),
+}
+
+pub(crate) struct Plugin {
+    pub(crate) name: String,
+    pub(crate) path: PathBuf,
+    pub(crate) enabled: bool,
+    pub(crate) error: Option<String>,
+    pub(crate) settings: Vec<(String, PluginSetting)>,
+    pub(crate) logs: Vec<String>,
+    // LoadLibrary 返回的句柄，卸载时交回去 FreeLibrary
+    pub(crate) library: usize,
+    api: *const PluginApi,
+}
+
+impl Plugin {
+    pub(crate) fn has_ui(&self) -> bool {
+        unsafe { (*self.api).on_ui.is_some() }
+    }
+}
+
+// 调用插件期间 HostApi.host 指向这个
+#[derive(Default)]
+struct Context {
+    frame: ScriptFrame,
+    entities: Vec<PluginEntity>,
+    cmds: Vec<DrawCmd>,
+    logs: Vec<String>,
+    settings: Vec<(String, PluginSetting)>,
+    ui: Option<*mut dyn PluginUi>,
+}
+
+// 不能 Clone: 插件拿着 context 和 api 的地址，每个 Plugin 的 DLL 句柄也只能 FreeLibrary 一次
+pub(crate) struct PluginHost {
+    // 插件拿着这两个的地址，所以放在 Box 里不让它们移动
+    context: Box<Context>,
+    api: Box<HostApi>,
+    pub(crate) plugins: Vec<Plugin>,
+    // 加载失败的文件和原因
+    pub(crate) failures: Vec<(String, String)>,
+}
+
+unsafe fn context<'a>(host: *mut c_void) -> &'a mut Context {
+    unsafe { &mut *(host as *mut Context) }
+}
+
+unsafe fn string(text: *const c_char) -> String {
+    if text.is_null() {
+        return String::new();
+    }
+
+    unsafe { CStr::from_ptr(text) }
+        .to_string_lossy()
+        .to_string()
+}
+
+unsafe fn color(color: *const [f32; 4]) -> [f32; 4] {
+    if color.is_null() {
+        return [1.0, 1.0, 1.0, 1.0];
+    }
+
+    unsafe { *color }
+}
+
+unsafe extern "C" fn host_frame_time(host: *mut c_void) -> f64 {
+    unsafe { context(host) }.frame.time
+}
+
+unsafe extern "C" fn host_player_pos(host: *mut c_void, out: *mut [f32; 3]) {
+    if out.is_null() {
+        return;
+    }
+
+    let pos = unsafe { context(host) }.frame.player_pos;
+    unsafe { *out = [pos.x, pos.y, pos.z] };
+}
+
+unsafe extern "C" fn host_entity_count(host: *mut c_void) -> u32 {
+    unsafe { context(host) }.entities.len() as u32
+}
+
+unsafe extern "C" fn host_entity(host: *mut c_void, index: u32, out: *mut PluginEntity) -> bool {
+    if out.is_null() {
+        return false;
+    }
+
+    match unsafe { context(host) }.entities.get(index as usize) {
+        Some(val) => {
+            unsafe { *out = *val };
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe extern "C" fn host_world_to_screen(
+    host: *mut c_void,
+    pos: *const [f32; 3],
+    out: *mut [f32; 2],
+) -> bool {
+    if pos.is_null() || out.is_null() {
+        return false;
+    }
+
+    let [x, y, z] = unsafe { *pos };
+    let camera = unsafe { context(host) }.frame.camera;
+
+    match camera.world_to_screen(Vec3::new(x, y, z)).screen() {
+        Some(val) => {
+            unsafe { *out = val.to_array() };
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe extern "C" fn host_draw_text(
+    host: *mut c_void,
+    x: f32,
+    y: f32,
+    text_color: *const [f32; 4],
+    text: *const c_char,
+) {
+    let cmd = DrawCmd::Text {
+        layer: Layer::Background,
+        pos: [x, y],
+        color: unsafe { color(text_color) },
+        text: unsafe { string(text) },
+    };
+
+    unsafe { context(host) }.cmds.push(cmd);
+}
+
+unsafe extern "C" fn host_draw_line(
+    host: *mut c_void,
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+    line_color: *const [f32; 4],
+    thickness: f32,
+) {
+    let cmd = DrawCmd::Line {
+        layer: Layer::Background,
+        from: [x1, y1],
+        to: [x2, y2],
+        color: unsafe { color(line_color) },
+        thickness,
+    };
+
+    unsafe { context(host) }.cmds.push(cmd);
+}
+
+unsafe extern "C" fn host_draw_circle(
+    host: *mut c_void,
+    x: f32,
+    y: f32,
+    radius: f32,
+    circle_color: *const [f32; 4],
+    thickness: f32,
+    filled: bool,
+) {
+    let cmd = DrawCmd::Circle {
+        layer: Layer::Background,
+        center: [x, y],
+        radius,
+        color: unsafe { color(circle_color) },
+        thickness,
+        filled,
+    };
+
+    unsafe { context(host) }.cmds.push(cmd);
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn host_draw_rect(
+    host: *mut c_void,
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+    rect_color: *const [f32; 4],
+    thickness: f32,
+    filled: bool,
+) {
+    let cmd = DrawCmd::Rect {
+        layer: Layer::Background,
+        min: [x1.min(x2), y1.min(y2)],
+        max: [x1.max(x2), y1.max(y2)],
+        color: unsafe { color(rect_color) },
+        thickness,
+        filled,
+    };
+
+    unsafe { context(host) }.cmds.push(cmd);
+}
+
+// 同名但类型不同时返回默认值，不覆盖已有的
+fn setting(context: &mut Context, name: String, default: PluginSetting) -> PluginSetting {
+    if let Some((_, val)) = context.settings.iter().find(|(key, _)| *key == name) {
+        return *val;
+    }
+
+    context.settings.push((name, default));
+    default
+}
+
+unsafe extern "C" fn host_setting_bool(
+    host: *mut c_void,
+    name: *const c_char,
+    default: bool,
+) -> bool {
+    let name = unsafe { string(name) };
+
+    match setting(unsafe { context(host) }, name, PluginSetting::Bool(default)) {
+        PluginSetting::Bool(val) => val,
+        _ => default,
+    }
+}
+
+unsafe extern "C" fn host_setting_float(
+    host: *mut c_void,
+    name: *const c_char,
+    default: f32,
+) -> f32 {
+    let name = unsafe { string(name) };
+
+    match setting(
+        unsafe { context(host) },
+        name,
+        PluginSetting::Float(default),
+    ) {
+        PluginSetting::Float(val) => val,
+        _ => default,
+    }
+}
+
+unsafe extern "C" fn host_log(host: *mut c_void, text: *const c_char) {
+    let text = unsafe { string(text) };
+    unsafe { context(host) }.logs.push(text);
+}
+
+unsafe fn ui<'a>(host: *mut c_void) -> Option<&'a mut dyn PluginUi> {
+    unsafe { context(host) }.ui.map(|val| unsafe { &mut *val })
+}
+
+unsafe extern "C" fn host_ui_text(host: *mut c_void, text: *const c_char) {
+    if let Some(ui) = unsafe { ui(host) } {
+        ui.text(&unsafe { string(text) });
+    }
+}
+
+unsafe extern "C" fn host_ui_checkbox(
+    host: *mut c_void,
+    label: *const c_char,
+    value: *mut bool,
+) -> bool {
+    if value.is_null() {
+        return false;
+    }
+
+    match unsafe { ui(host) } {
+        Some(ui) => ui.checkbox(&unsafe { string(label) }, unsafe { &mut *value }),
+        None => false,
+    }
+}
+
+unsafe extern "C" fn host_ui_button(host: *mut c_void, label: *const c_char) -> bool {
+    match unsafe { ui(host) } {
+        Some(ui) => ui.button(&unsafe { string(label) }),
+        None => false,
+    }
+}
+
+unsafe extern "C" fn host_ui_slider_float(
+    host: *mut c_void,
+    label: *const c_char,
+    min: f32,
+    max: f32,
+    value: *mut f32,
+) -> bool {
+    if value.is_null() {
+        return false;
+    }
+
+    match unsafe { ui(host) } {
+        Some(ui) => ui.slider_float(&unsafe { string(label) }, min, max, unsafe { &mut *value }),
+        None => false,
+    }
+}
+
+impl PluginHost {
+    pub(crate) fn new() -> Self {
+        let mut context = Box::new(Context::default());
+
+        let api = Box::new(HostApi {
+            abi_version: PLUGIN_ABI_VERSION,
+            size: std::mem::size_of::<HostApi>() as u32,
+            host: context.as_mut() as *mut Context as *mut c_void,
+            frame_time: host_frame_time,
+            player_pos: host_player_pos,
+            entity_count: host_entity_count,
+            entity: host_entity,
+            world_to_screen: host_world_to_screen,
+            draw_text: host_draw_text,
+            draw_line: host_draw_line,
+            draw_circle: host_draw_circle,
+            draw_rect: host_draw_rect,
+            setting_bool: host_setting_bool,
+            setting_float: host_setting_float,
+            log: host_log,
+            ui_text: host_ui_text,
+            ui_checkbox: host_ui_checkbox,
+            ui_button: host_ui_button,
+            ui_slider_float: host_ui_slider_float,
+        });
+
+        Self {
+            context,
+            api,
+            plugins: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    // 检查版本并调用 init，失败时记在 failures 里并返回 false，
+    // 这时 library 还是调用者的，要自己 FreeLibrary
+    pub(crate) unsafe fn add(
+        &mut self,
+        path: PathBuf,
+        library: usize,
+        abi_version: Option<AbiVersionFn>,
+        init: Option<InitFn>,
+    ) -> bool {
+        let file = path
+            .file_name()
+            .map(|val| val.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match unsafe { self.init(abi_version, init) } {
+            Ok(api) => {
+                let name = match unsafe { string((*api).name) } {
+                    val if val.is_empty() => file,
+                    val => val,
+                };
+
+                // init 里登记的设置和 log 也算这个插件的
+                let mut plugin = Plugin {
+                    name,
+                    path,
+                    enabled: true,
+                    error: None,
+                    settings: std::mem::take(&mut self.context.settings),
+                    logs: Vec::new(),
+                    library,
+                    api,
+                };
+                push_logs(&mut plugin, &mut self.context.logs);
+
+                self.plugins.push(plugin);
+                true
+            }
+            Err(err) => {
+                self.context.settings.clear();
+                self.context.logs.clear();
+                self.failures.push((file, err));
+                false
+            }
+        }
+    }
+
+    unsafe fn init(
+        &mut self,
+        abi_version: Option<AbiVersionFn>,
+        init: Option<InitFn>,
+    ) -> Result<*const PluginApi, String> {
+        let abi_version = abi_version.ok_or("没有导出 trainer_plugin_abi_version")?;
+        let init = init.ok_or("没有导出 trainer_plugin_init")?;
+
+        // 版本不对时不调用 init，函数表的布局可能对不上
+        let version = unsafe { abi_version() };
+        if version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "ABI 版本不匹配: 插件 {}，宿主 {}",
+                version, PLUGIN_ABI_VERSION
+            ));
+        }
+
+        self.context.settings.clear();
+        self.context.logs.clear();
+
+        let api = unsafe { init(self.api.as_ref()) };
+        if api.is_null() {
+            return Err("trainer_plugin_init 返回空指针".to_string());
+        }
+
+        let (version, size) = unsafe { ((*api).abi_version, (*api).size) };
+        if version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "PluginApi 版本不匹配: 插件 {}，宿主 {}",
+                version, PLUGIN_ABI_VERSION
+            ));
+        }
+
+        if (size as usize) < std::mem::size_of::<PluginApi>() {
+            return Err(format!("PluginApi 大小不对: {}", size));
+        }
+
+        Ok(api)
+    }
+
+    // 调用每个插件的 shutdown，返回要 FreeLibrary 的句柄
+    pub(crate) fn unload_all(&mut self) -> Vec<usize> {
+        self.failures.clear();
+
+        self.plugins
+            .drain(..)
+            .map(|plugin| {
+                if let Some(shutdown) = unsafe { (*plugin.api).shutdown } {
+                    unsafe { shutdown() };
+                }
+
+                plugin.library
+            })
+            .collect()
+    }
+
+    // 插件出错时只关掉它自己
+    pub(crate) fn run_frame<D: Draw>(&mut self, frame: &ScriptFrame, draw: &mut D) {
+        self.context.frame = frame.clone();
+        self.context.entities = frame
+            .entities
+            .iter()
+            .map(|entity| PluginEntity {
+                addr: entity.addr as u64,
+                model_type: entity.model_type as u32,
+                pos: [entity.pos.x, entity.pos.y, entity.pos.z],
+                health: entity.health,
+                distance: entity.distance,
+            })
+            .collect();
+
+        for plugin in &mut self.plugins {
+            if !plugin.enabled {
+                continue;
+            }
+
+            let Some(on_frame) = (unsafe { (*plugin.api).on_frame }) else {
+                continue;
+            };
+
+            self.context.cmds.clear();
+            self.context.logs.clear();
+            self.context.settings = std::mem::take(&mut plugin.settings);
+
+            let code = unsafe { on_frame(self.api.as_ref()) };
+
+            plugin.settings = std::mem::take(&mut self.context.settings);
+            push_logs(plugin, &mut self.context.logs);
+
+            if code != 0 {
+                plugin.error = Some(format!("on_frame 返回 {}", code));
+                plugin.enabled = false;
+                continue;
+            }
+
+            for cmd in self.context.cmds.drain(..) {
+                draw.submit(cmd);
+            }
+        }
+    }
+
+    // 在插件自己的菜单页里调用
+    pub(crate) fn run_ui(&mut self, index: usize, ui: &mut dyn PluginUi) {
+        let Some(plugin) = self.plugins.get_mut(index) else {
+            return;
+        };
+
+        let Some(on_ui) = (unsafe { (*plugin.api).on_ui }) else {
+            return;
+        };
+
+        // 只在这次调用里用，调用完马上清掉
+        let ui: *mut (dyn PluginUi + '_) = ui;
+        self.context.ui =
+            Some(unsafe { std::mem::transmute::<*mut (dyn PluginUi + '_), *mut dyn PluginUi>(ui) });
+        self.context.logs.clear();
+        self.context.settings = std::mem::take(&mut plugin.settings);
+
+        unsafe { on_ui(self.api.as_ref()) };
+
+        self.context.ui = None;
+        plugin.settings = std::mem::take(&mut self.context.settings);
+        push_logs(plugin, &mut self.context.logs);
+    }
+}
+
+fn push_logs(plugin: &mut Plugin, logs: &mut Vec<String>) {
+    plugin.logs.append(logs);
+
+    if plugin.logs.len() > MAX_LOGS {
+        let excess = plugin.logs.len() - MAX_LOGS;
+        plugin.logs.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{draw::Recorder, script::ScriptEntity, world::ModelType};
+
+    unsafe extern "C" fn abi_version() -> u32 {
+        PLUGIN_ABI_VERSION
+    }
+
+    unsafe fn show(host: *const HostApi) -> bool {
+        let host = unsafe { &*host };
+        unsafe { (host.setting_bool)(host.host, c"show".as_ptr(), true) }
+    }
+
+    unsafe extern "C" fn on_frame(host: *const HostApi) -> i32 {
+        if unsafe { show(host) } {
+            let api = unsafe { &*host };
+            let color = [1.0; 4];
+            unsafe { (api.draw_text)(api.host, 1.0, 2.0, &color, c"hi".as_ptr()) };
+        }
+        0
+    }
+
+    // init 里就登记设置
+    unsafe extern "C" fn init(host: *const HostApi) -> *const PluginApi {
+        unsafe { show(host) };
+
+        Box::leak(Box::new(PluginApi {
+            abi_version: PLUGIN_ABI_VERSION,
+            size: std::mem::size_of::<PluginApi>() as u32,
+            name: c"test".as_ptr(),
+            on_frame: Some(on_frame),
+            on_ui: None,
+            shutdown: None,
+        }))
+    }
+
+    #[test]
+    fn settings_registered_in_init_belong_to_plugin() {
+        let mut host = PluginHost::new();
+        assert!(unsafe { host.add(PathBuf::from("test.dll"), 0, Some(abi_version), Some(init)) });
+
+        let show = ("show".to_string(), PluginSetting::Bool(true));
+        assert_eq!(host.plugins[0].settings, vec![show.clone()]);
+
+        let mut draw = Recorder::default();
+        host.run_frame(&ScriptFrame::default(), &mut draw);
+        assert_eq!(host.plugins[0].settings, vec![show]);
+        assert_eq!(draw.take().len(), 1);
+
+        // 菜单里关掉
+        host.plugins[0].settings[0].1 = PluginSetting::Bool(false);
+        host.run_frame(&ScriptFrame::default(), &mut draw);
+        assert!(draw.cmds.is_empty());
+    }
+
+    #[test]
+    fn entity_model_type_matches_header() {
+        let types = [
+            (ModelType::ZombieNormal, 0),
+            (ModelType::ZombieSpecial, 1),
+            (ModelType::ZombieHunter, 2),
+            (ModelType::SurvivorNormal, 3),
+            (ModelType::SurvivorSpecial, 4),
+            (ModelType::SurvivorShopkeeper, 5),
+            (ModelType::PlayerHuman, 6),
+            (ModelType::PlayerHunter, 7),
+            (ModelType::Other, 8),
+        ];
+
+        let frame = ScriptFrame {
+            entities: types
+                .iter()
+                .map(|&(model_type, _)| ScriptEntity {
+                    model_type,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut host = PluginHost::new();
+        host.run_frame(&frame, &mut Recorder::default());
+
+        for (entity, (_, value)) in host.context.entities.iter().zip(types) {
+            assert_eq!(entity.model_type, value);
+        }
+    }
+}
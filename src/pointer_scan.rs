@@ -0,0 +1,315 @@
+use crate::snapshot::{Memory, Module, Snapshot};
+use std::{collections::HashSet, fmt::Write};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScanConfig {
+    pub(crate) max_depth: usize,
+    pub(crate) max_offset: usize,
+    pub(crate) max_results: usize,
+    // 每层最多保留的中间节点，防止堆上的指针网把内存吃光
+    pub(crate) max_nodes: usize,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 5,
+            max_offset: 0x1000,
+            max_results: 1000,
+            max_nodes: 200_000,
+        }
+    }
+}
+
+// module+base_offset 读出指针，逐级加上 offsets，最后一级的结果就是目标地址
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PointerPath {
+    pub(crate) module: String,
+    pub(crate) base_offset: usize,
+    pub(crate) offsets: Vec<usize>,
+}
+
+impl PointerPath {
+    pub(crate) fn resolve<M: Memory>(&self, mem: &M, modules: &[Module]) -> Option<usize> {
+        let module = modules
+            .iter()
+            .find(|module| module.name.eq_ignore_ascii_case(&self.module))?;
+
+        let mut addr = module.base + self.base_offset;
+        for offset in &self.offsets {
+            addr = mem.read_ptr(addr)?.checked_add(*offset)?;
+        }
+
+        Some(addr)
+    }
+
+    // "gamedll_x64_rwdi.dll+0x1234 0x98 0x540"
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+
+        let (module, base_offset) = parts.next()?.rsplit_once('+')?;
+
+        let mut offsets = Vec::new();
+        for part in parts {
+            offsets.push(parse_hex(part)?);
+        }
+
+        if offsets.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            module: module.to_string(),
+            base_offset: parse_hex(base_offset)?,
+            offsets,
+        })
+    }
+}
+
+impl std::fmt::Display for PointerPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}+{:#X}", self.module, self.base_offset)?;
+
+        for offset in &self.offsets {
+            write!(f, " {:#X}", offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_hex(text: &str) -> Option<usize> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+
+    usize::from_str_radix(text, 16).ok()
+}
+
+// 快照里所有 8 字节对齐、且指向快照内部的值，按指向的地址排序
+fn build_pointer_index(snapshot: &Snapshot) -> Vec<(usize, usize)> {
+    let mut index = Vec::new();
+
+    for region in snapshot.regions() {
+        let align = (8 - region.base % 8) % 8;
+
+        for (chunk_index, chunk) in region.bytes[align.min(region.bytes.len())..]
+            .chunks_exact(8)
+            .enumerate()
+        {
+            let val = u64::from_le_bytes(chunk.try_into().unwrap()) as usize;
+
+            if val == 0 || !snapshot.contains(val) {
+                continue;
+            }
+
+            index.push((val, region.base + align + chunk_index * 8));
+        }
+    }
+
+    index.sort_unstable();
+    index
+}
+
+// 从目标地址反向搜索，直到找到位于模块静态区内的指针
+pub(crate) fn scan(snapshot: &Snapshot, target: usize, config: &ScanConfig) -> Vec<PointerPath> {
+    let index = build_pointer_index(snapshot);
+
+    let mut results = Vec::new();
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut frontier: Vec<(usize, Vec<usize>)> = vec![(target, Vec::new())];
+
+    'scan: for depth in 0..config.max_depth {
+        let mut next = Vec::new();
+
+        for (addr, offsets) in &frontier {
+            let low =
+                index.partition_point(|(val, _)| *val < addr.saturating_sub(config.max_offset));
+            let high = index.partition_point(|(val, _)| *val <= *addr);
+
+            for (val, ptr_addr) in &index[low..high] {
+                let mut path_offsets = Vec::with_capacity(offsets.len() + 1);
+                path_offsets.push(addr - val);
+                path_offsets.extend_from_slice(offsets);
+
+                if let Some(module) = snapshot.module_at(*ptr_addr) {
+                    results.push(PointerPath {
+                        module: module.name.clone(),
+                        base_offset: ptr_addr - module.base,
+                        offsets: path_offsets,
+                    });
+
+                    if results.len() >= config.max_results {
+                        break 'scan;
+                    }
+
+                    continue;
+                }
+
+                if depth + 1 < config.max_depth
+                    && next.len() < config.max_nodes
+                    && visited.insert(*ptr_addr)
+                {
+                    next.push((*ptr_addr, path_offsets));
+                }
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+
+        frontier = next;
+    }
+
+    results.sort_by_key(|path| path.offsets.len());
+    results
+}
+
+// 游戏重启后，只保留仍然能解析到新目标地址的路径
+pub(crate) fn filter<M: Memory>(
+    paths: &mut Vec<PointerPath>,
+    mem: &M,
+    modules: &[Module],
+    target: usize,
+) {
+    paths.retain(|path| path.resolve(mem, modules) == Some(target));
+}
+
+pub(crate) fn save(paths: &[PointerPath]) -> String {
+    let mut text = String::new();
+
+    for path in paths {
+        writeln!(text, "{}", path).unwrap();
+    }
+
+    text
+}
+
+pub(crate) fn load(text: &str) -> Vec<PointerPath> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(PointerPath::parse)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE_BASE: usize = 0x1000_0000;
+
+    fn put_ptr(snapshot: &mut Snapshot, addr: usize, val: usize) {
+        snapshot.insert(addr, &(val as u64).to_le_bytes());
+    }
+
+    // game.dll+0x100 -> a, a+0x20 -> b，目标是 b+0x40
+    fn chain(a: usize, b: usize) -> Snapshot {
+        let mut snapshot = Snapshot::new(vec![Module {
+            name: "game.dll".to_string(),
+            base: MODULE_BASE,
+            size: 0x1000,
+        }]);
+
+        snapshot.insert(MODULE_BASE, &[0u8; 0x1000]);
+        snapshot.insert(a, &[0u8; 0x100]);
+        snapshot.insert(b, &[0u8; 0x100]);
+
+        put_ptr(&mut snapshot, MODULE_BASE + 0x100, a);
+        put_ptr(&mut snapshot, a + 0x20, b);
+
+        snapshot
+    }
+
+    fn expected() -> PointerPath {
+        PointerPath {
+            module: "game.dll".to_string(),
+            base_offset: 0x100,
+            offsets: vec![0x20, 0x40],
+        }
+    }
+
+    #[test]
+    fn scan_finds_chain() {
+        let snapshot = chain(0x2000_0000, 0x3000_0000);
+
+        let paths = scan(&snapshot, 0x3000_0040, &ScanConfig::default());
+
+        assert_eq!(paths.first(), Some(&expected()));
+        assert_eq!(
+            expected().resolve(&snapshot, &snapshot.modules),
+            Some(0x3000_0040)
+        );
+    }
+
+    #[test]
+    fn scan_respects_max_offset_and_depth() {
+        let snapshot = chain(0x2000_0000, 0x3000_0000);
+
+        let config = ScanConfig {
+            max_offset: 0x30,
+            ..Default::default()
+        };
+        assert!(scan(&snapshot, 0x3000_0040, &config).is_empty());
+
+        let config = ScanConfig {
+            max_depth: 1,
+            ..Default::default()
+        };
+        assert!(scan(&snapshot, 0x3000_0040, &config).is_empty());
+    }
+
+    #[test]
+    fn scan_prefers_shorter_paths() {
+        let mut snapshot = chain(0x2000_0000, 0x3000_0000);
+        put_ptr(&mut snapshot, MODULE_BASE + 0x200, 0x3000_0000);
+
+        let paths = scan(&snapshot, 0x3000_0040, &ScanConfig::default());
+
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].base_offset, 0x200);
+        assert_eq!(paths[0].offsets, vec![0x40]);
+        assert_eq!(paths[1], expected());
+    }
+
+    #[test]
+    fn filter_keeps_paths_that_survive_restart() {
+        let first = chain(0x2000_0000, 0x3000_0000);
+        let mut paths = scan(&first, 0x3000_0040, &ScanConfig::default());
+        paths.push(PointerPath {
+            module: "game.dll".to_string(),
+            base_offset: 0x100,
+            offsets: vec![0x28, 0x40],
+        });
+
+        // 重启后堆地址变了
+        let second = chain(0x4000_0000, 0x5000_0000);
+        filter(&mut paths, &second, &second.modules, 0x5000_0040);
+
+        assert_eq!(paths, vec![expected()]);
+    }
+
+    #[test]
+    fn save_load_round_trip() {
+        let paths = vec![
+            expected(),
+            PointerPath {
+                module: "engine_x64_rwdi.dll".to_string(),
+                base_offset: 0xABC0,
+                offsets: vec![0x8, 0x0, 0x1178],
+            },
+        ];
+
+        let text = save(&paths);
+        assert_eq!(
+            text,
+            "game.dll+0x100 0x20 0x40\nengine_x64_rwdi.dll+0xABC0 0x8 0x0 0x1178\n"
+        );
+
+        let text = format!("# 注释\n\n{}不是路径\n", text);
+        assert_eq!(load(&text), paths);
+    }
+}
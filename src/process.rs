@@ -0,0 +1,273 @@
+use crate::{
+    OFFSETS, Ptr,
+    snapshot::{Memory, Module, Snapshot},
+    world::{self, Array, ModelObject, World},
+};
+use hudhook::windows::Win32::System::{
+    Diagnostics::Debug::ReadProcessMemory,
+    Memory::{
+        MEM_COMMIT, MEM_IMAGE, MEM_PRIVATE, MEMORY_BASIC_INFORMATION, PAGE_GUARD, VirtualQuery,
+    },
+    Threading::GetCurrentProcess,
+};
+use std::collections::HashSet;
+
+// PAGE_READONLY | PAGE_READWRITE | PAGE_WRITECOPY | PAGE_EXECUTE_READ
+// | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY
+const PAGE_READABLE: u32 = 0x02 | 0x04 | 0x08 | 0x20 | 0x40 | 0x80;
+
+// PlayerDI 的字段最远到 0x1178，ModelObject 向前还有 0x18/0x50 的负偏移
+const OBJECT_WINDOW_BEFORE: usize = 0x100;
+const OBJECT_WINDOW_AFTER: usize = 0x1400;
+const POINTEE_WINDOW: usize = 0x200;
+
+// 当前进程的内存，读之前先用 IsBadReadPtr 检查
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct LiveMemory;
+
+impl Memory for LiveMemory {
+    fn read_bytes(&self, addr: usize, buf: &mut [u8]) -> bool {
+        let src = addr as *const u8;
+
+        unsafe {
+            if src.is_null() || src.is_bad_read_ptr(buf.len()) {
+                return false;
+            }
+
+            std::ptr::copy_nonoverlapping(src, buf.as_mut_ptr(), buf.len());
+        }
+
+        true
+    }
+}
+
+pub(crate) fn modules() -> Vec<Module> {
+    libmem::enum_modules()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|module| Module {
+            name: module.name,
+            base: module.base,
+            size: module.size,
+        })
+        .collect()
+}
+
+// 抓取所有已提交、可读的私有内存和模块映像，超过 max_bytes 的部分丢弃
+// 先列出区域并一次分配好缓冲区，复制过程中不再分配内存，快照自己的数据不会混进来
+// 游戏在抓取时还在跑，区域可能随时被释放，所以用 ReadProcessMemory 按页复制，读失败的页跳过
+pub(crate) unsafe fn capture(max_bytes: usize) -> Snapshot {
+    let mut regions = Vec::new();
+
+    let mut addr = 0usize;
+    let mut total = 0usize;
+    let mut info = MEMORY_BASIC_INFORMATION::default();
+
+    while VirtualQuery(
+        Some(addr as *const core::ffi::c_void),
+        &mut info,
+        size_of::<MEMORY_BASIC_INFORMATION>(),
+    ) != 0
+    {
+        let base = info.BaseAddress as usize;
+        let size = info.RegionSize;
+
+        if info.State == MEM_COMMIT
+            && (info.Type == MEM_PRIVATE || info.Type == MEM_IMAGE)
+            && info.Protect.0 & PAGE_READABLE != 0
+            && info.Protect.0 & PAGE_GUARD.0 == 0
+            && total + size <= max_bytes
+        {
+            regions.push((base, size));
+            total += size;
+        }
+
+        addr = match base.checked_add(size) {
+            Some(val) if size != 0 => val,
+            _ => break,
+        };
+    }
+
+    let mut buf = vec![0u8; total];
+    let buf_start = buf.as_ptr() as usize;
+    let buf_end = buf_start + total;
+
+    // 复制成功的连续页: (地址, 缓冲区里的位置, 长度)
+    // 最坏情况下每页一段，按这个预留，复制中途不会扩容
+    let mut runs: Vec<(usize, usize, usize)> = Vec::with_capacity(total / 0x1000 + regions.len());
+    let process = GetCurrentProcess();
+    let mut offset = 0usize;
+
+    for (base, size) in regions {
+        let mut page = base;
+        let end = base + size;
+
+        while page < end {
+            let len = (0x1000 - page % 0x1000).min(end - page);
+
+            // 缓冲区本身也可能落在列出来的区域里
+            let overlaps_buf = page < buf_end && page + len > buf_start;
+
+            let mut read = 0usize;
+            let ok = !overlaps_buf
+                && ReadProcessMemory(
+                    process,
+                    page as *const core::ffi::c_void,
+                    buf[offset..].as_mut_ptr().cast(),
+                    len,
+                    Some(&mut read),
+                )
+                .is_ok()
+                && read == len;
+
+            if ok {
+                match runs.last_mut() {
+                    Some((run_addr, run_offset, run_len))
+                        if *run_addr + *run_len == page && *run_offset + *run_len == offset =>
+                    {
+                        *run_len += len;
+                    }
+                    _ => runs.push((page, offset, len)),
+                }
+            }
+
+            page += len;
+            offset += len;
+        }
+    }
+
+    let mut snapshot = Snapshot::new(modules());
+    for (addr, offset, len) in runs {
+        snapshot.insert(addr, &buf[offset..offset + len]);
+    }
+
+    snapshot
+}
+
+fn capture_range(snapshot: &mut Snapshot, start: usize, len: usize) -> usize {
+    let mut captured = 0;
+    let mut addr = start;
+    let end = start.saturating_add(len);
+
+    // 按页读取，跳过不可读的页
+    while addr < end {
+        let chunk_len = (0x1000 - addr % 0x1000).min(end - addr);
+
+        let mut buf = vec![0u8; chunk_len];
+        if LiveMemory.read_bytes(addr, &mut buf) {
+            snapshot.insert(addr, &buf);
+            captured += chunk_len;
+        }
+
+        addr += chunk_len;
+    }
+
+    captured
+}
+
+// 抓取 World 链、ModelObject 数组以及解析实体需要的对象，再沿对象里的指针向外抓 depth 层
+pub(crate) unsafe fn capture_world(
+    world: &World,
+    array_p: *const Array<*const ModelObject>,
+    c_game_pp: usize,
+    depth: usize,
+    max_bytes: usize,
+) -> Snapshot {
+    let mut snapshot = Snapshot::new(modules());
+
+    snapshot.roots.push(("c_game_pp".to_string(), c_game_pp));
+    snapshot
+        .roots
+        .push(("model_obj_array".to_string(), array_p as usize));
+    for offset in OFFSETS.all() {
+        snapshot
+            .roots
+            .push((format!("offset:{}", offset.name), offset.value));
+    }
+
+    let mut total = capture_range(&mut snapshot, c_game_pp, 8);
+    total += capture_range(
+        &mut snapshot,
+        array_p as usize,
+        size_of::<Array<*const ModelObject>>(),
+    );
+
+    let array = &*array_p;
+    total += capture_range(&mut snapshot, array.ptr as usize, array.len as usize * 8);
+
+    let mut objects = vec![
+        world.game_p as usize,
+        world.game_di_p as usize,
+        world.session_cooperative_di_p as usize,
+        world.level_di_p as usize,
+        world.c_level_p as usize,
+        world.local_client_di_p as usize,
+        world.player_di_p as usize,
+        world.player_c_model_obj_p as usize,
+        world.camera_manage_di_p as usize,
+        world.camera_fpp_di_p as usize,
+    ];
+
+    for model_obj_p in world::model_obj_addrs(&LiveMemory, &OFFSETS, array) {
+        objects.push(model_obj_p);
+
+        if let Some(c_model_obj_p) = LiveMemory.read_ptr(model_obj_p + OFFSETS.c_model_obj.value) {
+            objects.push(c_model_obj_p);
+
+            if let Some(str_p) = LiveMemory.read_ptr(c_model_obj_p + OFFSETS.model_type_data.value)
+            {
+                objects.push(str_p);
+            }
+        }
+
+        if let Some(health_module_p) =
+            LiveMemory.read_ptr(model_obj_p + OFFSETS.health_module.value)
+        {
+            objects.push(health_module_p);
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut level: Vec<usize> = objects;
+
+    for current_depth in 0..=depth {
+        // 链上的对象抓得大一些，后面各层只抓指针附近
+        let (before, after) = if current_depth == 0 {
+            (OBJECT_WINDOW_BEFORE, OBJECT_WINDOW_AFTER)
+        } else {
+            (0, POINTEE_WINDOW)
+        };
+
+        let mut next = Vec::new();
+
+        for addr in level {
+            if addr == 0 || !visited.insert(addr) || total >= max_bytes {
+                continue;
+            }
+
+            let start = addr.saturating_sub(before);
+            total += capture_range(&mut snapshot, start, before + after);
+
+            if current_depth == depth {
+                continue;
+            }
+
+            let mut buf = vec![0u8; after];
+            if !snapshot.read_bytes(addr, &mut buf) {
+                continue;
+            }
+
+            for chunk in buf.chunks_exact(8) {
+                let val = u64::from_le_bytes(chunk.try_into().unwrap()) as usize;
+
+                if val != 0 && LiveMemory.read_bytes(val, &mut [0u8; 8]) {
+                    next.push(val);
+                }
+            }
+        }
+
+        level = next;
+    }
+
+    snapshot
+}
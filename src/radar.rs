@@ -0,0 +1,310 @@
+use crate::{
+    draw::{Draw, Layer},
+    math::{Vec2, Vec3},
+};
+use std::collections::BTreeMap;
+
+// 最多记住多少个实体，满了以后丢掉最久没见到的
+const MAX_KNOWN: usize = 4096;
+
+const DOT_RADIUS: f32 = 3.5;
+const PLAYER_SIZE: f32 = 7.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum RadarShape {
+    #[default]
+    Circle,
+    Square,
+}
+
+impl std::fmt::Display for RadarShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RadarShape::Circle => write!(f, "圆形"),
+            RadarShape::Square => write!(f, "方形"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RadarConfig {
+    pub(crate) shape: RadarShape,
+    // 雷达边缘对应的世界距离
+    pub(crate) range: f32,
+    pub(crate) rings: u32,
+    pub(crate) background: [f32; 4],
+    pub(crate) foreground: [f32; 4],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RadarDot {
+    pub(crate) pos: Vec3<f32>,
+    pub(crate) color: [f32; 4],
+}
+
+// GetObjectsInFrustum 只返回视野里的实体，见过的 ModelObject 先记下来，
+// 转过身以后还能继续读它们的坐标，读不出来时再丢掉
+#[derive(Debug, Default, Clone)]
+pub(crate) struct EntityCache {
+    // 地址 -> 最后一次见到的序号
+    addrs: BTreeMap<usize, u64>,
+    // 序号 -> 地址，第一个就是最久没见到的
+    seen: BTreeMap<u64, usize>,
+    next: u64,
+}
+
+impl EntityCache {
+    pub(crate) const fn new() -> Self {
+        Self {
+            addrs: BTreeMap::new(),
+            seen: BTreeMap::new(),
+            next: 0,
+        }
+    }
+
+    pub(crate) fn extend(&mut self, addrs: impl IntoIterator<Item = usize>) {
+        for addr in addrs {
+            if let Some(old) = self.addrs.insert(addr, self.next) {
+                self.seen.remove(&old);
+            }
+
+            self.seen.insert(self.next, addr);
+            self.next += 1;
+
+            if self.addrs.len() > MAX_KNOWN
+                && let Some((_, oldest)) = self.seen.pop_first()
+            {
+                self.addrs.remove(&oldest);
+            }
+        }
+    }
+
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&usize) -> bool) {
+        let seen = &mut self.seen;
+
+        self.addrs.retain(|addr, order| {
+            let keep = f(addr);
+
+            if !keep {
+                seen.remove(order);
+            }
+
+            keep
+        });
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.addrs.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.addrs.clear();
+        self.seen.clear();
+    }
+}
+
+// 把世界坐标换到雷达上，朝向 yaw 的方向在上面，单位是像素
+pub(crate) fn to_radar(player_pos: Vec3<f32>, yaw: f32, scale: f32, pos: Vec3<f32>) -> Vec2<f32> {
+    let delta = pos - player_pos;
+    let (sin, cos) = yaw.to_radians().sin_cos();
+
+    // 和 Mat4::look_to 一样是左手系，right = up x forward
+    let forward = delta.x * cos + delta.z * sin;
+    let right = delta.x * sin - delta.z * cos;
+
+    Vec2::new(right * scale, -forward * scale)
+}
+
+// 超出雷达的点压到边缘上
+fn clamp_to_shape(shape: RadarShape, radius: f32, offset: Vec2<f32>) -> Vec2<f32> {
+    match shape {
+        RadarShape::Circle => {
+            let len = offset.length();
+            if len > radius {
+                offset * (radius / len)
+            } else {
+                offset
+            }
+        }
+        RadarShape::Square => Vec2::new(
+            offset.x.clamp(-radius, radius),
+            offset.y.clamp(-radius, radius),
+        ),
+    }
+}
+
+// 画在当前 imgui 窗口里，min/size 是可用的区域，在其中取最大的正方形
+pub(crate) fn draw_radar<D: Draw>(
+    draw: &mut D,
+    min: Vec2<f32>,
+    size: Vec2<f32>,
+    config: &RadarConfig,
+    player_pos: Vec3<f32>,
+    yaw: f32,
+    dots: &[RadarDot],
+) {
+    let layer = Layer::Window;
+
+    let radius = size.x.min(size.y) / 2.0;
+    if radius <= PLAYER_SIZE || config.range <= 0.0 {
+        return;
+    }
+
+    let center = min + size * 0.5;
+    let scale = radius / config.range;
+
+    match config.shape {
+        RadarShape::Circle => {
+            draw.circle(
+                layer,
+                center.to_array(),
+                radius,
+                config.background,
+                1.0,
+                true,
+            );
+            draw.circle(
+                layer,
+                center.to_array(),
+                radius,
+                config.foreground,
+                1.5,
+                false,
+            );
+        }
+        RadarShape::Square => {
+            let corner = Vec2::new(radius, radius);
+            let (top_left, bottom_right) =
+                ((center - corner).to_array(), (center + corner).to_array());
+
+            draw.rect(layer, top_left, bottom_right, config.background, 1.0, true);
+            draw.rect(layer, top_left, bottom_right, config.foreground, 1.5, false);
+        }
+    }
+
+    for ring in 1..config.rings {
+        let ring_radius = radius * ring as f32 / config.rings as f32;
+
+        draw.circle(
+            layer,
+            center.to_array(),
+            ring_radius,
+            config.foreground,
+            1.0,
+            false,
+        );
+        draw.text(
+            layer,
+            [center.x + 2.0, center.y - ring_radius],
+            config.foreground,
+            format!("{:.0}", config.range * ring as f32 / config.rings as f32),
+        );
+    }
+
+    draw.line(
+        layer,
+        [center.x - radius, center.y],
+        [center.x + radius, center.y],
+        config.foreground,
+        1.0,
+    );
+    draw.line(
+        layer,
+        [center.x, center.y - radius],
+        [center.x, center.y + radius],
+        config.foreground,
+        1.0,
+    );
+
+    for dot in dots {
+        let offset = clamp_to_shape(
+            config.shape,
+            radius - DOT_RADIUS,
+            to_radar(player_pos, yaw, scale, dot.pos),
+        );
+
+        draw.circle(
+            layer,
+            (center + offset).to_array(),
+            DOT_RADIUS,
+            dot.color,
+            1.0,
+            true,
+        );
+    }
+
+    draw.polyline(
+        layer,
+        vec![
+            [center.x, center.y - PLAYER_SIZE],
+            [center.x + PLAYER_SIZE * 0.7, center.y + PLAYER_SIZE * 0.7],
+            [center.x - PLAYER_SIZE * 0.7, center.y + PLAYER_SIZE * 0.7],
+        ],
+        config.foreground,
+        2.0,
+        true,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_evicts_oldest_when_full() {
+        let mut cache = EntityCache::new();
+
+        cache.extend(0..MAX_KNOWN);
+        assert_eq!(cache.len(), MAX_KNOWN);
+
+        // 新见到的实体挤掉最早见到的
+        cache.extend([MAX_KNOWN, MAX_KNOWN + 1]);
+        assert_eq!(cache.len(), MAX_KNOWN);
+
+        let mut kept = Vec::new();
+        cache.retain(|addr| {
+            kept.push(*addr);
+            true
+        });
+
+        assert!(!kept.contains(&0));
+        assert!(!kept.contains(&1));
+        assert!(kept.contains(&2));
+        assert!(kept.contains(&MAX_KNOWN));
+        assert!(kept.contains(&(MAX_KNOWN + 1)));
+    }
+
+    #[test]
+    fn cache_seen_again_is_not_evicted() {
+        let mut cache = EntityCache::new();
+
+        cache.extend(0..MAX_KNOWN);
+        // 0 又出现在视野里，下一个被挤掉的应该是 1
+        cache.extend([0]);
+        cache.extend([MAX_KNOWN]);
+
+        let mut kept = Vec::new();
+        cache.retain(|addr| {
+            kept.push(*addr);
+            true
+        });
+
+        assert_eq!(kept.len(), MAX_KNOWN);
+        assert!(kept.contains(&0));
+        assert!(!kept.contains(&1));
+    }
+
+    #[test]
+    fn cache_retain_drops_order_too() {
+        let mut cache = EntityCache::new();
+
+        cache.extend(0..10);
+        cache.retain(|addr| addr % 2 == 0);
+        assert_eq!(cache.len(), 5);
+
+        // 删掉的地址不能再占着淘汰顺序
+        cache.extend(10..MAX_KNOWN + 5);
+        assert_eq!(cache.len(), MAX_KNOWN);
+        assert_eq!(cache.seen.len(), MAX_KNOWN);
+    }
+}
@@ -0,0 +1,631 @@
+use crate::{
+    draw::{Draw, DrawCmd, Layer},
+    math::{Camera, Vec3},
+    offsets::Offsets,
+    snapshot::Memory,
+    world::{self, ModelType},
+};
+use rhai::{
+    AST, Array, CallFnOptions, Dynamic, Engine, EvalAltResult, ImmutableString, Map, Scope,
+};
+use std::{
+    cell::{RefCell, RefMut},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+// 防止脚本死循环卡住游戏
+const MAX_OPERATIONS: u64 = 1_000_000;
+// 每个脚本留最近几条 log
+const MAX_LOGS: usize = 20;
+
+// 每帧交给脚本的实体
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ScriptEntity {
+    pub(crate) addr: usize,
+    pub(crate) model_type: ModelType,
+    pub(crate) pos: Vec3<f32>,
+    pub(crate) health: f32,
+    pub(crate) distance: f32,
+    pub(crate) preset: String,
+}
+
+// 和 lib.rs 的实体循环一样跳过自己
+pub(crate) fn collect_entities<M: Memory>(
+    mem: &M,
+    offsets: &Offsets,
+    model_obj_addrs: &[usize],
+    player_pos: Vec3<f32>,
+    player_c_model_obj_p: usize,
+) -> Vec<ScriptEntity> {
+    let mut entities = Vec::new();
+
+    for &model_obj_p in model_obj_addrs {
+        let obj = match world::decode_obj(mem, offsets, model_obj_p) {
+            Some(val) => val,
+            None => continue,
+        };
+
+        if obj.c_model_obj_p as usize == player_c_model_obj_p {
+            continue;
+        }
+
+        entities.push(ScriptEntity {
+            addr: model_obj_p,
+            model_type: obj.model_obj_type,
+            pos: obj.c_model_obj_world_pos,
+            health: mem
+                .read::<f32>(obj.model_obj_health_p as usize)
+                .unwrap_or_default(),
+            distance: (obj.c_model_obj_world_pos - player_pos).length(),
+            preset: obj.model_obj_str,
+        });
+    }
+
+    entities
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ScriptFrame {
+    pub(crate) time: f64,
+    pub(crate) player_pos: Vec3<f32>,
+    pub(crate) camera: Camera,
+    pub(crate) entities: Vec<ScriptEntity>,
+    // 主程序的部分设置，只读
+    pub(crate) settings: Map,
+}
+
+fn vec3_map(pos: Vec3<f32>) -> Map {
+    let mut map = Map::new();
+    map.insert("x".into(), Dynamic::from_float(pos.x as f64));
+    map.insert("y".into(), Dynamic::from_float(pos.y as f64));
+    map.insert("z".into(), Dynamic::from_float(pos.z as f64));
+    map
+}
+
+impl ScriptFrame {
+    // 脚本里看到的 frame:
+    //   frame.time  frame.player.x/y/z  frame.yaw  frame.pitch
+    //   frame.screen_w  frame.screen_h  frame.settings
+    //   frame.entities[i].addr/type/name/x/y/z/health/distance/preset
+    fn to_dynamic(&self) -> Dynamic {
+        let entities: Array = self
+            .entities
+            .iter()
+            .map(|entity| {
+                let mut map = vec3_map(entity.pos);
+                map.insert("addr".into(), Dynamic::from_int(entity.addr as i64));
+                map.insert("type".into(), format!("{:?}", entity.model_type).into());
+                map.insert("name".into(), entity.model_type.to_string().into());
+                map.insert("health".into(), Dynamic::from_float(entity.health as f64));
+                map.insert(
+                    "distance".into(),
+                    Dynamic::from_float(entity.distance as f64),
+                );
+                map.insert("preset".into(), entity.preset.clone().into());
+                Dynamic::from_map(map)
+            })
+            .collect();
+
+        let mut map = Map::new();
+        map.insert("time".into(), Dynamic::from_float(self.time));
+        map.insert(
+            "player".into(),
+            Dynamic::from_map(vec3_map(self.player_pos)),
+        );
+        map.insert("yaw".into(), Dynamic::from_float(self.camera.yaw as f64));
+        map.insert(
+            "pitch".into(),
+            Dynamic::from_float(self.camera.pitch as f64),
+        );
+        map.insert(
+            "screen_w".into(),
+            Dynamic::from_float(self.camera.width as f64),
+        );
+        map.insert(
+            "screen_h".into(),
+            Dynamic::from_float(self.camera.height as f64),
+        );
+        map.insert("entities".into(), Dynamic::from_array(entities));
+        map.insert("settings".into(), Dynamic::from_map(self.settings.clone()));
+
+        Dynamic::from_map(map)
+    }
+}
+
+// 注册的函数和宿主之间共享的状态，调用每个脚本前换成这个脚本的
+#[derive(Default)]
+struct Shared {
+    cmds: Vec<DrawCmd>,
+    logs: Vec<String>,
+    settings: Vec<(String, Dynamic)>,
+    camera: Camera,
+}
+
+pub(crate) struct Script {
+    pub(crate) name: String,
+    pub(crate) path: PathBuf,
+    pub(crate) enabled: bool,
+    pub(crate) error: Option<String>,
+    // setting() 登记的设置，菜单里可以改
+    pub(crate) settings: Vec<(String, Dynamic)>,
+    pub(crate) logs: Vec<String>,
+    modified: Option<SystemTime>,
+    ast: Option<AST>,
+    // 脚本里的 this，跨帧保存状态
+    state: Dynamic,
+}
+
+pub(crate) struct ScriptHost {
+    engine: Engine,
+    shared: Rc<RefCell<Shared>>,
+    pub(crate) scripts: Vec<Script>,
+}
+
+type RhaiResult<T> = Result<T, Box<EvalAltResult>>;
+
+// 注册的函数里不 panic，借不到时返回错误让脚本停掉
+fn borrow_shared(state: &RefCell<Shared>) -> RhaiResult<RefMut<'_, Shared>> {
+    state
+        .try_borrow_mut()
+        .map_err(|_| "宿主状态正在使用，不能重入".into())
+}
+
+// 整数和小数都接受
+fn num(val: &Dynamic) -> RhaiResult<f32> {
+    if let Ok(val) = val.as_float() {
+        return Ok(val as f32);
+    }
+
+    match val.as_int() {
+        Ok(val) => Ok(val as f32),
+        Err(_) => Err(format!("需要数字，传入的是 {}", val.type_name()).into()),
+    }
+}
+
+// [r, g, b, a] 或 [r, g, b]，0.0 ~ 1.0
+fn color(val: &Array) -> RhaiResult<[f32; 4]> {
+    let parts = val.iter().map(num).collect::<RhaiResult<Vec<f32>>>()?;
+
+    match parts[..] {
+        [r, g, b] => Ok([r, g, b, 1.0]),
+        [r, g, b, a] => Ok([r, g, b, a]),
+        _ => Err("颜色要有 3 或 4 个数".into()),
+    }
+}
+
+impl ScriptHost {
+    pub(crate) fn new() -> Self {
+        let shared = Rc::new(RefCell::new(Shared::default()));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(32);
+        engine.set_max_string_size(0x10000);
+        engine.set_max_array_size(0x10000);
+
+        let state = shared.clone();
+        engine.on_print(move |text| {
+            if let Ok(mut state) = state.try_borrow_mut() {
+                state.logs.push(text.to_string());
+            }
+        });
+
+        let state = shared.clone();
+        engine.register_fn("log", move |text: ImmutableString| -> RhaiResult<()> {
+            borrow_shared(&state)?.logs.push(text.to_string());
+            Ok(())
+        });
+
+        engine.register_fn(
+            "rgba",
+            |r: Dynamic, g: Dynamic, b: Dynamic, a: Dynamic| -> RhaiResult<Array> {
+                for val in [&r, &g, &b, &a] {
+                    num(val)?;
+                }
+                Ok(vec![r, g, b, a])
+            },
+        );
+
+        let state = shared.clone();
+        engine.register_fn(
+            "draw_text",
+            move |x: Dynamic, y: Dynamic, text: ImmutableString, rgba: Array| -> RhaiResult<()> {
+                borrow_shared(&state)?.cmds.push(DrawCmd::Text {
+                    layer: Layer::Background,
+                    pos: [num(&x)?, num(&y)?],
+                    color: color(&rgba)?,
+                    text: text.to_string(),
+                });
+                Ok(())
+            },
+        );
+
+        let state = shared.clone();
+        engine.register_fn(
+            "draw_line",
+            move |x1: Dynamic,
+                  y1: Dynamic,
+                  x2: Dynamic,
+                  y2: Dynamic,
+                  rgba: Array,
+                  thickness: Dynamic|
+                  -> RhaiResult<()> {
+                borrow_shared(&state)?.cmds.push(DrawCmd::Line {
+                    layer: Layer::Background,
+                    from: [num(&x1)?, num(&y1)?],
+                    to: [num(&x2)?, num(&y2)?],
+                    color: color(&rgba)?,
+                    thickness: num(&thickness)?,
+                });
+                Ok(())
+            },
+        );
+
+        let state = shared.clone();
+        engine.register_fn(
+            "draw_circle",
+            move |x: Dynamic,
+                  y: Dynamic,
+                  radius: Dynamic,
+                  rgba: Array,
+                  filled: bool|
+                  -> RhaiResult<()> {
+                borrow_shared(&state)?.cmds.push(DrawCmd::Circle {
+                    layer: Layer::Background,
+                    center: [num(&x)?, num(&y)?],
+                    radius: num(&radius)?,
+                    color: color(&rgba)?,
+                    thickness: 1.5,
+                    filled,
+                });
+                Ok(())
+            },
+        );
+
+        let state = shared.clone();
+        engine.register_fn(
+            "draw_rect",
+            move |x1: Dynamic,
+                  y1: Dynamic,
+                  x2: Dynamic,
+                  y2: Dynamic,
+                  rgba: Array,
+                  filled: bool|
+                  -> RhaiResult<()> {
+                borrow_shared(&state)?.cmds.push(DrawCmd::Rect {
+                    layer: Layer::Background,
+                    min: [num(&x1)?, num(&y1)?],
+                    max: [num(&x2)?, num(&y2)?],
+                    color: color(&rgba)?,
+                    thickness: 1.5,
+                    filled,
+                });
+                Ok(())
+            },
+        );
+
+        // 身后的点返回 ()
+        let state = shared.clone();
+        engine.register_fn(
+            "world_to_screen",
+            move |x: Dynamic, y: Dynamic, z: Dynamic| -> RhaiResult<Dynamic> {
+                let pos = Vec3::new(num(&x)?, num(&y)?, num(&z)?);
+
+                let screen_pos = match borrow_shared(&state)?.camera.world_to_screen(pos).screen() {
+                    Some(val) => val,
+                    None => return Ok(Dynamic::UNIT),
+                };
+
+                let mut map = Map::new();
+                map.insert("x".into(), Dynamic::from_float(screen_pos.x as f64));
+                map.insert("y".into(), Dynamic::from_float(screen_pos.y as f64));
+                Ok(Dynamic::from_map(map))
+            },
+        );
+
+        // setting("名字", 默认值) 返回菜单里的当前值，第一次调用时登记
+        let state = shared.clone();
+        engine.register_fn(
+            "setting",
+            move |name: ImmutableString, default: Dynamic| -> RhaiResult<Dynamic> {
+                let mut state = borrow_shared(&state)?;
+
+                if let Some((_, val)) = state.settings.iter().find(|(key, _)| *key == name) {
+                    return Ok(val.clone());
+                }
+
+                state.settings.push((name.to_string(), default.clone()));
+                Ok(default)
+            },
+        );
+
+        Self {
+            engine,
+            shared,
+            scripts: Vec::new(),
+        }
+    }
+
+    // 编译并执行顶层语句，出错时记在 error 里
+    fn compile(&self, script: &mut Script, source: &str) {
+        script.ast = None;
+        script.state = Dynamic::from_map(Map::new());
+        script.logs.clear();
+
+        let ast = match self.engine.compile(source) {
+            Ok(val) => val,
+            Err(err) => {
+                script.error = Some(format!("编译失败: {}", err));
+                return;
+            }
+        };
+
+        let result = self.engine.run_ast_with_scope(&mut Scope::new(), &ast);
+        script.logs.append(&mut self.shared.borrow_mut().logs);
+
+        if let Err(err) = result {
+            script.error = Some(format!("运行失败: {}", err));
+            return;
+        }
+
+        script.error = None;
+        script.ast = Some(ast);
+    }
+
+    // 不经过文件直接加载，离线测试用
+    pub(crate) fn load_source(&mut self, name: &str, source: &str) {
+        let mut script = Script {
+            name: name.to_string(),
+            path: PathBuf::new(),
+            enabled: true,
+            error: None,
+            settings: Vec::new(),
+            logs: Vec::new(),
+            modified: None,
+            ast: None,
+            state: Dynamic::UNIT,
+        };
+
+        self.compile(&mut script, source);
+        self.scripts.retain(|val| val.name != name);
+        self.scripts.push(script);
+    }
+
+    // 扫描目录里的 *.rhai，新的和改过的重新编译，删掉的移除
+    // 返回重新编译的脚本名
+    pub(crate) fn scan(&mut self, dir: &Path) -> Result<Vec<String>, String> {
+        let entries =
+            std::fs::read_dir(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|val| val.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+            .collect();
+        paths.sort();
+
+        self.scripts.retain(|script| paths.contains(&script.path));
+
+        let mut reloaded = Vec::new();
+
+        for path in paths {
+            let modified = std::fs::metadata(&path).and_then(|val| val.modified()).ok();
+
+            let index = match self.scripts.iter().position(|script| script.path == path) {
+                Some(index) if self.scripts[index].modified == modified => continue,
+                Some(index) => index,
+                None => {
+                    self.scripts.push(Script {
+                        name: path
+                            .file_stem()
+                            .map(|val| val.to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                        path: path.clone(),
+                        enabled: false,
+                        error: None,
+                        settings: Vec::new(),
+                        logs: Vec::new(),
+                        modified: None,
+                        ast: None,
+                        state: Dynamic::UNIT,
+                    });
+                    self.scripts.len() - 1
+                }
+            };
+
+            let mut script = self.scripts.remove(index);
+            script.modified = modified;
+
+            match std::fs::read_to_string(&path) {
+                Ok(source) => self.compile(&mut script, &source),
+                Err(err) => script.error = Some(format!("读取失败: {}", err)),
+            }
+
+            reloaded.push(script.name.clone());
+            self.scripts.insert(index, script);
+        }
+
+        Ok(reloaded)
+    }
+
+    // 调用每个开启的脚本的 on_frame(frame)，出错的脚本会被关掉
+    pub(crate) fn run_frame<D: Draw>(&mut self, frame: &ScriptFrame, draw: &mut D) {
+        let frame_dynamic = frame.to_dynamic();
+
+        for script in &mut self.scripts {
+            if !script.enabled {
+                continue;
+            }
+
+            let Some(ast) = &script.ast else {
+                continue;
+            };
+
+            if !ast.iter_functions().any(|func| func.name == "on_frame") {
+                continue;
+            }
+
+            {
+                let mut shared = self.shared.borrow_mut();
+                shared.cmds.clear();
+                shared.logs.clear();
+                shared.settings = std::mem::take(&mut script.settings);
+                shared.camera = frame.camera;
+            }
+
+            let engine = &self.engine;
+            let state = &mut script.state;
+            let frame = frame_dynamic.clone();
+
+            // release 是 panic = 'abort'，注册的函数出错只能返回 Err
+            let result = engine.call_fn_with_options::<Dynamic>(
+                CallFnOptions::new().eval_ast(false).bind_this_ptr(state),
+                &mut Scope::new(),
+                ast,
+                "on_frame",
+                (frame,),
+            );
+
+            let mut shared = self.shared.borrow_mut();
+            script.settings = std::mem::take(&mut shared.settings);
+
+            script.logs.append(&mut shared.logs);
+            if script.logs.len() > MAX_LOGS {
+                let excess = script.logs.len() - MAX_LOGS;
+                script.logs.drain(..excess);
+            }
+
+            match result {
+                Ok(_) => {
+                    for cmd in shared.cmds.drain(..) {
+                        draw.submit(cmd);
+                    }
+                }
+                Err(err) => {
+                    script.error = Some(err.to_string());
+                    script.enabled = false;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::Recorder;
+
+    fn frame() -> ScriptFrame {
+        ScriptFrame {
+            time: 1.0,
+            camera: Camera {
+                fov: 60.0,
+                width: 1920.0,
+                height: 1080.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn on_frame_draw_calls_become_draw_cmds() {
+        let mut host = ScriptHost::new();
+        host.load_source(
+            "draw",
+            r#"
+            fn on_frame(frame) {
+                draw_text(10, 20.5, "hi", [1, 0, 0]);
+                draw_line(0, 0, frame.screen_w, frame.screen_h, rgba(0, 1, 0, 0.5), 2);
+            }
+            "#,
+        );
+
+        let mut draw = Recorder::default();
+        host.run_frame(&frame(), &mut draw);
+
+        assert_eq!(host.scripts[0].error, None);
+        assert_eq!(
+            draw.take(),
+            vec![
+                DrawCmd::Text {
+                    layer: Layer::Background,
+                    pos: [10.0, 20.5],
+                    color: [1.0, 0.0, 0.0, 1.0],
+                    text: "hi".to_string(),
+                },
+                DrawCmd::Line {
+                    layer: Layer::Background,
+                    from: [0.0, 0.0],
+                    to: [1920.0, 1080.0],
+                    color: [0.0, 1.0, 0.0, 0.5],
+                    thickness: 2.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn error_disables_script_and_drops_its_cmds() {
+        let mut host = ScriptHost::new();
+        host.load_source(
+            "bad",
+            r#"
+            fn on_frame(frame) {
+                draw_text(0, 0, "half", [1, 1, 1]);
+                draw_text(0, 0, "bad color", [1, 1]);
+            }
+            "#,
+        );
+
+        let mut draw = Recorder::default();
+        host.run_frame(&frame(), &mut draw);
+
+        let script = &host.scripts[0];
+        assert!(!script.enabled);
+        assert!(
+            script
+                .error
+                .as_ref()
+                .is_some_and(|err| err.contains("颜色"))
+        );
+        assert!(draw.cmds.is_empty());
+
+        // 关掉之后不再运行
+        host.run_frame(&frame(), &mut draw);
+        assert!(draw.cmds.is_empty());
+    }
+
+    #[test]
+    fn setting_keeps_value_changed_in_menu() {
+        let mut host = ScriptHost::new();
+        host.load_source(
+            "setting",
+            r#"
+            fn on_frame(frame) {
+                draw_text(0, 0, setting("label", "default"), [1, 1, 1]);
+            }
+            "#,
+        );
+
+        let mut draw = Recorder::default();
+        host.run_frame(&frame(), &mut draw);
+
+        let settings = &mut host.scripts[0].settings;
+        assert_eq!(settings.len(), 1);
+        assert_eq!(settings[0].0, "label");
+        assert_eq!(settings[0].1.to_string(), "default");
+
+        // 菜单里改过
+        settings[0].1 = Dynamic::from("changed".to_string());
+        draw.take();
+        host.run_frame(&frame(), &mut draw);
+
+        assert_eq!(host.scripts[0].settings.len(), 1);
+        assert!(matches!(
+            &draw.cmds[..],
+            [DrawCmd::Text { text, .. }] if text == "changed"
+        ));
+    }
+}
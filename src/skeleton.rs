@@ -0,0 +1,267 @@
+use crate::world::ModelType;
+
+// 没有骨架文件时用的默认骨架，索引和 EBones 一致
+// 格式:
+//   rig <名字>             开始一个新骨架
+//   types <ModelType...>  用这个骨架的分类，可以没有
+//   thickness <粗细>
+//   joints <0|1>          关节上画点
+//   bone <索引> <名字>     给索引起名，索引可以超出 EBones
+//   chain <索引或名字...>  一条骨骼链
+const BUILTIN: &str = "\
+rig humanoid
+types ZombieNormal ZombieSpecial ZombieHunter SurvivorNormal SurvivorSpecial SurvivorShopkeeper PlayerHuman PlayerHunter
+thickness 1.5
+joints 0
+bone 0 Pelvis
+bone 1 Spine
+bone 2 Spine1
+bone 3 Spine2
+bone 4 Spine3
+bone 5 Neck
+bone 6 Neck1
+bone 7 Neck2
+bone 8 Head
+bone 9 EyeCamera
+bone 10 LClavicle
+bone 11 LUpperarm
+bone 12 LForearm
+bone 13 LHand
+bone 14 RClavicle
+bone 15 RUpperarm
+bone 16 RForearm
+bone 17 RHand
+bone 18 LThigh
+bone 19 RThigh
+bone 20 LCalf
+bone 21 RCalf
+bone 22 LFoot
+bone 23 RFoot
+chain Head Neck Spine3 Spine2 Spine1 Pelvis
+chain Neck LUpperarm LForearm LHand
+chain Neck RUpperarm RForearm RHand
+chain Pelvis LThigh LCalf LFoot
+chain Pelvis RThigh RCalf RFoot
+";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Rig {
+    pub(crate) name: String,
+    pub(crate) types: Vec<ModelType>,
+    pub(crate) bones: Vec<(u8, String)>,
+    pub(crate) chains: Vec<Vec<u8>>,
+    pub(crate) thickness: f32,
+    pub(crate) joints: bool,
+}
+
+impl Rig {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            types: Vec::new(),
+            bones: Vec::new(),
+            chains: Vec::new(),
+            thickness: 1.5,
+            joints: false,
+        }
+    }
+
+    // 骨骼链用到的所有索引，不重复
+    pub(crate) fn chain_bones(&self) -> Vec<u8> {
+        let mut bones: Vec<u8> = self.chains.iter().flatten().copied().collect();
+        bones.sort_unstable();
+        bones.dedup();
+
+        bones
+    }
+
+    fn bone_index(&self, text: &str) -> Option<u8> {
+        if let Ok(val) = text.parse() {
+            return Some(val);
+        }
+
+        self.bones
+            .iter()
+            .find(|(_, name)| name == text)
+            .map(|(index, _)| *index)
+    }
+
+    fn bone_name(&self, index: u8) -> String {
+        match self.bones.iter().find(|(val, _)| *val == index) {
+            Some((_, name)) => name.clone(),
+            None => index.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Rig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "rig {}", self.name)?;
+
+        if !self.types.is_empty() {
+            write!(f, "types")?;
+            for model_type in &self.types {
+                write!(f, " {:?}", model_type)?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "thickness {}", self.thickness)?;
+        writeln!(f, "joints {}", self.joints as u8)?;
+
+        for (index, name) in &self.bones {
+            writeln!(f, "bone {} {}", index, name)?;
+        }
+
+        for chain in &self.chains {
+            write!(f, "chain")?;
+            for index in chain {
+                write!(f, " {}", self.bone_name(*index))?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Skeletons {
+    pub(crate) rigs: Vec<Rig>,
+}
+
+impl Skeletons {
+    pub(crate) fn builtin() -> Self {
+        Self::parse(BUILTIN).unwrap_or_default()
+    }
+
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let mut rigs: Vec<Rig> = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |msg: &str| format!("第 {} 行: {}", line_index + 1, msg);
+
+            let mut parts = line.split_whitespace();
+            let key = parts.next().unwrap_or_default();
+
+            if key == "rig" {
+                let name = parts.next().ok_or_else(|| error("缺少骨架名"))?;
+                if rigs.iter().any(|rig| rig.name == name) {
+                    return Err(error("骨架名重复"));
+                }
+
+                rigs.push(Rig::new(name));
+                continue;
+            }
+
+            let rig = rigs.last_mut().ok_or_else(|| error("前面没有 rig"))?;
+
+            match key {
+                "types" => {
+                    for name in parts {
+                        let model_type =
+                            ModelType::from_name(name).ok_or_else(|| error("未知的分类"))?;
+                        rig.types.push(model_type);
+                    }
+                }
+                "thickness" => {
+                    rig.thickness = parts
+                        .next()
+                        .and_then(|val| val.parse().ok())
+                        .ok_or_else(|| error("粗细不是数字"))?;
+                }
+                "joints" => {
+                    rig.joints = match parts.next() {
+                        Some("0") => false,
+                        Some("1") => true,
+                        _ => return Err(error("joints 只能是 0 或 1")),
+                    };
+                }
+                "bone" => {
+                    let index = parts
+                        .next()
+                        .and_then(|val| val.parse().ok())
+                        .ok_or_else(|| error("骨骼索引要在 0-255 之间"))?;
+                    let name = parts.next().ok_or_else(|| error("缺少骨骼名"))?;
+
+                    if name.parse::<u8>().is_ok() {
+                        return Err(error("骨骼名不能是数字"));
+                    }
+
+                    rig.bones.retain(|(val, _)| *val != index);
+                    rig.bones.push((index, name.to_string()));
+                }
+                "chain" => {
+                    let mut chain = Vec::new();
+                    for name in parts {
+                        chain.push(rig.bone_index(name).ok_or_else(|| error("未知的骨骼"))?);
+                    }
+
+                    if chain.len() < 2 {
+                        return Err(error("骨骼链至少要两个骨骼"));
+                    }
+
+                    rig.chains.push(chain);
+                }
+                _ => return Err(error("未知的关键字")),
+            }
+        }
+
+        Ok(Self { rigs })
+    }
+
+    pub(crate) fn to_text(&self) -> String {
+        let rigs: Vec<String> = self.rigs.iter().map(|rig| rig.to_string()).collect();
+
+        rigs.join("\n")
+    }
+
+    // 先找列出了这个分类的骨架，后面的优先，这样追加到文件末尾的骨架能覆盖默认的
+    // 没有骨骼链的骨架画不出东西，跳过，都没有就用名字是 fallback 的骨架
+    pub(crate) fn rig_for(&self, model_type: ModelType, fallback: &str) -> Option<&Rig> {
+        self.rigs
+            .iter()
+            .rev()
+            .find(|rig| !rig.chains.is_empty() && rig.types.contains(&model_type))
+            .or_else(|| self.rigs.iter().find(|rig| rig.name == fallback))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rig_for_prefers_later_rigs_with_chains() {
+        let mut skeletons = Skeletons::builtin();
+
+        // 探测页刚保存、还没补骨骼链的骨架
+        let mut probe = Rig::new("probe");
+        probe.types.push(ModelType::ZombieNormal);
+        probe.bones.push((0, "Root".to_string()));
+        skeletons.rigs.push(probe);
+
+        let rig = skeletons
+            .rig_for(ModelType::ZombieNormal, "humanoid")
+            .unwrap();
+        assert_eq!(rig.name, "humanoid");
+
+        skeletons.rigs.last_mut().unwrap().chains.push(vec![0, 1]);
+        let rig = skeletons
+            .rig_for(ModelType::ZombieNormal, "humanoid")
+            .unwrap();
+        assert_eq!(rig.name, "probe");
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let skeletons = Skeletons::builtin();
+        assert_eq!(skeletons.rigs.len(), 1);
+        assert_eq!(Skeletons::parse(&skeletons.to_text()), Ok(skeletons));
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    mem::{MaybeUninit, size_of},
+};
+
+const DUMP_MAGIC: &[u8; 8] = b"DLSNAP01";
+
+pub(crate) trait Memory {
+    fn read_bytes(&self, addr: usize, buf: &mut [u8]) -> bool;
+
+    fn read<T: Copy>(&self, addr: usize) -> Option<T>
+    where
+        Self: Sized,
+    {
+        let mut val = MaybeUninit::<T>::zeroed();
+
+        // 只用于 u8/u32/f32/usize/Vec3 这类纯数据
+        let buf = unsafe {
+            std::slice::from_raw_parts_mut(val.as_mut_ptr().cast::<u8>(), size_of::<T>())
+        };
+
+        if !self.read_bytes(addr, buf) {
+            return None;
+        }
+
+        Some(unsafe { val.assume_init() })
+    }
+
+    fn read_ptr(&self, addr: usize) -> Option<usize>
+    where
+        Self: Sized,
+    {
+        self.read::<u64>(addr).map(|val| val as usize)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Module {
+    pub(crate) name: String,
+    pub(crate) base: usize,
+    pub(crate) size: usize,
+}
+
+impl Module {
+    pub(crate) fn contains(&self, addr: usize) -> bool {
+        addr >= self.base && addr < self.base + self.size
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Region {
+    pub(crate) base: usize,
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl Region {
+    pub(crate) fn end(&self) -> usize {
+        self.base + self.bytes.len()
+    }
+}
+
+// 按地址排序、互不重叠的内存区域
+// roots 记录重放时需要的起点，比如 CGame 指针和 ModelObject 数组的地址
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Snapshot {
+    pub(crate) modules: Vec<Module>,
+    pub(crate) roots: Vec<(String, usize)>,
+    regions: Vec<Region>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(modules: Vec<Module>) -> Self {
+        Self {
+            modules,
+            roots: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn root(&self, name: &str) -> Option<usize> {
+        self.roots
+            .iter()
+            .find(|(root, _)| root == name)
+            .map(|(_, addr)| *addr)
+    }
+
+    pub(crate) fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub(crate) fn total_bytes(&self) -> usize {
+        self.regions.iter().map(|region| region.bytes.len()).sum()
+    }
+
+    pub(crate) fn module_at(&self, addr: usize) -> Option<&Module> {
+        self.modules.iter().find(|module| module.contains(addr))
+    }
+
+    pub(crate) fn contains(&self, addr: usize) -> bool {
+        self.region_at(addr).is_some()
+    }
+
+    fn region_at(&self, addr: usize) -> Option<&Region> {
+        let index = self.regions.partition_point(|region| region.base <= addr);
+        if index == 0 {
+            return None;
+        }
+
+        let region = &self.regions[index - 1];
+        if addr < region.end() {
+            Some(region)
+        } else {
+            None
+        }
+    }
+
+    // 新数据覆盖旧数据，相邻或重叠的区域合并为一块
+    pub(crate) fn insert(&mut self, base: usize, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        // 按地址顺序抓取时大部分区域都是首尾相接的，直接追加
+        if let Some(region) = self.regions.last_mut()
+            && region.end() == base
+        {
+            region.bytes.extend_from_slice(bytes);
+            return;
+        }
+
+        let end = base + bytes.len();
+
+        let first = self.regions.partition_point(|region| region.end() < base);
+        let last = self.regions.partition_point(|region| region.base <= end);
+
+        if first >= last {
+            self.regions.insert(
+                first,
+                Region {
+                    base,
+                    bytes: bytes.to_vec(),
+                },
+            );
+            return;
+        }
+
+        let merged_base = base.min(self.regions[first].base);
+        let merged_end = end.max(self.regions[last - 1].end());
+
+        let mut merged = vec![0u8; merged_end - merged_base];
+        for region in &self.regions[first..last] {
+            let start = region.base - merged_base;
+            merged[start..start + region.bytes.len()].copy_from_slice(&region.bytes);
+        }
+
+        let start = base - merged_base;
+        merged[start..start + bytes.len()].copy_from_slice(bytes);
+
+        self.regions.splice(
+            first..last,
+            [Region {
+                base: merged_base,
+                bytes: merged,
+            }],
+        );
+    }
+}
+
+impl Snapshot {
+    // DLSNAP01
+    // u32 模块数，每个模块: u32 名字长度, 名字, u64 base, u64 size
+    // u32 起点数，每个起点: u32 名字长度, 名字, u64 地址
+    // u32 区域数，每个区域: u64 base, u64 长度, 数据
+    pub(crate) fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(DUMP_MAGIC)?;
+
+        w.write_all(&(self.modules.len() as u32).to_le_bytes())?;
+        for module in &self.modules {
+            write_str(w, &module.name)?;
+            w.write_all(&(module.base as u64).to_le_bytes())?;
+            w.write_all(&(module.size as u64).to_le_bytes())?;
+        }
+
+        w.write_all(&(self.roots.len() as u32).to_le_bytes())?;
+        for (name, addr) in &self.roots {
+            write_str(w, name)?;
+            w.write_all(&(*addr as u64).to_le_bytes())?;
+        }
+
+        w.write_all(&(self.regions.len() as u32).to_le_bytes())?;
+        for region in &self.regions {
+            w.write_all(&(region.base as u64).to_le_bytes())?;
+            w.write_all(&(region.bytes.len() as u64).to_le_bytes())?;
+            w.write_all(&region.bytes)?;
+        }
+
+        Ok(())
+    }
+
+    // 只在离线重放里用到
+    #[allow(dead_code)]
+    pub(crate) fn read_from<R: Read + Seek>(r: &mut R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != DUMP_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "不是快照文件"));
+        }
+
+        let mut snapshot = Snapshot::default();
+
+        for _ in 0..read_u32(r)? {
+            let name = read_str(r)?;
+            let base = read_u64(r)? as usize;
+            let size = read_u64(r)? as usize;
+            snapshot.modules.push(Module { name, base, size });
+        }
+
+        for _ in 0..read_u32(r)? {
+            let name = read_str(r)?;
+            let addr = read_u64(r)? as usize;
+            snapshot.roots.push((name, addr));
+        }
+
+        for _ in 0..read_u32(r)? {
+            let base = read_u64(r)? as usize;
+            let len = read_u64(r)?;
+            check_len(r, len)?;
+
+            let mut bytes = vec![0u8; len as usize];
+            r.read_exact(&mut bytes)?;
+
+            snapshot.insert(base, &bytes);
+        }
+
+        Ok(snapshot)
+    }
+}
+
+fn write_str<W: Write>(w: &mut W, text: &str) -> io::Result<()> {
+    w.write_all(&(text.len() as u32).to_le_bytes())?;
+    w.write_all(text.as_bytes())
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+// 长度是从文件里读出来的，先确认后面真有这么多数据再分配
+fn check_len<R: Seek>(r: &mut R, len: u64) -> io::Result<()> {
+    let pos = r.stream_position()?;
+    let end = r.seek(SeekFrom::End(0))?;
+    r.seek(SeekFrom::Start(pos))?;
+
+    if len > end.saturating_sub(pos) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "长度 {} 超过文件剩余的 {} 字节",
+                len,
+                end.saturating_sub(pos)
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+fn read_str<R: Read + Seek>(r: &mut R) -> io::Result<String> {
+    let len = read_u32(r)? as u64;
+    check_len(r, len)?;
+
+    let mut bytes = vec![0u8; len as usize];
+    r.read_exact(&mut bytes)?;
+
+    String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl Memory for Snapshot {
+    fn read_bytes(&self, addr: usize, buf: &mut [u8]) -> bool {
+        let region = match self.region_at(addr) {
+            Some(val) => val,
+            None => return false,
+        };
+
+        if addr + buf.len() > region.end() {
+            return false;
+        }
+
+        let start = addr - region.base;
+        buf.copy_from_slice(&region.bytes[start..start + buf.len()]);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn write_read_round_trip() {
+        let mut snapshot = Snapshot::new(vec![Module {
+            name: "game.dll".to_string(),
+            base: 0x1000,
+            size: 0x100,
+        }]);
+        snapshot.roots.push(("c_game_pp".to_string(), 0x1010));
+        snapshot.insert(0x1000, &[1, 2, 3, 4]);
+        snapshot.insert(0x2000, &[5, 6]);
+
+        let mut bytes = Vec::new();
+        snapshot.write_to(&mut bytes).unwrap();
+
+        let loaded = Snapshot::read_from(&mut Cursor::new(bytes)).unwrap();
+
+        assert_eq!(loaded.modules, snapshot.modules);
+        assert_eq!(loaded.root("c_game_pp"), Some(0x1010));
+        assert_eq!(loaded.read::<u32>(0x1000), Some(0x0403_0201));
+        assert_eq!(loaded.read::<u16>(0x2000), Some(0x0605));
+        assert_eq!(loaded.read::<u16>(0x2001), None);
+    }
+
+    #[test]
+    fn read_rejects_length_past_end() {
+        let mut bytes = DUMP_MAGIC.to_vec();
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&0x1000u64.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 16]);
+
+        let err = Snapshot::read_from(&mut Cursor::new(bytes)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn insert_merges_overlapping_regions() {
+        let mut snapshot = Snapshot::default();
+        snapshot.insert(0x10, &[1; 4]);
+        snapshot.insert(0x20, &[2; 4]);
+        snapshot.insert(0x12, &[3; 0x10]);
+
+        assert_eq!(snapshot.regions().len(), 1);
+        assert_eq!(snapshot.regions()[0].base, 0x10);
+        assert_eq!(snapshot.total_bytes(), 0x14);
+        assert_eq!(snapshot.read::<u8>(0x11), Some(1));
+        assert_eq!(snapshot.read::<u8>(0x21), Some(3));
+        assert_eq!(snapshot.read::<u8>(0x23), Some(2));
+    }
+}
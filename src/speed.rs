@@ -0,0 +1,73 @@
+pub(crate) const SPEED_MIN: f32 = 0.1;
+pub(crate) const SPEED_MAX: f32 = 4.0;
+
+// 热键加速/减速时在这几档之间切换
+const SPEED_STEPS: [f32; 9] = [0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 4.0];
+
+pub(crate) fn step_speed(scale: f32, faster: bool) -> f32 {
+    let next = match faster {
+        true => SPEED_STEPS.iter().find(|&&val| val > scale + 0.001),
+        false => SPEED_STEPS.iter().rev().find(|&&val| val < scale - 0.001),
+    };
+
+    match next {
+        Some(val) => *val,
+        None => scale.clamp(SPEED_MIN, SPEED_MAX),
+    }
+}
+
+// 把真实的计数换成按倍数走的计数
+// 改倍数时从当前值接着走，返回值不会倒退，所以切回 1.0 时游戏里的时间不会跳
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SpeedClock {
+    scale: f64,
+    base_real: i64,
+    base_fake: i64,
+    last_real: i64,
+    last_fake: i64,
+    started: bool,
+}
+
+impl SpeedClock {
+    pub(crate) const fn new() -> Self {
+        Self {
+            scale: 1.0,
+            base_real: 0,
+            base_fake: 0,
+            last_real: 0,
+            last_fake: 0,
+            started: false,
+        }
+    }
+
+    pub(crate) fn scale(&self) -> f32 {
+        self.scale as f32
+    }
+
+    pub(crate) fn now(&mut self, real: i64) -> i64 {
+        if !self.started {
+            self.started = true;
+            self.base_real = real;
+            self.base_fake = real;
+            self.last_fake = real;
+        }
+
+        self.last_real = real;
+
+        let fake = self.base_fake + ((real - self.base_real) as f64 * self.scale) as i64;
+
+        // 多个线程拿到的真实计数先后不一定，不让结果往回走
+        self.last_fake = self.last_fake.max(fake);
+        self.last_fake
+    }
+
+    pub(crate) fn set_scale(&mut self, scale: f32) {
+        if self.started {
+            let fake = self.now(self.last_real);
+            self.base_real = self.last_real;
+            self.base_fake = fake;
+        }
+
+        self.scale = scale as f64;
+    }
+}
@@ -0,0 +1,37 @@
+use crate::math::{Vec3, direction};
+
+pub(crate) const TELEPORT_SLOTS: usize = 4;
+
+// 传送到实体旁边时和实体保持的水平距离
+const APPROACH_GAP: f32 = 1.0;
+
+// "x y z" 或 "x, y, z"
+pub(crate) fn parse_coord(text: &str) -> Option<Vec3<f32>> {
+    let parts: Vec<f32> = text
+        .split(|val: char| val == ',' || val.is_whitespace())
+        .filter(|val| !val.is_empty())
+        .map(|val| val.parse().ok())
+        .collect::<Option<_>>()?;
+
+    match parts[..] {
+        [x, y, z] => Some(Vec3::new(x, y, z)),
+        _ => None,
+    }
+}
+
+// 沿水平方向的 yaw 走 meters 米，高度不变
+pub(crate) fn step_forward(pos: Vec3<f32>, yaw: f32, meters: f32) -> Vec3<f32> {
+    pos + direction(yaw, 0.0) * meters
+}
+
+// 停在 to 前面一点，不和实体重叠
+pub(crate) fn approach(from: Vec3<f32>, to: Vec3<f32>) -> Vec3<f32> {
+    let mut delta = to - from;
+    delta.y = 0.0;
+
+    if delta.length() <= APPROACH_GAP {
+        return to;
+    }
+
+    to - delta.normalize() * APPROACH_GAP
+}
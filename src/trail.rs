@@ -0,0 +1,179 @@
+use crate::math::Vec3;
+use std::collections::BTreeMap;
+
+// 两点之间超过这个距离当作传送，不连线
+const JUMP_DISTANCE: f32 = 50.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct TrailPoint {
+    pub(crate) pos: Vec3<f32>,
+    // 和上一个点断开
+    pub(crate) gap: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Trail {
+    pub(crate) points: Vec<TrailPoint>,
+}
+
+impl Trail {
+    // 离上一个点至少 interval 米才记，超过 max_points 丢掉最老的
+    pub(crate) fn record(&mut self, pos: Vec3<f32>, interval: f32, max_points: usize) -> bool {
+        let gap = match self.points.last() {
+            Some(last) => {
+                let distance = (pos - last.pos).length();
+                if distance < interval {
+                    return false;
+                }
+
+                distance > JUMP_DISTANCE
+            }
+            None => false,
+        };
+
+        self.points.push(TrailPoint { pos, gap });
+
+        if self.points.len() > max_points {
+            let excess = self.points.len() - max_points;
+            self.points.drain(..excess);
+        }
+
+        true
+    }
+
+    // 连着的两点，第三个值是新旧程度，0.0 最老，1.0 最新
+    pub(crate) fn segments(&self) -> impl Iterator<Item = (Vec3<f32>, Vec3<f32>, f32)> + '_ {
+        let len = self.points.len();
+
+        self.points
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| !pair[1].gap)
+            .map(move |(index, pair)| {
+                let age = (index + 1) as f32 / (len - 1) as f32;
+                (pair[0].pos, pair[1].pos, age)
+            })
+    }
+
+    pub(crate) fn length(&self) -> f32 {
+        self.segments()
+            .map(|(from, to, _)| (to - from).length())
+            .sum()
+    }
+}
+
+// 老的一头是 old 色并且更透明
+pub(crate) fn fade_color(new: [f32; 4], old: [f32; 4], age: f32) -> [f32; 4] {
+    let age = age.clamp(0.0, 1.0);
+    let mix = |index: usize| old[index] + (new[index] - old[index]) * age;
+
+    [mix(0), mix(1), mix(2), mix(3) * (0.2 + 0.8 * age)]
+}
+
+// 按地图保存的路线，地图名见 world::level_name
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct SavedTrails {
+    pub(crate) maps: BTreeMap<String, Vec<(String, Trail)>>,
+}
+
+impl SavedTrails {
+    pub(crate) fn get(&self, map: &str) -> &[(String, Trail)] {
+        match self.maps.get(map) {
+            Some(val) => val,
+            None => &[],
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, map: &str) -> &mut Vec<(String, Trail)> {
+        self.maps.entry(map.to_string()).or_default()
+    }
+
+    // 格式:
+    //   map <地图名>
+    //   trail <名字>
+    //   <x> <y> <z>
+    //   -            断开，下一个点不和上一个连线
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let mut trails = Self::default();
+        let mut map: Option<String> = None;
+        let mut gap = false;
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |msg: &str| format!("第 {} 行: {}", line_index + 1, msg);
+
+            if let Some(name) = line.strip_prefix("map ") {
+                map = Some(name.trim().to_string());
+                continue;
+            }
+
+            let map = map.as_ref().ok_or_else(|| error("前面没有 map"))?;
+
+            if let Some(name) = line.strip_prefix("trail ") {
+                trails
+                    .get_mut(map)
+                    .push((name.trim().to_string(), Trail::default()));
+                gap = false;
+                continue;
+            }
+
+            let (_, trail) = trails
+                .get_mut(map)
+                .last_mut()
+                .ok_or_else(|| error("前面没有 trail"))?;
+
+            if line == "-" {
+                gap = true;
+                continue;
+            }
+
+            let coord: Vec<f32> = line
+                .split_whitespace()
+                .map(|val| val.parse().ok())
+                .collect::<Option<_>>()
+                .ok_or_else(|| error("坐标不是数字"))?;
+
+            let [x, y, z] = coord[..] else {
+                return Err(error("坐标要有 x y z 三个数"));
+            };
+
+            trail.points.push(TrailPoint {
+                pos: Vec3::new(x, y, z),
+                gap,
+            });
+            gap = false;
+        }
+
+        Ok(trails)
+    }
+
+    pub(crate) fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (map, trails) in &self.maps {
+            if trails.is_empty() {
+                continue;
+            }
+
+            text += &format!("map {}\n", map);
+
+            for (name, trail) in trails {
+                text += &format!("trail {}\n", name);
+
+                for point in &trail.points {
+                    if point.gap {
+                        text += "-\n";
+                    }
+
+                    text += &format!("{} {} {}\n", point.pos.x, point.pos.y, point.pos.z);
+                }
+            }
+        }
+
+        text
+    }
+}
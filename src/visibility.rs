@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+// 多少帧没见到的实体从表里删掉
+const FORGET_FRAMES: u64 = 600;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Visibility {
+    pub(crate) visible: bool,
+    // 逐骨骼检测时每个骨骼索引的结果
+    pub(crate) bones: Vec<(u8, bool)>,
+}
+
+impl Visibility {
+    // 没单独检测过的骨骼按整个实体算
+    pub(crate) fn bone(&self, index: u8) -> bool {
+        match self.bones.iter().find(|(val, _)| *val == index) {
+            Some((_, visible)) => *visible,
+            None => self.visible,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    visibility: Visibility,
+    checked: u64,
+    seen: u64,
+}
+
+// raytest_to_target 比较贵，结果按 ModelObject 缓存几帧，每帧最多做 budget 次检测
+// 预算不够时先检测等得最久的，不然排在数组前面的实体每帧都会把预算用完
+#[derive(Debug, Default, Clone)]
+pub(crate) struct VisibilityCache {
+    entries: HashMap<usize, Entry>,
+    // 该检测却没轮到的实体 -> (上次检测的帧, cost)，没检测过的是 0
+    waiting: HashMap<usize, (u64, u32)>,
+    // 这一帧给等待的实体留着的预算
+    reserved: HashMap<usize, u32>,
+    reserved_cost: u32,
+    frame: u64,
+    tests_left: u32,
+}
+
+impl VisibilityCache {
+    pub(crate) fn begin_frame(&mut self, budget: u32) {
+        self.frame += 1;
+        self.tests_left = budget;
+
+        let frame = self.frame;
+        self.entries
+            .retain(|_, entry| frame - entry.seen < FORGET_FRAMES);
+
+        let mut waiting: Vec<(usize, (u64, u32))> = self.waiting.drain().collect();
+        waiting.sort_by_key(|(addr, (checked, _))| (*checked, *addr));
+
+        self.reserved.clear();
+        self.reserved_cost = 0;
+
+        // 第一个总能留上，cost 比预算还大的实体也不会一直等下去
+        for (addr, (_, cost)) in waiting {
+            if !self.reserved.is_empty() && self.reserved_cost + cost > budget {
+                break;
+            }
+
+            self.reserved.insert(addr, cost);
+            self.reserved_cost += cost;
+        }
+    }
+
+    // cost 是 test 里要做的检测次数，预算用完时返回上次的结果，从没检测过就是 None
+    pub(crate) fn get(
+        &mut self,
+        model_obj_p: usize,
+        max_age: u64,
+        cost: u32,
+        test: impl FnOnce() -> Visibility,
+    ) -> Option<&Visibility> {
+        let frame = self.frame;
+
+        let (expired, checked) = match self.entries.get(&model_obj_p) {
+            Some(entry) => (frame - entry.checked > max_age, entry.checked),
+            None => (true, 0),
+        };
+
+        // 留给别人的预算不能动，剩下的只要没用完就让这个实体检测，一帧最多超出一个实体的 cost
+        let allowed = match self.reserved.remove(&model_obj_p) {
+            Some(reserved) => {
+                self.reserved_cost -= reserved;
+                self.tests_left > 0
+            }
+            None => self.tests_left > self.reserved_cost,
+        };
+
+        if expired && !allowed {
+            self.waiting.insert(model_obj_p, (checked, cost));
+        }
+
+        if expired && allowed {
+            self.tests_left = self.tests_left.saturating_sub(cost);
+            self.entries.insert(
+                model_obj_p,
+                Entry {
+                    visibility: test(),
+                    checked: frame,
+                    seen: frame,
+                },
+            );
+        }
+
+        let entry = self.entries.get_mut(&model_obj_p)?;
+        entry.seen = frame;
+
+        Some(&entry.visibility)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visible() -> Visibility {
+        Visibility {
+            visible: true,
+            bones: Vec::new(),
+        }
+    }
+
+    // 跑一帧，返回这一帧检测了哪些实体
+    fn run_frame(
+        cache: &mut VisibilityCache,
+        addrs: &[usize],
+        budget: u32,
+        max_age: u64,
+    ) -> Vec<usize> {
+        let mut tested = Vec::new();
+
+        cache.begin_frame(budget);
+        for addr in addrs {
+            cache.get(*addr, max_age, 1, || {
+                tested.push(*addr);
+                visible()
+            });
+        }
+
+        tested
+    }
+
+    #[test]
+    fn budget_rotates_through_all_entities() {
+        let mut cache = VisibilityCache::default();
+        let addrs: Vec<usize> = (1..=30).collect();
+
+        // max_age 0 时每帧都过期，预算只够三分之一，三帧内每个实体都要轮到一次
+        let mut tested = Vec::new();
+        for _ in 0..3 {
+            let frame = run_frame(&mut cache, &addrs, 10, 0);
+            assert_eq!(frame.len(), 10);
+            tested.extend(frame);
+        }
+
+        tested.sort_unstable();
+        assert_eq!(tested, addrs);
+
+        // 第四帧又从最早检测的那批开始
+        assert_eq!(
+            run_frame(&mut cache, &addrs, 10, 0),
+            (1..=10).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn waiting_entities_keep_priority_in_any_order() {
+        let mut cache = VisibilityCache::default();
+        let addrs: Vec<usize> = (1..=4).collect();
+
+        assert_eq!(run_frame(&mut cache, &addrs, 2, 0), vec![1, 2]);
+
+        // 数组顺序反过来也不能让 1 和 2 抢在等待的 3 和 4 前面
+        let reversed: Vec<usize> = addrs.iter().rev().copied().collect();
+        let mut frame = run_frame(&mut cache, &reversed, 2, 0);
+        frame.sort_unstable();
+        assert_eq!(frame, vec![3, 4]);
+    }
+
+    #[test]
+    fn fresh_results_are_not_tested_again() {
+        let mut cache = VisibilityCache::default();
+
+        assert_eq!(run_frame(&mut cache, &[1, 2], 10, 5), vec![1, 2]);
+        assert!(run_frame(&mut cache, &[1, 2], 10, 5).is_empty());
+
+        // 预算用完时返回上次的结果，从没检测过的是 None
+        cache.begin_frame(0);
+        assert_eq!(cache.get(1, 0, 1, visible).cloned(), Some(visible()));
+        assert_eq!(cache.get(3, 0, 1, visible), None);
+    }
+}
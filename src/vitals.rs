@@ -0,0 +1,192 @@
+use crate::{offsets::Offsets, pointer_scan::parse_hex, snapshot::Memory, world};
+
+// 数值所在的位置，偏移都是相对玩家的
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum VitalAddr {
+    // HealthModule + Offsets::health，和实体血量同一条路径
+    Health,
+    PlayerDI(usize),
+    HealthModule(usize),
+}
+
+impl std::fmt::Display for VitalAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VitalAddr::Health => write!(f, "HealthModule + Health"),
+            VitalAddr::PlayerDI(offset) => write!(f, "PlayerDI + {:#X}", offset),
+            VitalAddr::HealthModule(offset) => write!(f, "HealthModule + {:#X}", offset),
+        }
+    }
+}
+
+// 这一帧能不能写
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum WriteGate {
+    Allowed,
+    // 没开写入，解除锁定时还可以把原来的值写回去
+    Disabled,
+    // 联机或者读不到会话状态，什么都不能写
+    Blocked,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Vital {
+    pub(crate) name: String,
+    pub(crate) addr: VitalAddr,
+    pub(crate) lock: bool,
+    pub(crate) lock_value: f32,
+    // 锁定前的值，解除锁定时写回去
+    pub(crate) original: Option<f32>,
+}
+
+impl Vital {
+    pub(crate) fn new(name: &str, addr: VitalAddr) -> Self {
+        Self {
+            name: name.to_string(),
+            addr,
+            lock: false,
+            lock_value: 0.0,
+            original: None,
+        }
+    }
+
+    // 每帧调用，返回需要写入的值
+    // 解除锁定或者关掉写入时把原来的值写回去，联机时原来的值直接丢掉，不写
+    pub(crate) fn update(&mut self, current: f32, gate: WriteGate) -> Option<f32> {
+        match gate {
+            WriteGate::Blocked => {
+                self.original = None;
+                return None;
+            }
+            WriteGate::Disabled => return self.original.take(),
+            WriteGate::Allowed if !self.lock => return self.original.take(),
+            WriteGate::Allowed => (),
+        }
+
+        if self.original.is_none() {
+            self.original = Some(current);
+        }
+
+        if current != self.lock_value {
+            Some(self.lock_value)
+        } else {
+            None
+        }
+    }
+}
+
+// 数值的地址，读不到时返回 None
+pub(crate) fn resolve<M: Memory>(
+    mem: &M,
+    offsets: &Offsets,
+    player_di_p: usize,
+    addr: VitalAddr,
+) -> Option<usize> {
+    let val_p = match addr {
+        VitalAddr::Health => {
+            world::health_module(mem, offsets, player_di_p)? + offsets.health.value
+        }
+        VitalAddr::PlayerDI(offset) => player_di_p + offset,
+        VitalAddr::HealthModule(offset) => {
+            world::health_module(mem, offsets, player_di_p)? + offset
+        }
+    };
+
+    mem.read::<f32>(val_p)?;
+
+    Some(val_p)
+}
+
+// 血量以外的数值，每行: <名字> playerdi|healthmodule <十六进制偏移>
+pub(crate) fn parse(text: &str) -> Result<Vec<Vital>, String> {
+    let mut vitals = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |msg: &str| format!("第 {} 行: {}", line_index + 1, msg);
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let [name, base, offset] = parts[..] else {
+            return Err(error("格式是 <名字> playerdi|healthmodule <偏移>"));
+        };
+
+        let offset = parse_hex(offset).ok_or_else(|| error("偏移不是十六进制数"))?;
+
+        let addr = match base.to_ascii_lowercase().as_str() {
+            "playerdi" => VitalAddr::PlayerDI(offset),
+            "healthmodule" => VitalAddr::HealthModule(offset),
+            _ => return Err(error("只能相对 playerdi 或 healthmodule")),
+        };
+
+        vitals.push(Vital::new(name, addr));
+    }
+
+    Ok(vitals)
+}
+
+pub(crate) fn to_text(vitals: &[Vital]) -> String {
+    let mut text = String::new();
+
+    for vital in vitals {
+        let (base, offset) = match vital.addr {
+            VitalAddr::Health => continue,
+            VitalAddr::PlayerDI(offset) => ("playerdi", offset),
+            VitalAddr::HealthModule(offset) => ("healthmodule", offset),
+        };
+
+        text += &format!("{} {} {:#X}\n", vital.name, base, offset);
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked_at(value: f32) -> Vital {
+        let mut vital = Vital::new("血量", VitalAddr::Health);
+        vital.lock = true;
+        vital.lock_value = value;
+        vital
+    }
+
+    #[test]
+    fn unlock_restores_original() {
+        let mut vital = locked_at(100.0);
+
+        assert_eq!(vital.update(40.0, WriteGate::Allowed), Some(100.0));
+        assert_eq!(vital.update(100.0, WriteGate::Allowed), None);
+
+        vital.lock = false;
+        assert_eq!(vital.update(100.0, WriteGate::Allowed), Some(40.0));
+        assert_eq!(vital.update(40.0, WriteGate::Allowed), None);
+    }
+
+    #[test]
+    fn write_disabled_restores_original() {
+        let mut vital = locked_at(100.0);
+
+        vital.update(40.0, WriteGate::Allowed);
+        assert_eq!(vital.update(100.0, WriteGate::Disabled), Some(40.0));
+        assert_eq!(vital.update(40.0, WriteGate::Disabled), None);
+    }
+
+    #[test]
+    fn blocked_drops_original_without_writing() {
+        let mut vital = locked_at(100.0);
+
+        vital.update(40.0, WriteGate::Allowed);
+        assert_eq!(vital.update(100.0, WriteGate::Blocked), None);
+        assert_eq!(vital.original, None);
+
+        // 联机结束后解除锁定也不会把联机前的值写回去
+        vital.lock = false;
+        assert_eq!(vital.update(70.0, WriteGate::Allowed), None);
+        assert_eq!(vital.update(70.0, WriteGate::Disabled), None);
+    }
+}
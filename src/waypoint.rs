@@ -0,0 +1,124 @@
+use crate::{
+    draw::{Draw, Layer},
+    math::{Vec2, Vec3},
+};
+use std::collections::BTreeMap;
+
+const MARKER_SIZE: f32 = 6.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Waypoint {
+    pub(crate) name: String,
+    pub(crate) pos: Vec3<f32>,
+}
+
+// 按地图分组，地图名见 world::level_name
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Waypoints {
+    pub(crate) maps: BTreeMap<String, Vec<Waypoint>>,
+}
+
+impl Waypoints {
+    pub(crate) fn get(&self, map: &str) -> &[Waypoint] {
+        match self.maps.get(map) {
+            Some(val) => val,
+            None => &[],
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, map: &str) -> &mut Vec<Waypoint> {
+        self.maps.entry(map.to_string()).or_default()
+    }
+
+    // 格式:
+    //   map <地图名>
+    //   <x> <y> <z> <名字>
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let mut waypoints = Self::default();
+        let mut map: Option<String> = None;
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |msg: &str| format!("第 {} 行: {}", line_index + 1, msg);
+
+            if let Some(name) = line.strip_prefix("map ") {
+                map = Some(name.trim().to_string());
+                continue;
+            }
+
+            let map = map.as_ref().ok_or_else(|| error("前面没有 map"))?;
+
+            // 名字里可以有空格，前三段是坐标，剩下的都是名字
+            let mut rest = line;
+            let mut coord = [0.0; 3];
+            for val in coord.iter_mut() {
+                let (part, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                *val = part.parse().map_err(|_| error("坐标不是数字"))?;
+                rest = tail.trim_start();
+            }
+
+            let pos = Vec3::new(coord[0], coord[1], coord[2]);
+            let name = rest.trim_end().to_string();
+
+            waypoints.get_mut(map).push(Waypoint { name, pos });
+        }
+
+        Ok(waypoints)
+    }
+
+    pub(crate) fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (map, waypoints) in &self.maps {
+            if waypoints.is_empty() {
+                continue;
+            }
+
+            text += &format!("map {}\n", map);
+
+            for waypoint in waypoints {
+                text += &format!(
+                    "{} {} {} {}\n",
+                    waypoint.pos.x, waypoint.pos.y, waypoint.pos.z, waypoint.name
+                );
+            }
+        }
+
+        text
+    }
+}
+
+// 屏幕上的标记，屏幕外的用 esp::draw_offscreen_arrow
+pub(crate) fn draw_marker<D: Draw>(
+    draw: &mut D,
+    screen_pos: Vec2<f32>,
+    name: &str,
+    distance: f32,
+    color: [f32; 4],
+) {
+    let Vec2 { x, y } = screen_pos;
+
+    draw.polyline(
+        Layer::Background,
+        vec![
+            [x, y - MARKER_SIZE * 2.0],
+            [x + MARKER_SIZE, y - MARKER_SIZE],
+            [x, y],
+            [x - MARKER_SIZE, y - MARKER_SIZE],
+        ],
+        color,
+        2.0,
+        true,
+    );
+
+    draw.text(
+        Layer::Background,
+        [x + MARKER_SIZE + 2.0, y - MARKER_SIZE * 2.0],
+        color,
+        format!("{}  {:.1}", name, distance),
+    );
+}
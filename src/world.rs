@@ -0,0 +1,469 @@
+use crate::{
+    items::{ItemCategory, classify_item},
+    math::{Vec2, Vec3},
+    offsets::Offsets,
+    snapshot::Memory,
+};
+use std::ptr::null;
+
+#[repr(C)]
+pub(crate) struct CGame;
+
+#[repr(C)]
+pub(crate) struct GameDI;
+
+#[repr(C)]
+pub(crate) struct ModelObject;
+
+#[repr(C)]
+pub(crate) struct CModelObject;
+
+#[repr(C)]
+pub(crate) struct SessionCooperativeDI;
+
+#[repr(C)]
+pub(crate) struct LevelDI;
+
+#[repr(C)]
+pub(crate) struct CLevel;
+
+#[repr(C)]
+pub(crate) struct LocalClientDI;
+
+#[repr(C)]
+pub(crate) struct PlayerDI;
+
+#[repr(C)]
+pub(crate) struct CameraManagerDI;
+
+#[repr(C)]
+pub(crate) struct CameraFPPDI;
+
+// 值和 plugins/trainer_plugin.h 里的 model_type 一致，不能改
+#[repr(u32)]
+#[derive(Default, PartialEq, Debug, Clone, Copy)]
+pub(crate) enum ModelType {
+    ZombieNormal = 0,
+    ZombieSpecial = 1,
+    ZombieHunter = 2,
+    SurvivorNormal = 3,
+    SurvivorSpecial = 4,
+    SurvivorShopkeeper = 5,
+    PlayerHuman = 6,
+    PlayerHunter = 7,
+    #[default]
+    Other = 8,
+}
+pub(crate) const MODEL_TYPES: [ModelType; 9] = [
+    ModelType::ZombieNormal,
+    ModelType::ZombieSpecial,
+    ModelType::ZombieHunter,
+    ModelType::SurvivorNormal,
+    ModelType::SurvivorSpecial,
+    ModelType::SurvivorShopkeeper,
+    ModelType::PlayerHuman,
+    ModelType::PlayerHunter,
+    ModelType::Other,
+];
+
+impl ModelType {
+    // 按枚举名查找，骨架文件里用
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        MODEL_TYPES
+            .into_iter()
+            .find(|val| format!("{:?}", val) == name)
+    }
+}
+
+impl std::fmt::Display for ModelType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelType::ZombieNormal => write!(f, "丧尸"),
+            ModelType::ZombieSpecial => write!(f, "特感"),
+            ModelType::ZombieHunter => write!(f, "夜魔"),
+            ModelType::SurvivorNormal => write!(f, "NPC"),
+            ModelType::SurvivorShopkeeper => write!(f, "商贩"),
+            ModelType::SurvivorSpecial => write!(f, "强盗"),
+            ModelType::PlayerHuman => write!(f, "人类"),
+            ModelType::PlayerHunter => write!(f, "猎手"),
+            ModelType::Other => write!(f, "其他"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct World {
+    pub(crate) game_p: *const CGame,
+    pub(crate) game_di_p: *const GameDI,
+    pub(crate) session_cooperative_di_p: *const SessionCooperativeDI,
+    pub(crate) level_di_p: *const LevelDI,
+    pub(crate) c_level_p: *const CLevel,
+    pub(crate) local_client_di_p: *const LocalClientDI,
+    pub(crate) player_di_p: *mut PlayerDI,
+    pub(crate) player_c_model_obj_p: *const CModelObject,
+    pub(crate) camera_manage_di_p: *const CameraManagerDI,
+    pub(crate) camera_fpp_di_p: *const CameraFPPDI,
+    pub(crate) player_world_pos_p: *mut Vec3<f32>,
+    pub(crate) camera_angle_p: *mut Vec2<f32>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Obj {
+    pub(crate) model_obj_p: *const ModelObject,
+    pub(crate) c_model_obj_p: *const CModelObject,
+    pub(crate) c_model_obj_logo_p: *const u32,
+    pub(crate) c_model_obj_world_pos: Vec3<f32>,
+    pub(crate) model_obj_health_p: *mut f32,
+    pub(crate) model_obj_str_p: *const i8,
+    pub(crate) model_obj_str: String,
+    pub(crate) model_obj_type: ModelType,
+}
+
+// 没有血量的物件: 收集品、容器、书信、门等
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Item {
+    pub(crate) logo: u32,
+    pub(crate) world_pos: Vec3<f32>,
+    pub(crate) preset: String,
+    pub(crate) category: ItemCategory,
+}
+
+#[derive(Debug, Default)]
+#[repr(C)]
+pub(crate) struct Array<T> {
+    pub(crate) ptr: *const T,
+    pub(crate) len: u32,
+    pub(crate) max: u32,
+}
+
+unsafe impl Send for Array<*const ModelObject> {}
+unsafe impl Sync for Array<*const ModelObject> {}
+
+impl Array<*const ModelObject> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            ptr: null(),
+            len: 0,
+            max: 0,
+        }
+    }
+}
+
+// 读出 addr 处的指针，并且确认指针本身指向可读的内存
+fn deref<M: Memory>(mem: &M, addr: usize) -> Option<usize> {
+    let p = mem.read_ptr(addr)?;
+    if !mem.read_bytes(p, &mut [0u8; 8]) {
+        return None;
+    }
+
+    Some(p)
+}
+
+pub(crate) fn read_c_string<M: Memory>(mem: &M, addr: usize, max_len: usize) -> Option<String> {
+    let mut bytes = Vec::new();
+
+    while bytes.len() < max_len {
+        let cur = addr + bytes.len();
+
+        // 不跨页读取，同一页内要么都可读要么都不可读
+        let chunk_len = (0x1000 - cur % 0x1000).min(max_len - bytes.len()).min(64);
+
+        let mut chunk = [0u8; 64];
+        if !mem.read_bytes(cur, &mut chunk[..chunk_len]) {
+            return None;
+        }
+
+        if let Some(end) = chunk[..chunk_len].iter().position(|&b| b == 0) {
+            bytes.extend_from_slice(&chunk[..end]);
+            return Some(String::from_utf8_lossy(&bytes).to_string());
+        }
+
+        bytes.extend_from_slice(&chunk[..chunk_len]);
+    }
+
+    Some(String::from_utf8_lossy(&bytes).to_string())
+}
+
+pub(crate) fn resolve_world<M: Memory>(
+    mem: &M,
+    offsets: &Offsets,
+    c_game_pp: usize,
+) -> Option<World> {
+    let mut world = World::default();
+
+    // CGame
+    let game_p = mem.read_ptr(c_game_pp)?;
+    world.game_p = game_p as *const CGame;
+
+    // GameDI
+    let game_di_p = deref(mem, game_p + offsets.game_di.value)?;
+    world.game_di_p = game_di_p as *const GameDI;
+
+    // SessionCooperativeDI
+    let session_cooperative_di_p = deref(mem, game_di_p + offsets.session_cooperative_di.value)?;
+    world.session_cooperative_di_p = session_cooperative_di_p as *const SessionCooperativeDI;
+
+    // LevelDI
+    let level_di_p = deref(mem, session_cooperative_di_p + offsets.level_di.value)?;
+    world.level_di_p = level_di_p as *const LevelDI;
+
+    // CLevel
+    world.c_level_p = deref(mem, level_di_p + offsets.c_level.value)? as *const CLevel;
+
+    // LocalClientDI
+    let local_client_di_p = deref(
+        mem,
+        session_cooperative_di_p + offsets.local_client_di.value,
+    )?;
+    world.local_client_di_p = local_client_di_p as *const LocalClientDI;
+
+    // PlayerDI
+    let player_di_p = deref(mem, local_client_di_p + offsets.player_di.value)?;
+    world.player_di_p = player_di_p as *mut PlayerDI;
+
+    // PlayerPos
+    let player_world_pos_p = player_di_p + offsets.player_world_pos.value;
+    if mem.read::<Vec3<f32>>(player_world_pos_p)?.y == 0.0 {
+        return None;
+    }
+    world.player_world_pos_p = player_world_pos_p as *mut Vec3<f32>;
+
+    // Angle
+    let camera_angle_p = player_di_p + offsets.camera_angle.value;
+    mem.read::<Vec2<f32>>(camera_angle_p)?;
+    world.camera_angle_p = camera_angle_p as *mut Vec2<f32>;
+
+    // PlayerCModelObject
+    world.player_c_model_obj_p =
+        deref(mem, player_di_p - offsets.player_c_model_obj.value)? as *const CModelObject;
+
+    // CameraManagerDI
+    let camera_manage_di_p = deref(
+        mem,
+        session_cooperative_di_p + offsets.camera_manager_di.value,
+    )?;
+    world.camera_manage_di_p = camera_manage_di_p as *const CameraManagerDI;
+
+    // CameraFPPDI
+    world.camera_fpp_di_p =
+        deref(mem, camera_manage_di_p + offsets.camera_fpp_di.value)? as *const CameraFPPDI;
+
+    Some(world)
+}
+
+// 导出表里没有 ILevel 的地图名函数时才用 (见 impls::get_level_name)，没确认过
+// 在 CLevel 开头找第一个指向路径字符串的指针，比如 "levels/xxx/xxx.map"，找不到时返回 None
+pub(crate) fn level_name<M: Memory>(mem: &M, c_level_p: usize) -> Option<String> {
+    for offset in (0..0x400).step_by(8) {
+        let str_p = match deref(mem, c_level_p + offset) {
+            Some(val) => val,
+            None => continue,
+        };
+
+        let name = match read_c_string(mem, str_p, 128) {
+            Some(val) => val,
+            None => continue,
+        };
+
+        if name.len() >= 4
+            && name.chars().all(|val| val.is_ascii_graphic())
+            && (name.contains('/') || name.contains('\\'))
+        {
+            return Some(name);
+        }
+    }
+
+    None
+}
+
+// GetObjectsInFrustum 填充的数组里存的是 ModelObject 内部的指针
+pub(crate) fn model_obj_addrs<M: Memory>(
+    mem: &M,
+    offsets: &Offsets,
+    array: &Array<*const ModelObject>,
+) -> Vec<usize> {
+    let mut addrs = Vec::with_capacity(array.len as usize);
+
+    for index in 0..array.len as usize {
+        let model_obj_p = match deref(mem, array.ptr as usize + index * 8) {
+            Some(val) => val - offsets.model_obj_array_entry.value,
+            None => continue,
+        };
+
+        if !mem.read_bytes(model_obj_p, &mut [0u8; 8]) {
+            continue;
+        }
+
+        addrs.push(model_obj_p);
+    }
+
+    addrs
+}
+
+// 实体和 PlayerDI 都用同一个偏移找 HealthModule
+pub(crate) fn health_module<M: Memory>(mem: &M, offsets: &Offsets, base: usize) -> Option<usize> {
+    deref(mem, base + offsets.health_module.value)
+}
+
+pub(crate) fn decode_obj<M: Memory>(mem: &M, offsets: &Offsets, model_obj_p: usize) -> Option<Obj> {
+    let mut obj = Obj {
+        model_obj_p: model_obj_p as *const ModelObject,
+        ..Default::default()
+    };
+
+    // CModelObject
+    let c_model_obj_p = deref(mem, model_obj_p + offsets.c_model_obj.value)?;
+    obj.c_model_obj_p = c_model_obj_p as *const CModelObject;
+
+    // Logo
+    let c_model_obj_logo_p = c_model_obj_p + offsets.c_model_obj_logo.value;
+    let logo = mem.read::<u32>(c_model_obj_logo_p)?;
+    obj.c_model_obj_logo_p = c_model_obj_logo_p as *const u32;
+
+    // 0x1 AI Preset , Shape Box, PlayerFall 等等
+    // 0x2 可能是书信物件，也可能记错了
+    // 0x8 可能是可互动物件，也可能记错了
+    // 0x20 玩家: 人类和猎手
+    // 0x40 站着的僵尸
+    // 0x80 倒地的丧尸
+    // 0x2000 所有NPC，包括商人
+    // 0x40000 正在倒地的丧尸
+
+    match logo {
+        0x0 | 0x1 | 0x2 | 0x8 => return None,
+        _ => (),
+    }
+
+    obj.c_model_obj_world_pos = read_world_pos(mem, offsets, c_model_obj_p)?;
+
+    // ModelObjectHealth
+    let health_module_p = health_module(mem, offsets, model_obj_p)?;
+
+    let model_obj_health_p = health_module_p + offsets.health.value;
+    if mem.read::<f32>(model_obj_health_p)? == 0.0 {
+        return None;
+    }
+    obj.model_obj_health_p = model_obj_health_p as *mut f32;
+
+    // ModelObjectTypeData
+    let model_obj_str_p = deref(mem, c_model_obj_p + offsets.model_type_data.value)?;
+    obj.model_obj_str_p = model_obj_str_p as *const i8;
+
+    obj.model_obj_str = read_c_string(mem, model_obj_str_p, 256)?;
+
+    obj.model_obj_type = classify(&obj.model_obj_str, logo)?;
+
+    Some(obj)
+}
+
+// 世界坐标存在矩阵的最后一列里，读出来是 0 的当作无效
+fn read_world_pos<M: Memory>(
+    mem: &M,
+    offsets: &Offsets,
+    c_model_obj_p: usize,
+) -> Option<Vec3<f32>> {
+    let world_pos_p = c_model_obj_p + offsets.c_model_obj_world_pos.value;
+
+    let world_pos = Vec3 {
+        x: mem.read::<f32>(world_pos_p)?,
+        y: mem.read::<f32>(world_pos_p + 0x10)?,
+        z: mem.read::<f32>(world_pos_p + 0x20)?,
+    };
+
+    if world_pos.is_zero() {
+        return None;
+    }
+
+    Some(world_pos)
+}
+
+// decode_obj 丢掉的 0x1 0x2 0x8 物件，不需要 HealthModule
+pub(crate) fn decode_item<M: Memory>(
+    mem: &M,
+    offsets: &Offsets,
+    model_obj_p: usize,
+) -> Option<Item> {
+    let c_model_obj_p = deref(mem, model_obj_p + offsets.c_model_obj.value)?;
+
+    let logo = mem.read::<u32>(c_model_obj_p + offsets.c_model_obj_logo.value)?;
+    if !matches!(logo, 0x1 | 0x2 | 0x8) {
+        return None;
+    }
+
+    let world_pos = read_world_pos(mem, offsets, c_model_obj_p)?;
+
+    let preset_p = deref(mem, c_model_obj_p + offsets.model_type_data.value)?;
+    let preset = read_c_string(mem, preset_p, 256)?;
+
+    Some(Item {
+        logo,
+        world_pos,
+        category: classify_item(&preset, logo),
+        preset,
+    })
+}
+
+pub(crate) fn classify(model_obj_str: &str, logo: u32) -> Option<ModelType> {
+    let bytes = model_obj_str.as_bytes();
+
+    let start = bytes.iter().position(|&b| b == b';')? + 1;
+    if start >= bytes.len() {
+        return None;
+    }
+
+    let model_obj_type = match &bytes[start..] {
+        // b if b.starts_with(b"Nig")
+        //     || b.starts_with(b"Scr")
+        //     || b.starts_with(b"Gas")
+        //     || b.starts_with(b"Dem")
+        //     || b.starts_with(b"Goo")
+        //     || b.starts_with(b"Toa")
+        //     || b.starts_with(b"Bom")
+        //     // BTZ_Su BTZ_Bi
+        //     || b.starts_with(b"BTZ_Su") =>
+        // {
+        //     ModelType::ZombieSpecial
+        // }
+        b if b.starts_with(b"Bi") || b.starts_with(b"Vi") || b.starts_with(b"Dea") => {
+            ModelType::ZombieNormal
+        }
+
+        b if b.starts_with(b"Ni")
+            || b.starts_with(b"Sc")
+            || b.starts_with(b"Ga")
+            || b.starts_with(b"Dem")
+            || b.starts_with(b"Go")
+            || b.starts_with(b"To")
+            || b.starts_with(b"Bo")
+            // BTZ_Su BTZ_Bi
+            || b.starts_with(b"BT") =>
+        {
+            ModelType::ZombieSpecial
+        }
+
+        b if b.starts_with(b"Vo") => ModelType::ZombieHunter,
+
+        // DW_Zombie
+        b if b.starts_with(b"Zo") || b.starts_with(b"DW") => ModelType::PlayerHunter,
+
+        // enc很多是中立    Quest_GoodNight是友好NPC
+        b if b.starts_with(b"en") || b.starts_with(b"0T") => ModelType::SurvivorSpecial,
+
+        b if b.starts_with(b"Sh") || b.starts_with(b"Sp") => ModelType::SurvivorShopkeeper,
+
+        b if b.starts_with(b"Pl") => ModelType::PlayerHuman,
+
+        // 塔楼上面的坐在桌子面前操作的机械工
+        // 泽雷博士车门前躺着的马里克
+        // b if b.starts_with(b"Hub") || b.starts_with(b"Maa") => ModelType::SurvivorNormal,
+        _ => {
+            if logo == 0x2000 {
+                ModelType::SurvivorNormal
+            } else {
+                ModelType::Other
+            }
+        }
+    };
+
+    Some(model_obj_type)
+}